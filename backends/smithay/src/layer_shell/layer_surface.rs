@@ -5,14 +5,17 @@ use crate::{
         touch::{Position, TouchEvent, TouchPoint},
     },
     layer_shell::layer_window::LayerWindowMessage,
-    new_raw_wayland_handle, WindowEvent, WindowInfo, WindowMessage, WindowOptions,
+    new_raw_wayland_handle,
+    shm::ShmBuffers,
+    WindowEvent, WindowInfo, WindowMessage, WindowOptions,
 };
 use ahash::AHashMap;
 use anyhow::Context;
+use mctk_core::types::AABB;
 use smithay_client_toolkit::{
     compositor::{CompositorHandler, CompositorState},
     delegate_compositor, delegate_keyboard, delegate_layer, delegate_output, delegate_pointer,
-    delegate_registry, delegate_seat, delegate_touch,
+    delegate_registry, delegate_seat, delegate_shm, delegate_touch,
     output::{OutputHandler, OutputState},
     reexports::{
         calloop::{
@@ -49,6 +52,7 @@ use smithay_client_toolkit::{
         wlr_layer::{self, LayerShell, LayerShellHandler, LayerSurface},
         WaylandSurface,
     },
+    shm::{Shm, ShmHandler},
 };
 use wayland_client::{
     globals::BindError,
@@ -82,6 +86,8 @@ pub struct LayerShellSctkWindow {
     text_input_manager: Option<ZwpTextInputManagerV3>,
     text_input: Option<ZwpTextInputV3>,
     pub scale_factor: f32,
+    shm: Shm,
+    shm_buffers: Option<ShmBuffers>,
     exit: bool,
 }
 
@@ -121,6 +127,7 @@ impl LayerShellSctkWindow {
             height,
             width,
             scale_factor,
+            ..
         } = window_opts;
         let LayerOptions {
             anchor,
@@ -142,6 +149,7 @@ impl LayerShellSctkWindow {
 
         let compositor = CompositorState::bind(&globals, &queue_handle)
             .context("wl_compositor not availible")?;
+        let shm = Shm::bind(&globals, &queue_handle).context("wl_shm not availible")?;
 
         let layer_shell =
             LayerShell::bind(&globals, &queue_handle).context("layer shell not availible")?;
@@ -197,6 +205,8 @@ impl LayerShellSctkWindow {
             touch_map: AHashMap::new(),
             initial_configure_sent: false,
             scale_factor,
+            shm,
+            shm_buffers: None,
             exit: false,
             // gl_context,
             // gl_surface,
@@ -293,6 +303,21 @@ impl LayerShellSctkWindow {
         layer.wl_surface().frame(qh, layer.wl_surface().clone());
         layer.commit();
     }
+
+    /// Present a frame drawn by the software renderer, through `wl_shm`.
+    pub fn present(&mut self, width: u32, height: u32, data: &[u8], damage: &[AABB]) {
+        let surface = self.layer.wl_surface();
+        let Some(buffers) =
+            ShmBuffers::get_or_create(&mut self.shm_buffers, &self.shm, width, height)
+        else {
+            return;
+        };
+        buffers.present(surface, width, height, self.scale_factor, data, damage);
+        // presented on the next frame callback instead
+        if buffers.has_dropped_frame() {
+            self.next_frame();
+        }
+    }
 }
 
 impl CompositorHandler for LayerShellSctkWindow {
@@ -316,6 +341,12 @@ impl CompositorHandler for LayerShellSctkWindow {
         if self.layer.wl_surface() != surface {
             return;
         }
+        if let Some(buffers) = self.shm_buffers.as_mut() {
+            buffers.present_dropped(surface);
+            if buffers.has_dropped_frame() {
+                self.next_frame();
+            }
+        }
 
         let _ = self.send_compositor_frame();
     }
@@ -704,6 +735,12 @@ impl TouchHandler for LayerShellSctkWindow {
     }
 }
 
+impl ShmHandler for LayerShellSctkWindow {
+    fn shm_state(&mut self) -> &mut Shm {
+        &mut self.shm
+    }
+}
+
 impl ProvidesRegistryState for LayerShellSctkWindow {
     fn registry(&mut self) -> &mut RegistryState {
         &mut self.registry_state
@@ -743,4 +780,5 @@ delegate_keyboard!(LayerShellSctkWindow);
 delegate_pointer!(LayerShellSctkWindow);
 delegate_touch!(LayerShellSctkWindow);
 delegate_layer!(LayerShellSctkWindow);
+delegate_shm!(LayerShellSctkWindow);
 delegate_registry!(LayerShellSctkWindow);
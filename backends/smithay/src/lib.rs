@@ -1,6 +1,7 @@
 pub mod input;
 pub mod layer_shell;
 pub mod session_lock;
+pub mod shm;
 pub mod xdg_shell;
 
 use input::keyboard::KeyboardEvent;
@@ -8,6 +9,8 @@ use input::pointer::MouseEvent;
 use input::touch::TouchEvent;
 use mctk_core::component;
 use mctk_core::raw_handle::RawWaylandHandle;
+use mctk_core::renderer::RendererType;
+use mctk_core::types::AABB;
 use raw_window_handle::{
     RawDisplayHandle, RawWindowHandle, WaylandDisplayHandle, WaylandWindowHandle,
 };
@@ -25,6 +28,8 @@ pub struct WindowOptions {
     pub height: u32,
     pub width: u32,
     pub scale_factor: f32,
+    /// The renderer to draw with. Falls back to [`RendererType::Software`] if EGL cannot be initialized.
+    pub renderer: RendererType,
 }
 
 #[derive(Default, Clone)]
@@ -48,6 +53,12 @@ pub enum WindowMessage {
     MainEventsCleared,
     RedrawRequested,
     RequestNextFrame,
+    Present {
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+        damage: Vec<AABB>,
+    },
     Resize {
         width: u32,
         height: u32,
@@ -4,18 +4,20 @@ use crate::{
     input::touch::{Position, TouchEvent, TouchPoint},
     new_raw_wayland_handle,
     session_lock::lock_window::SessionLockMessage,
+    shm::ShmBuffers,
     WindowEvent, WindowMessage, WindowOptions,
 };
 use ahash::AHashMap;
 use anyhow::Context;
 use mctk_core::raw_handle::RawWaylandHandle;
+use mctk_core::types::AABB;
 use raw_window_handle::{
     RawDisplayHandle, RawWindowHandle, WaylandDisplayHandle, WaylandWindowHandle,
 };
 use smithay_client_toolkit::{
     compositor::{CompositorHandler, CompositorState},
     delegate_compositor, delegate_keyboard, delegate_output, delegate_pointer, delegate_registry,
-    delegate_seat, delegate_touch,
+    delegate_seat, delegate_shm, delegate_touch,
     output::{OutputHandler, OutputState},
     reexports::{
         calloop::{
@@ -49,6 +51,7 @@ use smithay_client_toolkit::{
         touch::TouchHandler,
         Capability, SeatHandler, SeatState,
     },
+    shm::{Shm, ShmHandler},
 };
 use wayland_client::{
     protocol::{
@@ -74,6 +77,8 @@ pub struct SessionLockSctkWindow {
     initial_configure_sent: bool,
     pub wayland_handle: RawWaylandHandle,
     pub scale_factor: f32,
+    shm: Shm,
+    shm_buffers: Option<ShmBuffers>,
     wl_display: WlDisplay,
     wl_surface: WlSurface,
     touch: Option<wl_touch::WlTouch>,
@@ -98,6 +103,7 @@ impl SessionLockSctkWindow {
             height,
             width,
             scale_factor,
+            ..
         } = window_opts;
 
         let (globals, event_queue) =
@@ -113,6 +119,7 @@ impl SessionLockSctkWindow {
         let output_state = OutputState::new(&globals, &queue_handle);
         let compositor = CompositorState::bind(&globals, &queue_handle)
             .context("wl_compositor not availible")?;
+        let shm = Shm::bind(&globals, &queue_handle).context("wl_shm not availible")?;
         let session_lock_manager = globals
             .bind::<ExtSessionLockManagerV1, _, _>(
                 &queue_handle,
@@ -180,6 +187,8 @@ impl SessionLockSctkWindow {
             initial_configure_sent: false,
             wayland_handle,
             scale_factor,
+            shm,
+            shm_buffers: None,
             // session_lock_manager: session_lock_manager,
             session_lock: session_lock,
             // session_lock_surface: session_lock_surface,
@@ -241,6 +250,21 @@ impl SessionLockSctkWindow {
         self.wl_surface.frame(qh, self.wl_surface.clone());
         self.wl_surface.commit();
     }
+
+    /// Present a frame drawn by the software renderer, through `wl_shm`.
+    pub fn present(&mut self, width: u32, height: u32, data: &[u8], damage: &[AABB]) {
+        let surface = &self.wl_surface;
+        let Some(buffers) =
+            ShmBuffers::get_or_create(&mut self.shm_buffers, &self.shm, width, height)
+        else {
+            return;
+        };
+        buffers.present(surface, width, height, self.scale_factor, data, damage);
+        // presented on the next frame callback instead
+        if buffers.has_dropped_frame() {
+            self.next_frame();
+        }
+    }
 }
 
 impl CompositorHandler for SessionLockSctkWindow {
@@ -264,6 +288,12 @@ impl CompositorHandler for SessionLockSctkWindow {
         if &self.wl_surface != surface {
             return;
         }
+        if let Some(buffers) = self.shm_buffers.as_mut() {
+            buffers.present_dropped(surface);
+            if buffers.has_dropped_frame() {
+                self.next_frame();
+            }
+        }
         let _ = self.send_compositor_frame();
     }
 
@@ -619,6 +649,12 @@ impl TouchHandler for SessionLockSctkWindow {
     }
 }
 
+impl ShmHandler for SessionLockSctkWindow {
+    fn shm_state(&mut self) -> &mut Shm {
+        &mut self.shm
+    }
+}
+
 impl ProvidesRegistryState for SessionLockSctkWindow {
     fn registry(&mut self) -> &mut RegistryState {
         &mut self.registry_state
@@ -633,6 +669,7 @@ delegate_seat!(SessionLockSctkWindow);
 delegate_keyboard!(SessionLockSctkWindow);
 delegate_pointer!(SessionLockSctkWindow);
 delegate_touch!(SessionLockSctkWindow);
+delegate_shm!(SessionLockSctkWindow);
 delegate_registry!(SessionLockSctkWindow);
 
 /* Session Lock binds */
@@ -0,0 +1,218 @@
+use mctk_core::types::AABB;
+use smithay_client_toolkit::{
+    reexports::client::protocol::{wl_shm, wl_surface::WlSurface},
+    shm::{
+        slot::{Buffer, SlotPool},
+        Shm,
+    },
+};
+
+/// Number of buffers kept alive per surface, one being shown while the other is drawn into.
+const BUFFER_COUNT: usize = 2;
+
+/// Presents frames drawn by the software renderer through double-buffered `wl_shm` buffers.
+///
+/// A buffer is only reused once the compositor has released it; if both buffers are still held
+/// by the compositor, the frame is kept until [`ShmBuffers::present_dropped`] is called, which
+/// surfaces do on their next frame callback. The damage of dropped frames is reported along with
+/// that of the next frame that is presented.
+pub struct ShmBuffers {
+    pool: SlotPool,
+    buffers: Vec<Buffer>,
+    width: u32,
+    height: u32,
+    /// Damage that has not been reported to the compositor yet.
+    pending_damage: Vec<AABB>,
+    /// Whether the whole buffer has to be reported as damaged.
+    pending_full_damage: bool,
+    /// The last frame, if it couldn't be presented yet.
+    dropped_frame: Option<DroppedFrame>,
+}
+
+/// A frame that is waiting for a buffer to be released.
+struct DroppedFrame {
+    width: u32,
+    height: u32,
+    scale_factor: f32,
+    data: Vec<u8>,
+}
+
+impl ShmBuffers {
+    pub fn new(shm: &Shm, width: u32, height: u32) -> anyhow::Result<Self> {
+        let pool = SlotPool::new((width * height * 4) as usize * BUFFER_COUNT, shm)?;
+
+        Ok(Self {
+            pool,
+            buffers: Vec::with_capacity(BUFFER_COUNT),
+            width,
+            height,
+            pending_damage: vec![],
+            pending_full_damage: true,
+            dropped_frame: None,
+        })
+    }
+
+    /// The buffers in `buffers`, which are created for frames of the given physical size if there are none yet.
+    /// Returns `None` if they can't be created.
+    pub fn get_or_create<'a>(
+        buffers: &'a mut Option<Self>,
+        shm: &Shm,
+        width: u32,
+        height: u32,
+    ) -> Option<&'a mut Self> {
+        if buffers.is_none() {
+            match Self::new(shm, width, height) {
+                Ok(new) => *buffers = Some(new),
+                Err(e) => tracing::error!("Error while creating shm pool {:?}", e),
+            }
+        }
+        buffers.as_mut()
+    }
+
+    /// Copy the premultiplied RGBA8 `data` into a free buffer, attach it to `surface` and commit.
+    /// Only `damage`, and that of any frames that were dropped before it, is reported to the compositor
+    /// as changed, unless the buffer is new. An empty `damage` means that everything changed.
+    /// The frame has the physical `width` and `height` of a surface with the given `scale_factor`.
+    ///
+    /// If both buffers are still held by the compositor, the frame is kept instead, until it is
+    /// presented by [`ShmBuffers::present_dropped`] or replaced by a newer one.
+    pub fn present(
+        &mut self,
+        surface: &WlSurface,
+        width: u32,
+        height: u32,
+        scale_factor: f32,
+        data: &[u8],
+        damage: &[AABB],
+    ) {
+        // kept until a frame is presented, so that the damage of dropped frames isn't lost
+        if damage.is_empty() {
+            self.pending_full_damage = true;
+        } else {
+            self.pending_damage.extend_from_slice(damage);
+        }
+
+        self.dropped_frame = None;
+        if data.len() < (width * height * 4) as usize {
+            return;
+        }
+        if !self.attach(surface, width, height, scale_factor, data) {
+            self.dropped_frame = Some(DroppedFrame {
+                width,
+                height,
+                scale_factor,
+                data: data.to_vec(),
+            });
+        }
+    }
+
+    /// Whether a frame was dropped because both buffers were held by the compositor, and is waiting
+    /// for [`ShmBuffers::present_dropped`].
+    pub fn has_dropped_frame(&self) -> bool {
+        self.dropped_frame.is_some()
+    }
+
+    /// Present the last frame that was dropped, if any, e.g. once the compositor asks for the next frame.
+    /// It is kept again if both buffers are still held by the compositor.
+    pub fn present_dropped(&mut self, surface: &WlSurface) {
+        if let Some(frame) = self.dropped_frame.take() {
+            if !self.attach(
+                surface,
+                frame.width,
+                frame.height,
+                frame.scale_factor,
+                &frame.data,
+            ) {
+                self.dropped_frame = Some(frame);
+            }
+        }
+    }
+
+    /// Attach a free buffer with `data` to `surface`, along with the pending damage, and commit.
+    /// Returns `false` if there is no free buffer.
+    fn attach(
+        &mut self,
+        surface: &WlSurface,
+        width: u32,
+        height: u32,
+        scale_factor: f32,
+        data: &[u8],
+    ) -> bool {
+        // buffers of the old size are released on drop
+        if width != self.width || height != self.height {
+            self.buffers.clear();
+            self.width = width;
+            self.height = height;
+        }
+
+        let mut new_buffer = false;
+        let pool = &mut self.pool;
+        let free = self
+            .buffers
+            .iter()
+            .position(|buffer| buffer.canvas(pool).is_some());
+
+        let index = match free {
+            Some(index) => index,
+            None if self.buffers.len() < BUFFER_COUNT => {
+                let stride = width as i32 * 4;
+                match pool.create_buffer(
+                    width as i32,
+                    height as i32,
+                    stride,
+                    wl_shm::Format::Argb8888,
+                ) {
+                    Ok((buffer, _)) => {
+                        self.buffers.push(buffer);
+                        new_buffer = true;
+                        self.buffers.len() - 1
+                    }
+                    Err(e) => {
+                        tracing::error!("Error while creating shm buffer {:?}", e);
+                        return true;
+                    }
+                }
+            }
+            // both buffers are still held by the compositor
+            None => return false,
+        };
+
+        let buffer = &self.buffers[index];
+        let Some(canvas) = buffer.canvas(pool) else {
+            return false;
+        };
+
+        // RGBA -> little endian ARGB8888, which is BGRA in memory
+        for (dst, src) in canvas.chunks_exact_mut(4).zip(data.chunks_exact(4)) {
+            dst[0] = src[2];
+            dst[1] = src[1];
+            dst[2] = src[0];
+            dst[3] = src[3];
+        }
+
+        // otherwise the compositor takes the buffer to be of the logical size
+        surface.set_buffer_scale(scale_factor.round().max(1.) as i32);
+        if let Err(e) = buffer.attach_to(surface) {
+            tracing::error!("Error while attaching shm buffer {:?}", e);
+            return true;
+        }
+        // the compositor has never seen the contents of a new buffer
+        if new_buffer || self.pending_full_damage {
+            surface.damage_buffer(0, 0, width as i32, height as i32);
+        } else {
+            for rect in &self.pending_damage {
+                surface.damage_buffer(
+                    rect.pos.x as i32,
+                    rect.pos.y as i32,
+                    rect.width().ceil() as i32,
+                    rect.height().ceil() as i32,
+                );
+            }
+        }
+        surface.commit();
+        self.pending_damage.clear();
+        self.pending_full_damage = false;
+
+        true
+    }
+}
@@ -4,16 +4,19 @@ use crate::{
         pointer::{convert_button, MouseEvent, Point, ScrollDelta},
         touch::{Position, TouchEvent, TouchPoint},
     },
-    new_raw_wayland_handle, WindowEvent, WindowInfo, WindowMessage, WindowOptions,
+    new_raw_wayland_handle,
+    shm::ShmBuffers,
+    WindowEvent, WindowInfo, WindowMessage, WindowOptions,
 };
 use ahash::AHashMap;
 use anyhow::Context;
+use mctk_core::types::AABB;
 use smithay_client_toolkit::{
     activation::{ActivationHandler, ActivationState, RequestData},
     compositor::{CompositorHandler, CompositorState},
     delegate_activation, delegate_compositor, delegate_keyboard, delegate_layer, delegate_output,
-    delegate_pointer, delegate_registry, delegate_seat, delegate_touch, delegate_xdg_shell,
-    delegate_xdg_window,
+    delegate_pointer, delegate_registry, delegate_seat, delegate_shm, delegate_touch,
+    delegate_xdg_shell, delegate_xdg_window,
     output::{OutputHandler, OutputState},
     reexports::{
         calloop::{
@@ -56,6 +59,7 @@ use smithay_client_toolkit::{
         },
         WaylandSurface,
     },
+    shm::{Shm, ShmHandler},
 };
 use wayland_client::{
     protocol::{
@@ -89,6 +93,8 @@ pub struct XdgShellSctkWindow {
     touch_map: AHashMap<i32, TouchPoint>,
     initial_configure_sent: bool,
     pub scale_factor: f32,
+    shm: Shm,
+    shm_buffers: Option<ShmBuffers>,
 }
 
 impl XdgShellSctkWindow {
@@ -105,6 +111,7 @@ impl XdgShellSctkWindow {
             height,
             width,
             scale_factor,
+            ..
         } = window_opts;
         let WindowInfo { id, title, .. } = window_info;
 
@@ -120,6 +127,7 @@ impl XdgShellSctkWindow {
 
         let compositor = CompositorState::bind(&globals, &queue_handle)
             .context("wl_compositor not availible")?;
+        let shm = Shm::bind(&globals, &queue_handle).context("wl_shm not availible")?;
 
         let xdg_shell =
             XdgShell::bind(&globals, &queue_handle).context("layer shell not availible")?;
@@ -183,6 +191,8 @@ impl XdgShellSctkWindow {
             touch_map: AHashMap::new(),
             initial_configure_sent: false,
             scale_factor,
+            shm,
+            shm_buffers: None,
         };
 
         if let Ok(text_input_manager) = state
@@ -264,6 +274,21 @@ impl XdgShellSctkWindow {
         self.xdg_window.commit();
     }
 
+    /// Present a frame drawn by the software renderer, through `wl_shm`.
+    pub fn present(&mut self, width: u32, height: u32, data: &[u8], damage: &[AABB]) {
+        let surface = self.xdg_window.wl_surface();
+        let Some(buffers) =
+            ShmBuffers::get_or_create(&mut self.shm_buffers, &self.shm, width, height)
+        else {
+            return;
+        };
+        buffers.present(surface, width, height, self.scale_factor, data, damage);
+        // presented on the next frame callback instead
+        if buffers.has_dropped_frame() {
+            self.next_frame();
+        }
+    }
+
     pub fn close(&mut self) {
         self.is_exited = true;
     }
@@ -290,6 +315,12 @@ impl CompositorHandler for XdgShellSctkWindow {
         if self.xdg_window.wl_surface() != surface {
             return;
         }
+        if let Some(buffers) = self.shm_buffers.as_mut() {
+            buffers.present_dropped(surface);
+            if buffers.has_dropped_frame() {
+                self.next_frame();
+            }
+        }
         let _ = self.send_compositor_frame();
     }
 
@@ -682,6 +713,12 @@ impl TouchHandler for XdgShellSctkWindow {
     }
 }
 
+impl ShmHandler for XdgShellSctkWindow {
+    fn shm_state(&mut self) -> &mut Shm {
+        &mut self.shm
+    }
+}
+
 impl ProvidesRegistryState for XdgShellSctkWindow {
     fn registry(&mut self) -> &mut RegistryState {
         &mut self.registry_state
@@ -723,4 +760,5 @@ delegate_touch!(XdgShellSctkWindow);
 delegate_xdg_shell!(XdgShellSctkWindow);
 delegate_xdg_window!(XdgShellSctkWindow);
 delegate_activation!(XdgShellSctkWindow);
+delegate_shm!(XdgShellSctkWindow);
 delegate_registry!(XdgShellSctkWindow);
@@ -2,8 +2,10 @@ use mctk_core::component::{self, Component, RootComponent};
 use mctk_core::input::{Button, Input, Motion, MouseButton, TouchAction};
 use mctk_core::raw_handle::RawWaylandHandle;
 use mctk_core::reexports::cosmic_text;
+use mctk_core::renderer::RendererType;
 use mctk_core::types::AssetParams;
 use mctk_core::types::PixelSize;
+use mctk_core::types::AABB;
 use mctk_core::ui::UI;
 use pointer::{MouseEvent, ScrollDelta};
 use raw_window_handle::{
@@ -27,6 +29,7 @@ pub struct XdgWindow {
     width: u32,
     height: u32,
     scale_factor: f32,
+    renderer_type: RendererType,
     handle: Option<RawWaylandHandle>,
     window_tx: Sender<WindowMessage>,
     fonts: cosmic_text::fontdb::Database,
@@ -74,6 +77,7 @@ impl XdgWindow {
             xdg_window_rx,
         } = params;
 
+        let renderer_type = window_opts.renderer;
         let (window_tx, window_rx) = calloop::channel::channel();
 
         let (app_window, event_loop) =
@@ -90,6 +94,7 @@ impl XdgWindow {
                 height: app_window.height,
                 handle: None,
                 scale_factor: app_window.scale_factor,
+                renderer_type,
                 window_tx: window_tx.clone(),
                 fonts,
                 assets,
@@ -140,6 +145,14 @@ impl XdgWindow {
                             WindowMessage::RequestNextFrame => {
                                 app_window.next_frame();
                             }
+                            WindowMessage::Present {
+                                width,
+                                height,
+                                data,
+                                damage,
+                            } => {
+                                app_window.present(width, height, &data, &damage);
+                            }
                             WindowMessage::CompositorFrame => {
                                 ui.handle_input(&Input::Timer);
                             }
@@ -305,6 +318,23 @@ impl mctk_core::window::Window for XdgWindow {
         let _ = self.window_tx.send(WindowMessage::RequestNextFrame);
     }
 
+    fn renderer_type(&self) -> RendererType {
+        self.renderer_type
+    }
+
+    fn set_renderer_type(&mut self, renderer_type: RendererType) {
+        self.renderer_type = renderer_type;
+    }
+
+    fn present(&self, width: u32, height: u32, data: &[u8], damage: &[AABB]) {
+        let _ = self.window_tx.send(WindowMessage::Present {
+            width,
+            height,
+            data: data.to_vec(),
+            damage: damage.to_vec(),
+        });
+    }
+
     fn fonts(&self) -> cosmic_text::fontdb::Database {
         self.fonts.clone()
     }
@@ -0,0 +1,318 @@
+//! A registry of images that are added at runtime, rather than through [`Window::assets`][crate::window::Window#tymethod.assets].
+//!
+//! Registered images are decoded one after the other on a background thread, and can be drawn by name like any other asset once they have loaded, e.g. with [`widgets::Image`][crate::widgets::Image].
+//! The renderers evict the least recently drawn of them from the GPU when they take up more than [`gpu_budget`], and upload them again when they are next drawn.
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+
+use image::DynamicImage;
+
+use crate::types::{AssetParams, ImgFilter};
+
+/// Whether a registered image can be drawn yet.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AssetState {
+    Loading,
+    Loaded,
+    /// The image could not be read or decoded, for the given reason.
+    Failed(String),
+}
+
+struct Entry {
+    state: AssetState,
+    image: Option<Arc<DynamicImage>>,
+    version: u64,
+}
+
+static VERSION: AtomicU64 = AtomicU64::new(1);
+static GPU_BUDGET: AtomicUsize = AtomicUsize::new(128 * 1024 * 1024);
+
+fn registry() -> MutexGuard<'static, HashMap<String, Entry>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, Entry>>> = OnceLock::new();
+    REGISTRY
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap()
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Run `job` on the thread that decodes images, once the jobs that were queued before it are done.
+/// A single thread is used, so that registering many images at once doesn't start as many threads.
+pub(crate) fn decode_in_background<F>(job: F)
+where
+    F: 'static + FnOnce() + Send,
+{
+    static QUEUE: OnceLock<Mutex<Sender<Job>>> = OnceLock::new();
+    let queue = QUEUE.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        std::thread::Builder::new()
+            .name("mctk-decoder".to_string())
+            .spawn(move || {
+                for job in receiver {
+                    // Decoders can panic on malformed files, which shouldn't stop the images after it from loading
+                    let _ = std::panic::catch_unwind(AssertUnwindSafe(job));
+                }
+            })
+            .unwrap();
+        Mutex::new(sender)
+    });
+    queue.lock().unwrap().send(Box::new(job)).unwrap();
+}
+
+fn register_with<F>(name: String, load: F)
+where
+    F: 'static + FnOnce() -> image::ImageResult<DynamicImage> + Send,
+{
+    let version = VERSION.fetch_add(1, Ordering::Relaxed);
+    registry().insert(
+        name.clone(),
+        Entry {
+            state: AssetState::Loading,
+            image: None,
+            version,
+        },
+    );
+
+    decode_in_background(move || {
+        let is_current = |registry: &HashMap<String, Entry>| {
+            registry.get(&name).map_or(false, |e| e.version == version)
+        };
+        // The name may have been registered again, or unregistered, while this was queued
+        if !is_current(&registry()) {
+            return;
+        }
+        let result = load();
+        let mut registry = registry();
+        // Or while it was decoded
+        if !is_current(&registry) {
+            return;
+        }
+        let entry = registry.get_mut(&name).unwrap();
+        match result {
+            Ok(image) => {
+                entry.image = Some(Arc::new(image));
+                entry.state = AssetState::Loaded;
+            }
+            Err(e) => {
+                println!("Error while loading image {:?} error: {:?}", name, e);
+                entry.state = AssetState::Failed(e.to_string());
+            }
+        }
+    });
+}
+
+/// Load the image file described by `params` under `name`, replacing any image that was registered under it before.
+pub fn register<S: Into<String>>(name: S, params: AssetParams) {
+    register_with(name.into(), move || {
+        let AssetParams { path, filter, blur } = params;
+        let mut image = image::open(path)?;
+        if let Some(sigma) = blur {
+            image = image.blur(sigma);
+        }
+        if let ImgFilter::GRAY = filter {
+            image = DynamicImage::ImageRgb8(image.grayscale().into_rgb8());
+        }
+        Ok(image)
+    });
+}
+
+/// Decode an encoded image, e.g. a PNG that was downloaded, under `name`, replacing any image that was registered under it before.
+pub fn register_bytes<S: Into<String>>(name: S, bytes: Vec<u8>) {
+    register_with(name.into(), move || image::load_from_memory(&bytes));
+}
+
+/// Forget the image registered under `name`.
+pub fn unregister(name: &str) {
+    registry().remove(name);
+}
+
+/// The state of the image registered under `name`, if any.
+pub fn state(name: &str) -> Option<AssetState> {
+    registry().get(name).map(|e| e.state.clone())
+}
+
+/// How many bytes of GPU memory the images that are loaded while drawing may take up, before the least recently drawn ones are evicted.
+pub fn gpu_budget() -> usize {
+    GPU_BUDGET.load(Ordering::Relaxed)
+}
+
+/// Set the [`gpu_budget`]. Defaults to 128 MiB.
+pub fn set_gpu_budget(bytes: usize) {
+    GPU_BUDGET.store(bytes, Ordering::Relaxed);
+}
+
+/// The decoded image registered under `name`, once it has loaded, along with the version of its registration.
+pub(crate) fn image(name: &str) -> Option<(Arc<DynamicImage>, u64)> {
+    let registry = registry();
+    let entry = registry.get(name)?;
+    Some((entry.image.clone()?, entry.version))
+}
+
+struct RuntimeImage<T> {
+    image: T,
+    version: u64,
+    bytes: usize,
+    last_used: u64,
+}
+
+/// The images that a renderer loaded while drawing, e.g. registered ones, as opposed to the ones it was created with.
+/// `T` is how the renderer stores an image.
+pub(crate) struct RuntimeImages<T> {
+    images: HashMap<String, RuntimeImage<T>>,
+    bytes: usize,
+    frame: u64,
+    evicted: Vec<T>,
+}
+
+impl<T> RuntimeImages<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            images: HashMap::new(),
+            bytes: 0,
+            frame: 0,
+            evicted: vec![],
+        }
+    }
+
+    /// Called at the start of every frame. Images drawn in the current frame are never evicted.
+    pub(crate) fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// The image loaded under `name`, unless it is older than `version`.
+    pub(crate) fn get(&mut self, name: &str, version: u64) -> Option<&T> {
+        let image = self
+            .images
+            .get_mut(name)
+            .filter(|image| image.version >= version)?;
+        image.last_used = self.frame;
+        Some(&image.image)
+    }
+
+    /// Add an image that takes up `bytes`, then evict the least recently used images until they fit within [`gpu_budget`].
+    pub(crate) fn insert(&mut self, name: &str, version: u64, image: T, bytes: usize) {
+        if let Some(old) = self.images.insert(
+            name.to_string(),
+            RuntimeImage {
+                image,
+                version,
+                bytes,
+                last_used: self.frame,
+            },
+        ) {
+            self.bytes -= old.bytes;
+            self.evicted.push(old.image);
+        }
+        self.bytes += bytes;
+        self.evict_to(gpu_budget());
+    }
+
+    /// Evict the least recently used images until they take up no more than `budget` bytes, or only images used in the current frame are left.
+    fn evict_to(&mut self, budget: usize) {
+        while self.bytes > budget {
+            let Some(name) = self
+                .images
+                .iter()
+                .filter(|(_, image)| image.last_used < self.frame)
+                .min_by_key(|(_, image)| image.last_used)
+                .map(|(name, _)| name.clone())
+            else {
+                break;
+            };
+            let image = self.images.remove(&name).unwrap();
+            self.bytes -= image.bytes;
+            self.evicted.push(image.image);
+        }
+    }
+
+    /// The images that were evicted since this was last called. They must be freed by the renderer.
+    pub(crate) fn take_evicted(&mut self) -> Vec<T> {
+        std::mem::take(&mut self.evicted)
+    }
+
+    /// Forget every image, without evicting them, e.g. when the canvas they were loaded into is gone.
+    pub(crate) fn clear(&mut self) {
+        self.images.clear();
+        self.bytes = 0;
+        self.evicted.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::TestHarness;
+    use crate::widgets;
+    use crate::{lay, node, size};
+
+    #[test]
+    fn test_runtime_images() {
+        let mut images = RuntimeImages::new();
+        images.insert("a", 0, 1, 10);
+        images.insert("b", 0, 2, 10);
+        images.next_frame();
+        images.insert("c", 0, 3, 10);
+        assert_eq!(images.get("a", 0), Some(&1));
+        images.next_frame();
+        assert_eq!(images.get("c", 0), Some(&3));
+        // A newer registration has to be loaded again
+        assert_eq!(images.get("c", 1), None);
+
+        // Least recently used first, but never what was used in this frame
+        images.evict_to(15);
+        assert_eq!(images.take_evicted(), vec![2, 1]);
+        images.evict_to(0);
+        assert!(images.take_evicted().is_empty());
+        assert_eq!(images.get("c", 0), Some(&3));
+
+        images.insert("c", 1, 4, 10);
+        assert_eq!(images.take_evicted(), vec![3]);
+        assert_eq!(images.get("c", 1), Some(&4));
+    }
+
+    #[test]
+    fn test_asset_registry() {
+        use image::{ImageOutputFormat, Rgba, RgbaImage};
+
+        let wait_for = |name: &str| {
+            for _ in 0..500 {
+                match state(name) {
+                    Some(AssetState::Loading) => {
+                        std::thread::sleep(std::time::Duration::from_millis(5))
+                    }
+                    state => return state,
+                }
+            }
+            None
+        };
+
+        let mut png = vec![];
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 2, Rgba([0, 255, 0, 255])))
+            .write_to(&mut std::io::Cursor::new(&mut png), ImageOutputFormat::Png)
+            .unwrap();
+        register_bytes("test_registry", png);
+        register_bytes("test_registry_broken", vec![1, 2, 3]);
+        assert!(matches!(
+            wait_for("test_registry_broken"),
+            Some(AssetState::Failed(_))
+        ));
+
+        let mut harness = TestHarness::single(|| {
+            node!(widgets::Image::new("test_registry"), lay![size: [50., 50.]])
+        });
+        harness.render();
+        assert_eq!(wait_for("test_registry"), Some(AssetState::Loaded));
+        // The Image notices that its asset loaded on the next tick
+        harness.tick();
+        let pixel = harness.render().pixmap().pixel(25, 25).unwrap();
+        assert_eq!((pixel.red(), pixel.green(), pixel.alpha()), (0, 255, 255));
+
+        unregister("test_registry");
+        unregister("test_registry_broken");
+        assert_eq!(state("test_registry"), None);
+    }
+}
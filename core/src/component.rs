@@ -198,6 +198,24 @@ pub trait Component: fmt::Debug {
         aabb
     }
 
+    /// Return `true` to render this Node and its children into an offscreen layer, which is composited on every frame and only rendered again once the combined render hash of the subtree changes -- i.e. when anything in it is re-rendered, moves, or resizes.
+    ///
+    /// Worthwhile for large or expensive subtrees that rarely change. Layers nested inside of a layer are flattened into it.
+    ///
+    /// By default this returns `false`.
+    fn cache_layer(&self) -> bool {
+        false
+    }
+
+    /// Return a [`Clip`] to keep everything that the children of this Node draw -- including images and text -- within a shape, e.g. rounded corners. The Node itself is not clipped.
+    ///
+    /// The children are drawn into an offscreen layer to clip them, like with [`cache_layer`][Component#method.cache_layer]. Include the clip in the [`render_hash`][Component#method.render_hash].
+    ///
+    /// By default this returns `None`.
+    fn clip(&self) -> Option<Clip> {
+        None
+    }
+
     /// Specifies spacing between its children
     fn spacing(&self) -> Scale {
         Scale::new(0.0, 0.0)
@@ -0,0 +1,268 @@
+//! Tracking of the regions of a window that changed between two frames.
+//!
+//! After each draw, the new Node graph is compared against the previous one: every Node whose
+//! [`render_hash`][crate::Component#method.render_hash] or [`AABB`] changed, was added or was removed damages
+//! both its old and new area. Renderers then only repaint the damaged regions, and report them to the compositor.
+
+use crate::node::Node;
+use crate::renderables::backdrop::Instance;
+use crate::renderables::Renderable;
+use crate::types::*;
+
+/// Past this many rects, the damage is collapsed into its bounding box.
+const MAX_DAMAGE_RECTS: usize = 16;
+
+/// Damage is grown by this many physical pixels, to cover anti-aliasing and strokes that straddle a Node's edge.
+const DAMAGE_MARGIN: f32 = 2.0;
+
+/// The regions of a frame that need to be repainted, in physical pixels.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Damage {
+    rects: Vec<AABB>,
+    full: bool,
+}
+
+impl Damage {
+    /// Damage covering the whole frame.
+    pub fn full() -> Self {
+        Self {
+            rects: vec![],
+            full: true,
+        }
+    }
+
+    /// Does the whole frame need to be repainted?
+    pub fn is_full(&self) -> bool {
+        self.full
+    }
+
+    /// Is there nothing to repaint?
+    pub fn is_empty(&self) -> bool {
+        !self.full && self.rects.is_empty()
+    }
+
+    /// The damaged rects. Full damage only has rects once it has been [`clip`][Damage#method.clip]ped to the frame.
+    pub fn rects(&self) -> &[AABB] {
+        &self.rects
+    }
+
+    /// The smallest AABB that covers all of the damaged rects.
+    pub fn bounds(&self) -> Option<AABB> {
+        self.rects.iter().copied().reduce(|a, b| a.union(&b))
+    }
+
+    /// Mark `aabb` as damaged. Overlapping rects are merged.
+    pub fn add(&mut self, aabb: AABB) {
+        if self.full || aabb.is_empty() {
+            return;
+        }
+
+        let mut aabb = aabb;
+        // Merging can make the rect overlap others that it did not before
+        while let Some(i) = self.rects.iter().position(|r| r.intersects(&aabb)) {
+            aabb = aabb.union(&self.rects.swap_remove(i));
+        }
+        self.rects.push(aabb);
+
+        if self.rects.len() > MAX_DAMAGE_RECTS {
+            let bounds = self.bounds().unwrap();
+            self.rects = vec![bounds];
+        }
+    }
+
+    /// Add all of `other` to `self`.
+    pub fn merge(&mut self, other: &Damage) {
+        if other.full {
+            *self = Self::full();
+            return;
+        }
+        for r in other.rects.iter() {
+            self.add(*r);
+        }
+    }
+
+    /// Restrict the damage to `bounds`, typically the size of the frame.
+    /// Full damage is turned into `bounds`.
+    pub fn clip(&self, bounds: AABB) -> Self {
+        if self.full {
+            return Self {
+                rects: vec![bounds],
+                full: true,
+            };
+        }
+        Self {
+            rects: self
+                .rects
+                .iter()
+                .filter_map(|r| r.intersection(&bounds))
+                .map(|r| r.round())
+                .collect(),
+            full: false,
+        }
+    }
+}
+
+impl Node {
+    /// Add the regions where `self` looks different from `prev`, the graph it replaces, to `damage`.
+    /// Children are matched by key, like in [`view`][Node#method.view].
+    pub(crate) fn damage(&self, prev: &Node, damage: &mut Damage) {
+        if (self.render_hash != prev.render_hash
+            || self.aabb != prev.aabb
+            || self.render_transform != prev.render_transform)
+            && (self.render_cache.is_some() || prev.render_cache.is_some())
+        {
+            damage.add(prev.drawn_aabb().expand(DAMAGE_MARGIN));
+            damage.add(self.drawn_aabb().expand(DAMAGE_MARGIN));
+        }
+
+        // The whole subtree is blended with the opacity
+        if self.opacity != prev.opacity {
+            for node in [prev, self] {
+                let area = node.aabb.expand(node.overflow).union(&node.inclusive_aabb);
+                damage.add(node.transformed(area).expand(DAMAGE_MARGIN));
+            }
+        }
+
+        for child in self.children.iter() {
+            match prev.children.iter().find(|c| c.key == child.key) {
+                Some(prev_child) => child.damage(prev_child, damage),
+                None => child.damage_all(damage),
+            }
+        }
+
+        for prev_child in prev.children.iter() {
+            if !self.children.iter().any(|c| c.key == prev_child.key) {
+                prev_child.damage_all(damage);
+            }
+        }
+    }
+
+    /// Backdrops blur what is beneath them, so they are repainted whole whenever any of that is damaged.
+    pub(crate) fn damage_backdrops(&self, damage: &mut Damage) {
+        if damage.full {
+            return;
+        }
+        for renderable in self.render_cache.iter().flatten() {
+            if let Renderable::Backdrop(backdrop) = renderable {
+                let Instance { pos, scale, .. } = backdrop.instance_data;
+                let area = self.transformed(AABB::new(pos, scale));
+                let reach = area.expand(backdrop.margin());
+                if damage.rects.iter().any(|r| r.intersects(&reach)) {
+                    damage.add(area.expand(DAMAGE_MARGIN));
+                }
+            }
+        }
+        for child in self.children.iter() {
+            child.damage_backdrops(damage);
+        }
+    }
+
+    /// Add everything that `self` and its children draw to `damage`.
+    fn damage_all(&self, damage: &mut Damage) {
+        if self.render_cache.is_some() {
+            damage.add(self.drawn_aabb().expand(DAMAGE_MARGIN));
+        }
+        for child in self.children.iter() {
+            child.damage_all(damage);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::TestHarness;
+    use crate::layout::Direction;
+    use crate::snapshot::{canvas_to_image, compare, snapshot};
+    use crate::widgets::{Div, Toggle};
+    use crate::{lay, node, size};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn aabb(x: f32, y: f32, w: f32, h: f32) -> AABB {
+        AABB::new(Pos::new(x, y, 0.0), Scale::new(w, h))
+    }
+
+    #[test]
+    fn test_add_merges_overlapping() {
+        let mut damage = Damage::default();
+        damage.add(aabb(0.0, 0.0, 10.0, 10.0));
+        damage.add(aabb(20.0, 0.0, 10.0, 10.0));
+        assert_eq!(damage.rects().len(), 2);
+
+        // Overlaps both, so all three are merged
+        damage.add(aabb(5.0, 5.0, 20.0, 2.0));
+        assert_eq!(damage.rects(), &[aabb(0.0, 0.0, 30.0, 10.0)]);
+
+        damage.add(aabb(0.0, 0.0, 0.0, 10.0));
+        assert_eq!(damage.rects().len(), 1);
+    }
+
+    #[test]
+    fn test_clip() {
+        let mut damage = Damage::default();
+        damage.add(aabb(-5.0, -5.0, 10.0, 10.0));
+        damage.add(aabb(200.0, 200.0, 10.0, 10.0));
+
+        let clipped = damage.clip(aabb(0.0, 0.0, 100.0, 100.0));
+        assert_eq!(clipped.rects(), &[aabb(0.0, 0.0, 5.0, 5.0)]);
+
+        let full = Damage::full().clip(aabb(0.0, 0.0, 100.0, 100.0));
+        assert!(full.is_full());
+        assert_eq!(full.rects(), &[aabb(0.0, 0.0, 100.0, 100.0)]);
+    }
+
+    #[test]
+    fn test_partial_repaint() {
+        static BLUE: AtomicBool = AtomicBool::new(false);
+        let mut harness = TestHarness::new(200, 100, || {
+            let color = if BLUE.load(Ordering::Relaxed) {
+                Color::BLUE
+            } else {
+                Color::RED
+            };
+            node!(
+                Div::new(),
+                lay![size: [200., 100.], direction: Direction::Row]
+            )
+            .push(node!(Div::new().bg(Color::RED), lay![size: [50., 50.]], 0))
+            .push(node!(Div::new().bg(color), lay![size: [50., 50.]], 1))
+        });
+        harness.render();
+        assert_eq!(harness.last_damage(), vec![aabb(0.0, 0.0, 200.0, 100.0)]);
+
+        // Nothing changed, so nothing is presented
+        harness.update(Box::new(()));
+        harness.render();
+        assert_eq!(harness.last_damage(), vec![]);
+
+        // Only the Div that changed is repainted, with a margin, clipped to the frame
+        BLUE.store(true, Ordering::Relaxed);
+        harness.update(Box::new(()));
+        harness.render();
+        assert_eq!(
+            harness.last_damage(),
+            vec![aabb(50.0, 0.0, 50.0, 50.0)
+                .expand(DAMAGE_MARGIN)
+                .intersection(&aabb(0.0, 0.0, 200.0, 100.0))
+                .unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_partial_repaint_matches_full_repaint() {
+        let mut harness = TestHarness::new(200, 100, || node!(Toggle::new(false)));
+        harness.render();
+        harness.click(10.0, 10.0);
+        let toggle = harness.root().children()[0]
+            .aabb()
+            .expand(DAMAGE_MARGIN + 1.0);
+        let image = canvas_to_image(harness.render());
+        let damage = harness.last_damage();
+        assert!(!damage.is_empty());
+        assert!(damage.iter().all(|r| toggle.union(r) == toggle));
+
+        // Repainting only the damage gives the same frame as painting everything
+        let expected = snapshot(200, 100, 1.0, || node!(Toggle::new(true)));
+        assert_eq!(compare(&image, &expected, 2).unwrap().differing_pixels, 0);
+    }
+}
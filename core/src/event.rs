@@ -18,6 +18,8 @@ pub const DRAG_THRESHOLD: f32 = 15.0; // px
 ///
 /// Note that this is longer than [`DRAG_THRESHOLD`].
 pub const DRAG_CLICK_MAX_DIST: f32 = 30.0; // px
+/// How much time (ms) a touch has to be held in place before it is considered a long press.
+pub const LONG_PRESS_MS: u128 = 500; // ms
 
 /// The contextual data that is sent to a [`Component`][crate::Component]'s `on_EVENT` methods.
 pub struct Event<T: EventInput> {
@@ -40,6 +42,7 @@ pub struct Event<T: EventInput> {
     pub(crate) scale_factor: f32,
     pub(crate) messages: Vec<Message>,
     pub(crate) registrations: Vec<crate::node::Registration>,
+    pub(crate) clipboard: Option<Data>,
 }
 
 impl<T: EventInput> std::fmt::Debug for Event<T> {
@@ -382,6 +385,7 @@ impl<T: EventInput> Event<T> {
             scale_factor: event_cache.scale_factor,
             messages: vec![],
             registrations: vec![],
+            clipboard: None,
         }
     }
 
@@ -412,6 +416,11 @@ impl<T: EventInput> Event<T> {
         self.messages.push(msg);
     }
 
+    /// Put the [`Data`] on the clipboard of the [`Window`][crate::window::Window], once this Event has been handled.
+    pub fn put_on_clipboard(&mut self, data: Data) {
+        self.clipboard = Some(data);
+    }
+
     /// Return the [`AABB`] of the current Node, in physical coordinates.
     pub fn current_physical_aabb(&self) -> AABB {
         self.current_aabb.unwrap()
@@ -2,12 +2,12 @@ use cosmic_text::fontdb::Database;
 use cosmic_text::{Buffer, FontSystem, LayoutGlyph, Metrics};
 use femtovg::Align;
 use std::hash::{Hash, Hasher};
-use std::ops::Deref;
+use std::ops::{Deref, Range};
 
 use crate::renderables::text::{self, InstanceBuilder};
 use crate::renderer::text::TextRenderer;
-use crate::style::HorizontalPosition;
-use crate::{Pos, Scale};
+use crate::style::{FontWeight, HorizontalPosition};
+use crate::{Color, Pos, Scale, AABB};
 
 /// Value by which fonts are scaled. 12 px fonts render at scale 18 px for some reason. Useful if you need to compute the line height: it will be `<font_size> * SIZE_SCALE` in logical size, and `<font_size> * SIZE_SCALE * <scale_factor>` in physical pixels.
 pub const SIZE_SCALE: f32 = 1.5;
@@ -65,19 +65,99 @@ impl FontCache {
 
         text_renderer.measure_text(text_instance)
     }
+
+    /// Measure the text of the given `Instance`, in the physical pixels that it is given in.
+    /// Text is wrapped and cut off at the width of its `scale`.
+    pub fn measure_instance(
+        &mut self,
+        instance: text::Instance,
+    ) -> (Option<f32>, Option<f32>, Vec<LayoutGlyph>) {
+        self.text_renderer.measure_text(instance)
+    }
+
+    /// The box of every glyph of the text of the given `Instance`, relative to its `pos`, with the byte range of the text that it shows.
+    /// Boxes are as high as the line they are on.
+    pub fn glyph_boxes(&mut self, instance: text::Instance) -> Vec<(AABB, Range<usize>)> {
+        self.text_renderer.glyph_boxes(instance)
+    }
+
+    /// The boxes that the glyphs in each of the given byte `ranges` of the text of the given `Instance` take up,
+    /// relative to its `pos`. A range that is broken over several lines has a box on each of them.
+    pub fn measure_ranges(
+        &mut self,
+        instance: text::Instance,
+        ranges: &[Range<usize>],
+    ) -> Vec<Vec<AABB>> {
+        self.text_renderer.measure_ranges(instance, ranges)
+    }
 }
 
 /// Used by [`FontCache#layout_text`][FontCache#method.layout_text] as an input. Accordingly, it is also commonly used as the input to Components that display text, e.g. [`widgets::Text`][crate::widgets::Text] and [`widgets::Button`][crate::widgets::Button].
 ///
 /// [`txt`][crate::txt] is provided as a convenient constructor, but you can also use `into` from a `&str` or `String`, e.g. `"some text".into()`.
-#[derive(Debug, Clone)]
+///
+/// Each segment of a [`widgets::Text`][crate::widgets::Text] is a span that can be drawn with its own color, weight, style, font and background,
+/// and that can be a link. Attributes that are not given are those of the `Text`.
+#[derive(Debug, Clone, Default)]
 pub struct TextSegment {
     /// The text to be laid out.
     pub text: String,
     /// An optional size. A default will be selected if `None`.
+    /// Lines can only have one size, so the first segment of a `Text` that has one sets it for all of them.
     pub size: Option<f32>,
     /// An optional font name. A default will be selected if `None`.
     pub font: Option<String>,
+    pub color: Option<Color>,
+    pub weight: Option<FontWeight>,
+    pub italic: bool,
+    /// A color that is drawn behind the text, to highlight it.
+    pub background: Option<Color>,
+    /// A link target, e.g. a URL, that is passed to [`on_link`][crate::widgets::Text::on_link] when the segment is clicked.
+    pub link: Option<String>,
+}
+
+impl TextSegment {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn size(mut self, size: f32) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn font(mut self, font: impl Into<String>) -> Self {
+        self.font = Some(font.into());
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn weight(mut self, weight: FontWeight) -> Self {
+        self.weight = Some(weight);
+        self
+    }
+
+    pub fn italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+
+    pub fn background(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    pub fn link(mut self, target: impl Into<String>) -> Self {
+        self.link = Some(target.into());
+        self
+    }
 }
 
 impl From<&str> for TextSegment {
@@ -88,11 +168,7 @@ impl From<&str> for TextSegment {
 
 impl From<String> for TextSegment {
     fn from(text: String) -> TextSegment {
-        TextSegment {
-            text,
-            size: None,
-            font: None,
-        }
+        TextSegment::new(text)
     }
 }
 
@@ -112,6 +188,7 @@ impl From<crate::open_iconic::Icon> for TextSegment {
 /// - `("text", None, 12.0)`: A text string and a font size.
 ///
 /// If no font name or size is given, defaults are assumed.
+/// Any [`TextSegment`] can also be given, e.g. `TextSegment::new("here").link("https://example.com")`.
 ///
 /// This lets you mix different text styles, e.g.:
 /// ```
@@ -166,19 +243,20 @@ macro_rules! txt {
     (@as_txt_seg  ($text:expr, None, $size:expr)) => { $crate::font_cache::TextSegment {
         text: $text.into(),
         size: Some($size),
-        font: None,
+        ..Default::default()
     } };
 
     (@as_txt_seg  ($text:expr, $font:expr, $size:expr)) => { $crate::font_cache::TextSegment {
         text: $text.into(),
         size: Some($size),
         font: Some($font.into()),
+        ..Default::default()
     } };
 
     (@as_txt_seg  ($text:expr, $font:expr)) => { $crate::font_cache::TextSegment {
         text: $text.into(),
-        size: None,
         font: Some($font.into()),
+        ..Default::default()
     } };
 
     (@as_txt_seg  $e:expr) => {
@@ -199,5 +277,10 @@ impl Hash for TextSegment {
         self.size.map(|s| (s * 100.0) as u32).hash(state);
         self.font.hash(state);
         self.text.hash(state);
+        self.color.hash(state);
+        self.weight.hash(state);
+        self.italic.hash(state);
+        self.background.hash(state);
+        self.link.hash(state);
     }
 }
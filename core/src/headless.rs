@@ -0,0 +1,433 @@
+//! A [`Window`] without a display, and a [`TestHarness`] that drives a [`UI`] with it.
+//!
+//! The harness runs view, layout and render synchronously on the calling thread, so tests can inject
+//! [`Input`]s and then inspect the resulting Node graph, focus, emitted messages and rendered frame.
+//!
+//!```ignore
+//! let mut harness = TestHarness::new(100, 100, || {
+//!     node!(Button::new(txt!("Ok")).on_click(Box::new(|| msg!(Clicked))), [size: [100, 100]])
+//! });
+//! harness.click(50.0, 50.0);
+//! assert!(harness.messages()[0].downcast_ref::<Clicked>().is_some());
+//!```
+
+use crate::component::{Component, Message, RootComponent};
+use crate::input::{Button, Input, Motion, MouseButton, TouchAction};
+use crate::node::Node;
+use crate::raw_handle::RawWaylandHandle;
+use crate::renderer::software::{SoftwareCanvas, SoftwareCanvasContext};
+use crate::renderer::RendererType;
+use crate::types::{Data, PixelSize, AABB};
+use crate::ui::UI;
+use crate::window::Window;
+use crate::AssetParams;
+use mctk_macros::{component, state_component_impl};
+use raw_window_handle::{
+    HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
+    WaylandDisplayHandle, WaylandWindowHandle,
+};
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, RwLockReadGuard};
+
+/// A [`Window`] that is never shown. It always uses the [`RendererType::Software`] renderer, and keeps
+/// the last presented frame and the clipboard contents in memory.
+pub struct HeadlessWindow {
+    width: u32,
+    height: u32,
+    scale_factor: f32,
+    fonts: cosmic_text::fontdb::Database,
+    assets: HashMap<String, AssetParams>,
+    svgs: HashMap<String, String>,
+    clipboard: Mutex<Option<Data>>,
+    frame: Mutex<Option<(u32, u32, Vec<u8>)>>,
+    damage: Mutex<Vec<AABB>>,
+}
+
+impl HeadlessWindow {
+    /// Create a window of the given logical size, with a scale factor of 1.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            scale_factor: 1.0,
+            fonts: cosmic_text::fontdb::Database::new(),
+            assets: HashMap::new(),
+            svgs: HashMap::new(),
+            clipboard: Mutex::new(None),
+            frame: Mutex::new(None),
+            damage: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn scale_factor(mut self, scale_factor: f32) -> Self {
+        self.scale_factor = scale_factor;
+        self
+    }
+
+    pub fn fonts(mut self, fonts: cosmic_text::fontdb::Database) -> Self {
+        self.fonts = fonts;
+        self
+    }
+
+    pub fn assets(mut self, assets: HashMap<String, AssetParams>) -> Self {
+        self.assets = assets;
+        self
+    }
+
+    pub fn svgs(mut self, svgs: HashMap<String, String>) -> Self {
+        self.svgs = svgs;
+        self
+    }
+
+    /// The last frame handed to [`Window#present`][Window#method.present], as `(width, height, rgba)`.
+    pub fn last_frame(&self) -> Option<(u32, u32, Vec<u8>)> {
+        self.frame.lock().unwrap().clone()
+    }
+
+    /// The damage that was presented with the last frame.
+    pub fn last_damage(&self) -> Vec<AABB> {
+        self.damage.lock().unwrap().clone()
+    }
+}
+
+impl fmt::Debug for HeadlessWindow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HeadlessWindow")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("scale_factor", &self.scale_factor)
+            .finish()
+    }
+}
+
+impl Window for HeadlessWindow {
+    fn logical_size(&self) -> PixelSize {
+        PixelSize {
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    fn physical_size(&self) -> PixelSize {
+        PixelSize {
+            width: (self.width as f32 * self.scale_factor).round() as u32,
+            height: (self.height as f32 * self.scale_factor).round() as u32,
+        }
+    }
+
+    fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    fn renderer_type(&self) -> RendererType {
+        RendererType::Software
+    }
+
+    fn present(&self, width: u32, height: u32, data: &[u8], damage: &[AABB]) {
+        *self.frame.lock().unwrap() = Some((width, height, data.to_vec()));
+        *self.damage.lock().unwrap() = damage.to_vec();
+    }
+
+    fn put_on_clipboard(&self, data: &Data) {
+        *self.clipboard.lock().unwrap() = Some(data.clone());
+    }
+
+    fn get_from_clipboard(&self) -> Option<Data> {
+        self.clipboard.lock().unwrap().clone()
+    }
+
+    fn fonts(&self) -> cosmic_text::fontdb::Database {
+        self.fonts.clone()
+    }
+
+    fn assets(&self) -> HashMap<String, AssetParams> {
+        self.assets.clone()
+    }
+
+    fn svgs(&self) -> HashMap<String, String> {
+        self.svgs.clone()
+    }
+
+    fn set_size(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+    }
+
+    fn exit(&mut self) {}
+
+    fn set_wayland_handle(&mut self, _wayland_handle: RawWaylandHandle) {}
+
+    fn activate_text_input(&self, _activate: bool) {}
+
+    fn has_handle(&self) -> bool {
+        false
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+unsafe impl HasRawWindowHandle for HeadlessWindow {
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        RawWindowHandle::Wayland(WaylandWindowHandle::empty())
+    }
+}
+
+unsafe impl HasRawDisplayHandle for HeadlessWindow {
+    fn raw_display_handle(&self) -> RawDisplayHandle {
+        RawDisplayHandle::Wayland(WaylandDisplayHandle::empty())
+    }
+}
+
+/// A font database with the Space Grotesk font of the examples, for tests that draw text.
+#[cfg(test)]
+pub(crate) fn test_fonts() -> cosmic_text::fontdb::Database {
+    let mut fonts = cosmic_text::fontdb::Database::new();
+    fonts.load_font_data(
+        include_bytes!("../../examples/hello-world/src/assets/fonts/SpaceGrotesk-Regular.ttf")
+            .to_vec(),
+    );
+    fonts
+}
+
+/// The view of a [`TestRoot`], called every time the harness draws.
+pub type TestView = Arc<dyn Fn() -> Node + Send + Sync>;
+
+/// App params of a [`TestRoot`].
+#[derive(Clone)]
+pub struct TestRootParams {
+    pub view: TestView,
+}
+
+struct TestRootState {
+    view: Option<TestView>,
+    messages: Vec<Message>,
+}
+
+/// The root component mounted by [`TestHarness::new`]. Renders the Node returned by its [`TestView`]
+/// and records every message that bubbles up to it.
+#[component(State = "TestRootState", Internal)]
+#[derive(Default)]
+pub struct TestRoot {}
+
+// Messages are only ever touched from the thread that drives the harness.
+unsafe impl Send for TestRoot {}
+unsafe impl Sync for TestRoot {}
+
+impl fmt::Debug for TestRoot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TestRoot").finish()
+    }
+}
+
+#[state_component_impl(TestRootState)]
+impl Component for TestRoot {
+    fn init(&mut self) {
+        self.state = Some(TestRootState {
+            view: None,
+            messages: vec![],
+        });
+    }
+
+    fn view(&self) -> Option<Node> {
+        self.state_ref().view.as_ref().map(|view| view())
+    }
+
+    fn update(&mut self, message: Message) -> Vec<Message> {
+        // Recording a message is not a reason to redraw
+        if let Some(state) = self.state.as_mut() {
+            state.messages.push(message);
+        }
+        vec![]
+    }
+}
+
+impl RootComponent<TestRootParams> for TestRoot {
+    fn root(&mut self, _window: &dyn Any, app_params: &dyn Any) {
+        let app_params = app_params.downcast_ref::<TestRootParams>().unwrap();
+        self.state_mut().view = Some(app_params.view.clone());
+    }
+}
+
+/// Drives a [`UI`] on a [`HeadlessWindow`], without spawning any threads.
+///
+/// Every injected [`Input`] is followed by a synchronous draw, so the Node graph can be queried right away.
+/// Frames are only rendered when [`render`][TestHarness#method.render] is called.
+pub struct TestHarness<A: Component + Default + Send + Sync, B> {
+    ui: UI<HeadlessWindow, A, B>,
+    context: SoftwareCanvasContext,
+}
+
+impl TestHarness<TestRoot, TestRootParams> {
+    /// Mount the Node returned by `view` under a [`TestRoot`], in a window of the given logical size.
+    pub fn new<F>(width: u32, height: u32, view: F) -> Self
+    where
+        F: 'static + Fn() -> Node + Send + Sync,
+    {
+        Self::with_window(
+            HeadlessWindow::new(width, height),
+            TestRootParams {
+                view: Arc::new(view),
+            },
+        )
+    }
+
+    /// Like [`TestHarness::new`], with the Space Grotesk font of the examples loaded, for tests of how text
+    /// is laid out and drawn.
+    #[cfg(test)]
+    pub(crate) fn with_font<F>(width: u32, height: u32, view: F) -> Self
+    where
+        F: 'static + Fn() -> Node + Send + Sync,
+    {
+        Self::with_window(
+            HeadlessWindow::new(width, height).fonts(test_fonts()),
+            TestRootParams {
+                view: Arc::new(view),
+            },
+        )
+    }
+
+    /// Mount the Node returned by `view` alone, in a Div that fills a 100x100 window, for tests of what a single
+    /// component draws. The Node is at `root().children()[0].children()[0]`.
+    #[cfg(test)]
+    pub(crate) fn single<F>(view: F) -> Self
+    where
+        F: 'static + Fn() -> Node + Send + Sync,
+    {
+        use crate::widgets::Div;
+        use crate::{lay, node, size};
+
+        Self::new(100, 100, move || {
+            node!(Div::new(), lay![size: [100., 100.]]).push(view())
+        })
+    }
+
+    /// Remove and return the messages that have bubbled up to the [`TestRoot`].
+    pub fn messages(&mut self) -> Vec<Message> {
+        let mut node = self.ui.node_mut();
+        let mut messages = vec![];
+        if let Some(mut state) = node.component.take_state() {
+            if let Some(s) = state.downcast_mut::<TestRootState>() {
+                messages.append(&mut s.messages);
+            }
+            node.component.replace_state(state);
+        }
+        messages
+    }
+}
+
+impl<
+        A: 'static + RootComponent<B> + Component + Default + Send + Sync,
+        B: 'static + Any + Clone,
+    > TestHarness<A, B>
+{
+    /// Mount the root component `A` in the given `window`, and draw it once.
+    pub fn with_window(window: HeadlessWindow, app_params: B) -> Self {
+        let size = window.logical_size();
+        let assets = window.assets();
+        let mut ui = UI::new(window, app_params);
+        ui.configure_sync(size.width, size.height);
+        ui.draw_sync();
+
+        let physical_size = ui.window.read().unwrap().physical_size();
+        Self {
+            ui,
+            context: SoftwareCanvasContext::new(physical_size.width, physical_size.height, assets),
+        }
+    }
+
+    pub fn ui(&mut self) -> &mut UI<HeadlessWindow, A, B> {
+        &mut self.ui
+    }
+
+    /// Handle `input`, then draw if it dirtied any Node.
+    pub fn input(&mut self, input: Input) {
+        self.ui.handle_input(&input);
+        self.ui.draw_sync();
+    }
+
+    /// Send `msg` to the root component, then draw.
+    pub fn update(&mut self, msg: Message) {
+        self.ui.update(msg);
+        self.ui.draw_sync();
+    }
+
+    /// Resize the window to the given logical size, then draw.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.ui.window.write().unwrap().set_size(width, height);
+        self.input(Input::Resize);
+
+        let physical_size = self.ui.window.read().unwrap().physical_size();
+        let assets = self.ui.window.read().unwrap().assets();
+        self.context =
+            SoftwareCanvasContext::new(physical_size.width, physical_size.height, assets);
+        self.ui.damage_all();
+    }
+
+    /// Move the mouse to the logical position `(x, y)`.
+    pub fn mouse_move(&mut self, x: f32, y: f32) {
+        self.input(Input::Motion(Motion::Mouse { x, y }));
+    }
+
+    /// Move the mouse to the logical position `(x, y)` and click the left button.
+    pub fn click(&mut self, x: f32, y: f32) {
+        self.mouse_move(x, y);
+        self.input(Input::Press(Button::Mouse(MouseButton::Left)));
+        self.input(Input::Release(Button::Mouse(MouseButton::Left)));
+    }
+
+    /// Touch down and up at the logical position `(x, y)`.
+    pub fn tap(&mut self, x: f32, y: f32) {
+        self.input(Input::Touch(TouchAction::Down { x, y }));
+        self.input(Input::Touch(TouchAction::Up { x, y }));
+    }
+
+    /// Advance the animation clock by one tick.
+    pub fn tick(&mut self) {
+        self.input(Input::Timer);
+    }
+
+    /// The current Node graph.
+    pub fn root(&self) -> RwLockReadGuard<'_, Node> {
+        self.ui.node_ref()
+    }
+
+    /// The physical-pixel [`AABB`] of the Node with the given `id`.
+    pub fn aabb(&self, id: u64) -> Option<AABB> {
+        self.root().find_by_id(id).map(|n| n.aabb())
+    }
+
+    /// The damage that was presented with the last rendered frame. Empty if nothing changed since the render before it.
+    pub fn last_damage(&self) -> Vec<AABB> {
+        self.ui.window.read().unwrap().last_damage()
+    }
+
+    /// The id of the focused Node. The root Node is focused when nothing else is.
+    pub fn focus(&self) -> u64 {
+        self.ui.focus()
+    }
+
+    /// Render the regions of the current Node graph that changed since the last render, and return the frame.
+    /// The frame and its damage are also [`present`][Window#method.present]ed to the [`HeadlessWindow`].
+    pub fn render(&mut self) -> &SoftwareCanvas {
+        // So that a render that presents nothing has no damage
+        self.ui
+            .window
+            .read()
+            .unwrap()
+            .damage
+            .lock()
+            .unwrap()
+            .clear();
+        self.ui.render_sync(&mut self.context);
+        &self.context.canvas
+    }
+
+    /// Render the current Node graph into an image, for [`assert_snapshot`][crate::snapshot::assert_snapshot].
+    pub fn snapshot(&mut self) -> image::RgbaImage {
+        crate::snapshot::canvas_to_image(self.render())
+    }
+}
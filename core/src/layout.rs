@@ -603,7 +603,76 @@ impl From<LayoutResult> for crate::types::AABB {
     }
 }
 
+/// The inputs and results of the two layout passes of a Node, as of the last time it was laid out.
+/// A Node that was recycled with its whole subtree can restore these results instead of laying out again,
+/// as long as its inputs have not changed.
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct LayoutMemo {
+    scale_factor: f32,
+    /// The Node's layout result size, as set by its parent, and the bounds given to it, for each pass
+    inputs: [Option<(Size, Size)>; 2],
+    results: [LayoutResult; 2],
+    inner_scales: [Option<crate::types::Scale>; 2],
+    /// Whether the first pass of the current layout was restored. The second pass depends on it.
+    restored: bool,
+}
+
 impl super::node::Node {
+    /// Restore the results of the given pass from the layout memo, if the inputs match.
+    fn restore_layout(&mut self, input: (Size, Size), scale_factor: f32, final_pass: bool) -> bool {
+        let pass = final_pass as usize;
+        let Some(memo) = self.layout_memo.as_mut() else {
+            return false;
+        };
+        let restore = memo.scale_factor == scale_factor
+            && memo.inputs[pass] == Some(input)
+            && (!final_pass || memo.restored);
+        memo.restored = restore;
+        if !restore {
+            return false;
+        }
+
+        // The position of this Node is set by its parent
+        self.layout_result.size = memo.results[pass].size;
+        self.inner_scale = memo.inner_scales[pass];
+        for child in self.children.iter_mut() {
+            child.restore_subtree_layout(pass);
+        }
+        true
+    }
+
+    fn restore_subtree_layout(&mut self, pass: usize) {
+        if let Some(memo) = self.layout_memo.as_mut() {
+            self.layout_result = memo.results[pass];
+            self.inner_scale = memo.inner_scales[pass];
+            memo.restored = true;
+        }
+        for child in self.children.iter_mut() {
+            child.restore_subtree_layout(pass);
+        }
+    }
+
+    fn record_layout(&mut self, input: (Size, Size), scale_factor: f32, final_pass: bool) {
+        let pass = final_pass as usize;
+        let memo = self.layout_memo.get_or_insert_with(Default::default);
+        if !final_pass {
+            *memo = LayoutMemo {
+                scale_factor,
+                ..Default::default()
+            };
+        }
+        memo.inputs[pass] = Some(input);
+        memo.results[pass] = self.layout_result;
+        memo.inner_scales[pass] = self.inner_scale;
+
+        // Children are only positioned once they have been laid out
+        for child in self.children.iter_mut() {
+            if let Some(memo) = child.layout_memo.as_mut() {
+                memo.results[pass].position = child.layout_result.position;
+            }
+        }
+    }
+
     fn resolve_child_sizes(
         &mut self,
         inner_size: Size,
@@ -1005,6 +1074,11 @@ impl super::node::Node {
         scale_factor: f32,
         final_pass: bool,
     ) {
+        let input = (self.layout_result.size, bounds_size);
+        if self.restore_layout(input, scale_factor, final_pass) {
+            return;
+        }
+
         let size = self.layout.size.most_specific(&self.layout_result.size);
 
         let mut inner_size = size.minus_rect(&self.layout.padding.maybe_resolve(&bounds_size));
@@ -1046,6 +1120,8 @@ impl super::node::Node {
                 &self.layout_result
             );
         }
+
+        self.record_layout(input, scale_factor, final_pass);
     }
 
     pub(crate) fn calculate_layout(
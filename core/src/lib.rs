@@ -1,13 +1,18 @@
+pub mod assets;
 pub mod component;
 pub mod context;
+pub mod damage;
 pub mod event;
 pub mod font_cache;
+pub mod headless;
 pub mod instrumenting;
 pub mod pointer;
 pub mod raw_handle;
 pub mod renderables;
 pub mod renderer;
+pub mod snapshot;
 pub mod style;
+pub mod texture;
 pub mod ui;
 pub mod window;
 
@@ -49,8 +54,8 @@ pub mod prelude {
     pub use crate::reexports::*;
     pub use crate::style::*;
     pub use crate::widgets::{
-        Button, Carousel, Div, IconButton, IconType, Image, RoundedRect, Slider, Svg, TextBox,
-        TextBoxAction, TextBoxVariant, TransitionPositions,
+        AnimatedImage, Button, Carousel, Div, IconButton, IconType, Image, RoundedRect, Slider, Svg,
+        TextBox, TextBoxAction, TextBoxVariant, TransitionPositions,
     };
     pub use crate::*;
 }
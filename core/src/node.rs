@@ -90,13 +90,29 @@ pub struct Node {
     pub(crate) layout_result: LayoutResult,
     pub(crate) aabb: AABB,
     pub(crate) inclusive_aabb: AABB,
-    // TODO: Marking a node dirty should propagate to all its parents.
-    //   Clean nodes can be fully recycled instead of performing a `view`
-    // pub(crate) dirty: bool,
+    /// Has the state of the Component changed since it was last viewed? New Nodes start out dirty.
+    pub(crate) dirty: bool,
+    /// Is any Node below this one dirty? Clean subtrees are recycled by the next draw instead of being viewed again.
+    pub(crate) descendant_dirty: bool,
+    pub(crate) layout_memo: Option<LayoutMemo>,
     /// If the node is scrollable, how big are its children?
     pub(crate) inner_scale: Option<Scale>,
     pub(crate) props_hash: u64,
     pub(crate) render_hash: u64,
+    /// For Nodes that [`cache_layer`][Component#method.cache_layer]: the combined render hash of the subtree, which decides when its layer is rendered again.
+    pub(crate) layer_hash: Option<u64>,
+    /// Set by [`transform`][Node#method.transform]. Translations are logical.
+    pub(crate) transform: Option<Transform>,
+    /// The transform of the Node, combined with those of its parents, in physical pixels. Set when rendering.
+    pub(crate) render_transform: Option<Transform>,
+    /// Set by [`opacity`][Node#method.opacity].
+    pub(crate) opacity: f32,
+    /// How far the renderables of the Node reach past its `aabb`, e.g. with shadows.
+    pub(crate) overflow: f32,
+    /// The render hash that `overflow` was measured for. `None` until the Node is rendered, when it could reach any distance past its `aabb`.
+    pub(crate) overflow_hash: Option<u64>,
+    /// The [`clip`][Component#method.clip] of the Node in physical pixels, that its children are drawn within.
+    pub(crate) clip_path: Option<Vec<PathCommand>>,
     pub(crate) key: u64,
 }
 
@@ -112,6 +128,7 @@ impl fmt::Debug for Node {
             .field("props_hash", &self.props_hash)
             .field("render_hash", &self.render_hash)
             .field("key", &self.key)
+            .field("dirty", &self.dirty)
             .field("children", &self.children)
             .finish()
     }
@@ -132,6 +149,12 @@ fn expand_aabb(a: &mut AABB, b: AABB) {
     }
 }
 
+/// Left in the place of a Component that [`Node::recycle`] moved to a new Node graph.
+#[derive(Debug)]
+struct Recycled;
+
+impl Component for Recycled {}
+
 impl Node {
     /// Constructor. In most cases it will be more convenient to use the [`node`] macro, which calls this method.
     pub fn new(component: Box<dyn Component + Send + Sync>, key: u64, layout: Layout) -> Self {
@@ -142,13 +165,22 @@ impl Node {
             key,
             aabb: Default::default(),
             inclusive_aabb: Default::default(),
-            // dirty: false,
+            dirty: true,
+            descendant_dirty: false,
+            layout_memo: None,
             inner_scale: None,
             layout_result: Default::default(),
             children: vec![],
             render_cache: None,
             props_hash: u64::max_value(),
             render_hash: u64::max_value(),
+            layer_hash: None,
+            transform: None,
+            render_transform: None,
+            opacity: 1.0,
+            overflow: 0.0,
+            overflow_hash: None,
+            clip_path: None,
             clip: None,
         }
     }
@@ -165,12 +197,59 @@ impl Node {
         self
     }
 
+    /// Transform everything drawn by the current Node and its children, about the center of the Node, returns itself.
+    /// Translations are in logical pixels. Layout is not affected, but events are delivered to the Nodes where they appear.
+    pub fn transform(mut self, transform: Transform) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    /// Set the opacity of everything drawn by the current Node and its children, from `0.0` (invisible) to `1.0`, returns itself.
+    /// The subtree is drawn into a layer first, which is then blended as a whole, so overlapping children do not show through each other.
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity.clamp(0.0, 1.0);
+        self
+    }
+
+    /// The id of the current Node. Stable across draws, for as long as the Node keeps its position and key.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The position and size of the current Node in physical pixels, as of the last layout.
+    pub fn aabb(&self) -> AABB {
+        self.aabb
+    }
+
+    /// The child Nodes of the current one.
+    pub fn children(&self) -> &[Node] {
+        &self.children
+    }
+
+    /// Find the first Node, depth-first from and including the current one, for which `f` returns true.
+    pub fn find<F: Fn(&Node) -> bool>(&self, f: F) -> Option<&Node> {
+        self.find_inner(&f)
+    }
+
+    fn find_inner<F: Fn(&Node) -> bool>(&self, f: &F) -> Option<&Node> {
+        if f(self) {
+            return Some(self);
+        }
+        self.children.iter().find_map(|c| c.find_inner(f))
+    }
+
+    /// Find the Node with the given [`id`][Node#method.id].
+    pub fn find_by_id(&self, id: u64) -> Option<&Node> {
+        self.find(|n| n.id == id)
+    }
+
     pub(crate) fn view(
         &mut self,
         mut prev: Option<&mut Self>,
         registrations: &mut Vec<Registration>,
     ) {
-        // TODO: skip non-visible (out of frame) nodes
+        // Nodes can't be culled here: where they lie is only known after layout, which needs the whole graph.
+        // Non-visible Nodes are skipped when rendering instead.
         // Set up state and props
         let mut hasher = ComponentHasher::new_with_keys(0, 0);
         if let Some(prev) = &mut prev {
@@ -192,8 +271,22 @@ impl Node {
             self.props_hash = hasher.finish();
         }
 
+        let view = self.component.view();
+        self.view_children(view, prev, registrations);
+    }
+
+    /// Add `view`, the Node returned by [`view`][Component#method.view], to the children of this one, and view them in turn.
+    /// `prev` is the Node that this one replaces, if any.
+    fn view_children(
+        &mut self,
+        view: Option<Self>,
+        mut prev: Option<&mut Self>,
+        registrations: &mut Vec<Registration>,
+    ) {
+        self.dirty = false;
+
         // Create children
-        if let Some(mut child) = self.component.view() {
+        if let Some(mut child) = view {
             if let Some(indexes) = self.component.container() {
                 // Pull out the children that were pushed onto this node, since we need to moves
                 // them to the correct position.
@@ -237,6 +330,10 @@ impl Node {
             }
         }
 
+        self.register(registrations);
+    }
+
+    fn register(&mut self, registrations: &mut Vec<Registration>) {
         // Children's registrations come first, so they can prevent bubbling
         registrations.append(
             &mut self
@@ -248,6 +345,68 @@ impl Node {
         );
     }
 
+    /// Set [`descendant_dirty`][Node#structfield.descendant_dirty] on every ancestor of a dirty Node.
+    /// Since the children of a container are pushed onto it by its parent, a dirty container makes its parent dirty.
+    /// Returns whether the Node or any of its descendants is dirty.
+    pub(crate) fn propagate_dirty(&mut self) -> bool {
+        self.descendant_dirty = false;
+        for child in self.children.iter_mut() {
+            if child.propagate_dirty() {
+                self.descendant_dirty = true;
+                if child.dirty && child.component.container().is_some() {
+                    self.dirty = true;
+                }
+            }
+        }
+        self.dirty || self.descendant_dirty
+    }
+
+    /// Move `prev` into a new Node graph, without calling [`view`][Component#method.view] or
+    /// [`props_hash`][Component#method.props_hash] on clean Nodes. Only dirty Nodes are viewed again,
+    /// as are all of the Nodes that they create. [`propagate_dirty`][Node#method.propagate_dirty] must be called first.
+    ///
+    /// `prev` is left with its render cache, hashes and layout, so it can still be compared against,
+    /// but its Component is moved to the returned Node.
+    pub(crate) fn recycle(prev: &mut Self, registrations: &mut Vec<Registration>) -> Self {
+        let mut new = Self {
+            id: prev.id,
+            component: std::mem::replace(&mut prev.component, Box::new(Recycled)),
+            layout: prev.layout.clone(),
+            key: prev.key,
+            transform: prev.transform,
+            opacity: prev.opacity,
+            props_hash: prev.props_hash,
+            // Clean subtrees can restore their previous layout
+            layout_memo: if prev.dirty || prev.descendant_dirty {
+                None
+            } else {
+                prev.layout_memo.take()
+            },
+            ..Self::new(Box::new(Recycled), 0, Layout::default())
+        };
+
+        let view = if prev.dirty {
+            new.component.view()
+        } else {
+            None
+        };
+
+        if view.is_some() {
+            new.view_children(view, Some(prev), registrations);
+        } else {
+            // Either clean, or all of the children were pushed onto this Node by its parent, which has not changed
+            new.children = prev
+                .children
+                .iter_mut()
+                .map(|child| Self::recycle(child, registrations))
+                .collect();
+            new.dirty = false;
+            new.register(registrations);
+        }
+
+        new
+    }
+
     fn set_aabb(
         &mut self,
         parent_pos: Pos,
@@ -368,14 +527,54 @@ impl Node {
         prev: Option<&mut Self>,
         scale_factor: f32,
     ) -> bool {
-        // TODO: skip non-visible nodes
-        let mut hasher = ComponentHasher::new_with_keys(0, 0);
-        if let Some(prev) = prev {
-            let mut ret = false;
+        // The root Node covers the window
+        let window = self.aabb;
+        self.render_visible(caches, prev, window, None, scale_factor)
+    }
+
+    /// Render the Node and its children, skipping the subtrees that lie entirely outside of `visible`:
+    /// the window, clipped by any scroll frames above the Node.
+    /// Skipped Nodes keep no render cache, so they are rendered once they come into view.
+    fn render_visible(
+        &mut self,
+        caches: Caches,
+        prev: Option<&mut Self>,
+        visible: AABB,
+        parent_transform: Option<Transform>,
+        scale_factor: f32,
+    ) -> bool {
+        self.render_transform = match (self.local_transform(scale_factor), parent_transform) {
+            (Some(t), Some(parent)) => Some(t.then(parent)),
+            (t, parent) => t.or(parent),
+        };
+        // How far the Node reaches past its box is known if it is rendered just as it was before
+        let render_hash = {
+            let mut hasher = ComponentHasher::new_with_keys(0, 0);
             self.component.render_hash(&mut hasher);
             self.aabb.size().hash(&mut hasher);
             self.inner_scale.hash(&mut hasher);
-            self.render_hash = hasher.finish();
+            hasher.finish()
+        };
+        if let Some(prev) = prev.as_deref() {
+            if prev.overflow_hash == Some(render_hash) {
+                self.overflow = prev.overflow;
+                self.overflow_hash = prev.overflow_hash;
+            }
+        }
+        // Otherwise, the Node is rendered to find out, e.g. whether a shadow reaches into view
+        if self.overflow_hash.is_some() && !self.is_visible(&visible) {
+            return false;
+        }
+        let children_visible = self.children_visible(visible);
+        self.clip_path = self
+            .component
+            .clip()
+            .map(|clip| clip.to_path(self.aabb, scale_factor));
+
+        let mut hasher = ComponentHasher::new_with_keys(0, 0);
+        if let Some(prev) = prev {
+            let mut ret = false;
+            self.render_hash = render_hash;
 
             if self.render_hash != prev.render_hash {
                 let context = RenderContext {
@@ -419,20 +618,26 @@ impl Node {
 
             // let scrollable = self.scrollable();
 
-            let prev_children = &mut prev.children;
-            for child in self.children.iter_mut() {
-                ret |= child.render(
-                    caches.clone(),
-                    // if scrollable {
-                    //     None
-                    // } else {
-                    //     prev_children.iter_mut().find(|x| x.key == child.key)
-                    // }
-                    prev_children.iter_mut().find(|x| x.key == child.key),
-                    scale_factor,
-                )
+            if let Some(children_visible) = children_visible {
+                let prev_children = &mut prev.children;
+                for child in self.children.iter_mut() {
+                    ret |= child.render_visible(
+                        caches.clone(),
+                        // if scrollable {
+                        //     None
+                        // } else {
+                        //     prev_children.iter_mut().find(|x| x.key == child.key)
+                        // }
+                        prev_children.iter_mut().find(|x| x.key == child.key),
+                        children_visible,
+                        self.render_transform,
+                        scale_factor,
+                    )
+                }
             }
 
+            self.update_overflow(render_hash);
+            self.update_layer_hash();
             ret
         } else {
             let context = RenderContext {
@@ -446,14 +651,86 @@ impl Node {
             self.component.render_hash(&mut hasher);
             self.render_hash = hasher.finish();
 
-            for child in self.children.iter_mut() {
-                child.render(caches.clone(), None, scale_factor);
+            if let Some(children_visible) = children_visible {
+                for child in self.children.iter_mut() {
+                    child.render_visible(
+                        caches.clone(),
+                        None,
+                        children_visible,
+                        self.render_transform,
+                        scale_factor,
+                    );
+                }
             }
 
+            self.update_overflow(render_hash);
+            self.update_layer_hash();
             true
         }
     }
 
+    /// Is the Node drawn into a layer of its own?
+    fn has_layer(&self) -> bool {
+        self.component.cache_layer() || self.opacity < 1.0 || self.clip_path.is_some()
+    }
+
+    fn update_overflow(&mut self, render_hash: u64) {
+        self.overflow = self
+            .render_cache
+            .iter()
+            .flatten()
+            .map(|r| r.overflow())
+            .fold(0.0, f32::max);
+        self.overflow_hash = Some(render_hash);
+    }
+
+    fn update_layer_hash(&mut self) {
+        self.layer_hash = if self.has_layer() {
+            let mut hasher = ComponentHasher::new_with_keys(0, 0);
+            self.hash_subtree(&mut hasher);
+            Some(hasher.finish())
+        } else {
+            None
+        };
+    }
+
+    /// Hash what the Node and its children draw, and where.
+    /// The opacity of the Node itself is left out, since it is applied when its layer is composited.
+    fn hash_subtree(&self, hasher: &mut ComponentHasher) {
+        self.render_hash.hash(hasher);
+        self.render_cache.is_some().hash(hasher);
+        self.aabb.pos.hash(hasher);
+        self.render_transform.hash(hasher);
+        self.clip_path.hash(hasher);
+        self.children.len().hash(hasher);
+        for child in self.children.iter() {
+            child.opacity.to_bits().hash(hasher);
+            child.hash_subtree(hasher);
+        }
+    }
+
+    /// The area that the layer of a Node that [`cache_layer`][Component#method.cache_layer]s covers,
+    /// in the untransformed space of the Node, limited to what can be seen of `window`.
+    pub(crate) fn layer_bounds(&self, window: AABB) -> Option<AABB> {
+        let window = match self.render_transform.and_then(|t| t.invert()) {
+            Some(inverse) => inverse.apply_aabb(window),
+            None => window,
+        };
+        self.aabb
+            .expand(self.overflow)
+            .union(&self.inclusive_aabb)
+            .intersection(&window)
+            .map(|b| b.round())
+    }
+
+    /// The transform of the Node relative to `root`, one of its ancestors. Used to draw into the layer of `root`.
+    pub(crate) fn transform_within(&self, root: &Node) -> Option<Transform> {
+        match root.render_transform.and_then(|t| t.invert()) {
+            Some(inverse) => Some(self.render_transform.unwrap_or_default().then(inverse)),
+            None => self.render_transform,
+        }
+    }
+
     pub(crate) fn scroll_x(&self) -> Option<f32> {
         self.component.scroll_position().and_then(|p| p.x)
     }
@@ -466,12 +743,92 @@ impl Node {
         self.scroll_x().is_some() || self.scroll_y().is_some()
     }
 
+    /// Could the Node, or any of its children, draw inside of `visible`?
+    /// Nodes without an area are always considered visible, since their children may be positioned outside of them.
+    pub(crate) fn is_visible(&self, visible: &AABB) -> bool {
+        let bounds = self.aabb.expand(self.overflow).union(&self.inclusive_aabb);
+        bounds.is_empty() || self.transformed(bounds).intersects(visible)
+    }
+
+    /// The part of `visible` that the children of this Node can be seen through, if any.
+    fn children_visible(&self, visible: AABB) -> Option<AABB> {
+        if self.scrollable() {
+            visible.intersection(&self.frame())
+        } else {
+            Some(visible)
+        }
+    }
+
+    /// Where the scrollable frame of the Node appears.
+    fn frame(&self) -> ScrollFrame {
+        self.transformed(self.component.frame_bounds(self.aabb, self.inner_scale))
+    }
+
+    /// The [`transform`][Node#method.transform] of the Node in physical pixels, about its center.
+    fn local_transform(&self, scale_factor: f32) -> Option<Transform> {
+        let t = self.transform?;
+        let center = Point::new(
+            (self.aabb.pos.x + self.aabb.bottom_right.x) / 2.0,
+            (self.aabb.pos.y + self.aabb.bottom_right.y) / 2.0,
+        );
+        Some(
+            Transform {
+                e: t.e * scale_factor,
+                f: t.f * scale_factor,
+                ..t
+            }
+            .about(center),
+        )
+    }
+
+    /// Where the renderables of the Node appear in the window.
+    pub(crate) fn drawn_aabb(&self) -> AABB {
+        self.transformed(self.aabb.expand(self.overflow))
+    }
+
+    /// Where `aabb`, in the untransformed space of the Node, appears in the window.
+    pub(crate) fn transformed(&self, aabb: AABB) -> AABB {
+        match self.render_transform {
+            Some(t) => t.apply_aabb(aabb),
+            None => aabb,
+        }
+    }
+
+    /// Map a position in the window into the untransformed space of the Node, for hit-testing.
+    fn local_position(&self, p: Point) -> Point {
+        match self.render_transform.and_then(|t| t.invert()) {
+            Some(inverse) => inverse.apply(p),
+            None => p,
+        }
+    }
+
+    /// Iterate over the renderables of the Node and its visible children, in drawing order.
+    /// The root Node is expected to cover the window.
     pub(crate) fn iter_renderables(&self) -> NodeRenderableIterator<'_> {
+        self.iter_within(self.aabb, false)
+    }
+
+    /// Like [`iter_renderables`][Node#method.iter_renderables], but yields a [`RenderItem::Layer`] in place of the
+    /// subtree of every Node below this one that has a [`layer_hash`][Node#structfield.layer_hash].
+    pub(crate) fn iter_layered(&self) -> NodeRenderableIterator<'_> {
+        self.iter_within(self.aabb, true)
+    }
+
+    /// Iterate over everything drawn in the layer of this Node, within `bounds`. Nested layers are yielded as such.
+    pub(crate) fn iter_layer(&self, bounds: AABB) -> NodeRenderableIterator<'_> {
+        self.iter_within(bounds, true)
+    }
+
+    fn iter_within(&self, window: AABB, layers: bool) -> NodeRenderableIterator<'_> {
         NodeRenderableIterator {
             queue: vec![self],
+            window,
+            visible: Some(window),
             current_frame: vec![],
             frame_queue: vec![],
             i: 0,
+            layers,
+            root: self.id,
         }
     }
 
@@ -513,9 +870,10 @@ impl Node {
         event.over_child_n = None;
         event.over_subchild_n = None;
         for (n, child) in self.children.iter_mut().enumerate() {
+            let child_position = child.local_position(event_target_position);
             if child
                 .component
-                .is_mouse_maybe_over(event_target_position, child.inclusive_aabb)
+                .is_mouse_maybe_over(child_position, child.inclusive_aabb)
             {
                 for message in child
                     ._handle_event_under_mouse(event, handler, node_order, use_touch)
@@ -523,13 +881,11 @@ impl Node {
                 {
                     m.append(&mut self.component.update(message));
                     if self.component.is_dirty() {
+                        self.dirty = true;
                         event.dirty();
                     }
                 }
-                if child
-                    .component
-                    .is_mouse_over(event_target_position, child.aabb)
-                {
+                if child.component.is_mouse_over(child_position, child.aabb) {
                     event.over_subchild_n = event.over_child_n;
                     event.over_child_n = Some(n);
                     event.over_child_n_aabb = Some(child.aabb);
@@ -541,7 +897,7 @@ impl Node {
             && Some(self.id) == node_order.last().map(|x| x.0)
             && self
                 .component
-                .is_mouse_over(event_target_position, self.aabb)
+                .is_mouse_over(self.local_position(event_target_position), self.aabb)
         {
             node_order.pop();
             event.current_node_id = Some(self.id);
@@ -549,6 +905,7 @@ impl Node {
             event.current_inner_scale = self.inner_scale;
             handler(self, event);
             if self.component.is_dirty() {
+                self.dirty = true;
                 event.dirty();
             }
             m.append(&mut event.messages);
@@ -599,15 +956,14 @@ impl Node {
             event_target_position = event.touch_position;
         }
 
-        if self
-            .component
-            .is_mouse_over(event_target_position, self.aabb)
-        {
+        // Hit-test in the untransformed space of the Node
+        let position = self.local_position(event_target_position);
+        if self.component.is_mouse_over(position, self.aabb) {
             collector.push((self.id, self.aabb.pos.z))
         }
 
         let is_mouse_over = self.component.is_mouse_over(
-            event_target_position,
+            position,
             self.component.frame_bounds(self.aabb, self.inner_scale),
         );
 
@@ -616,10 +972,10 @@ impl Node {
         }
 
         for child in self.children.iter() {
-            if child
-                .component
-                .is_mouse_maybe_over(event_target_position, child.inclusive_aabb)
-            {
+            if child.component.is_mouse_maybe_over(
+                child.local_position(event_target_position),
+                child.inclusive_aabb,
+            ) {
                 child._nodes_under(event, collector, use_touch);
             }
         }
@@ -723,7 +1079,8 @@ impl Node {
             event.current_aabb = Some(node.aabb);
             event.current_inner_scale = node.inner_scale;
             handler(node, event);
-            if self.component.is_dirty() {
+            if node.component.is_dirty() {
+                node.dirty = true;
                 event.dirty();
             }
             if stack.is_empty() {
@@ -738,6 +1095,7 @@ impl Node {
                 for message in event.messages.drain(..) {
                     next_messages.append(&mut node.component.update(message));
                     if node.component.is_dirty() {
+                        node.dirty = true;
                         dirty = true;
                     }
                 }
@@ -765,6 +1123,7 @@ impl Node {
             for message in messages.drain(..) {
                 next_messages.append(&mut node.component.update(message));
                 if node.component.is_dirty() {
+                    node.dirty = true;
                     dirty = true;
                 }
             }
@@ -930,6 +1289,7 @@ impl Node {
         event.current_inner_scale = self.inner_scale;
         self.component.on_tick(event);
         if self.component.is_dirty() {
+            self.dirty = true;
             event.dirty();
         }
         m.append(&mut event.messages);
@@ -940,18 +1300,46 @@ impl Node {
 
 pub(crate) type ScrollFrame = AABB;
 
+/// What a [`NodeRenderableIterator`] yields, in drawing order.
+#[derive(Debug)]
+pub(crate) enum RenderItem<'a> {
+    Renderable(&'a Renderable),
+    /// A Node that draws itself and its children from a cached layer.
+    Layer(&'a Node),
+}
+
 pub(crate) struct NodeRenderableIterator<'a> {
     queue: Vec<&'a Node>,
+    window: AABB,
+    // The window, clipped by the current frames
+    visible: Option<AABB>,
     current_frame: Vec<ScrollFrame>,
     frame_queue: Vec<(&'a Node, Vec<ScrollFrame>)>,
     i: usize,
+    // Yield layers in place of their subtrees
+    layers: bool,
+    // The Node being iterated over, which is never yielded as a layer
+    root: u64,
+}
+
+impl<'a> NodeRenderableIterator<'a> {
+    /// Queue the children of `n` that are visible.
+    fn push_children(&mut self, n: &'a Node) {
+        if let Some(visible) = self.visible {
+            self.queue
+                .extend(n.children.iter().filter(|c| c.is_visible(&visible)));
+        }
+    }
 }
 
 impl<'a> Iterator for NodeRenderableIterator<'a> {
-    type Item = (&'a Renderable, &'a AABB, Vec<ScrollFrame>);
+    type Item = (RenderItem<'a>, &'a Node, Vec<ScrollFrame>);
 
     fn next(&mut self) -> Option<Self::Item> {
         while let Some(n) = self.queue.pop() {
+            if self.layers && n.layer_hash.is_some() && n.id != self.root {
+                return Some((RenderItem::Layer(n), n, self.current_frame.clone()));
+            }
             if let Some(c) = &n.render_cache {
                 let i = self.i;
 
@@ -959,26 +1347,29 @@ impl<'a> Iterator for NodeRenderableIterator<'a> {
                     self.i = 0;
                     if n.scrollable() {
                         let mut f = self.current_frame.clone();
-                        f.push(n.component.frame_bounds(n.aabb, n.inner_scale));
+                        f.push(n.frame());
                         self.frame_queue.push((n, f));
                     } else {
-                        self.queue.extend(n.children.iter().collect::<Vec<&Node>>());
+                        self.push_children(n);
                     }
                 } else {
                     self.i += 1;
                     self.queue.push(n);
-                    return Some((&c[i], &n.aabb, self.current_frame.clone()));
+                    return Some((RenderItem::Renderable(&c[i]), n, self.current_frame.clone()));
                 }
             } else if n.scrollable() {
                 let mut f = self.current_frame.clone();
-                f.push(n.component.frame_bounds(n.aabb, n.inner_scale));
+                f.push(n.frame());
                 self.frame_queue.push((n, f));
             } else {
-                self.queue.extend(n.children.iter().collect::<Vec<&Node>>());
+                self.push_children(n);
             }
 
             if self.queue.is_empty() && !self.frame_queue.is_empty() {
                 let (n, f) = self.frame_queue.pop().unwrap();
+                self.visible = f
+                    .iter()
+                    .try_fold(self.window, |visible, frame| visible.intersection(frame));
                 self.current_frame = f;
                 // println!(
                 //     "pushing children {:?} {:?}",
@@ -989,7 +1380,7 @@ impl<'a> Iterator for NodeRenderableIterator<'a> {
                 if has_clip {
                     self.queue.push(n.clip.as_ref().unwrap().1.as_ref());
                 }
-                self.queue.extend(n.children.iter().collect::<Vec<&Node>>());
+                self.push_children(n);
                 if has_clip {
                     self.queue.push(n.clip.as_ref().unwrap().0.as_ref());
                 }
@@ -999,556 +1390,636 @@ impl<'a> Iterator for NodeRenderableIterator<'a> {
     }
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-//     use crate::render::{Renderable, Renderer};
-//     use crate::window::Window;
-//     use raw_window_handle::{
-//         HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
-//     };
-
-//     pub struct TestWindow {}
-//     impl Window for TestWindow {
-//         fn logical_size(&self) -> PixelSize {
-//             PixelSize {
-//                 width: 100,
-//                 height: 100,
-//             }
-//         }
-
-//         fn physical_size(&self) -> PixelSize {
-//             PixelSize {
-//                 width: 100,
-//                 height: 100,
-//             }
-//         }
-
-//         fn scale_factor(&self) -> f32 {
-//             1.0
-//         }
-//     }
-//     unsafe impl HasRawWindowHandle for TestWindow {
-//         fn raw_window_handle(&self) -> RawWindowHandle {
-//             panic!("Can't get windows handle in a test")
-//         }
-//     }
-
-//     unsafe impl HasRawDisplayHandle for TestWindow {
-//         fn raw_display_handle(&self) -> RawDisplayHandle {
-//             panic!("Can't get windows handle in a test")
-//         }
-//     }
-
-//     #[derive(Debug)]
-//     pub struct TestRenderer {}
-//     impl Renderer for TestRenderer {
-//         fn new<W: Window>(_window: &W) -> Self {
-//             Self {}
-//         }
-//     }
-
-//     mod container {
-//         use super::*;
-//         #[derive(Debug)]
-//         pub struct Container {}
-
-//         impl Component for Container {}
-//     }
-
-//     mod test_button {
-//         use super::*;
-//         #[derive(Debug)]
-//         pub struct TestButton<M> {
-//             label: String,
-//             on_click: Option<M>,
-//         }
-
-//         impl<M> TestButton<M> {
-//             pub fn new(label: &str) -> Self {
-//                 Self {
-//                     on_click: None,
-//                     label: label.to_string(),
-//                 }
-//             }
-//             pub fn on_click(mut self, msg: M) -> Self {
-//                 self.on_click = Some(msg);
-//                 self
-//             }
-//         }
-
-//         impl<M: 'static + fmt::Debug + Clone> Component for TestButton<M> {
-//             fn on_click(&mut self, event: &mut Event<event::Click>) {
-//                 // println!("ON CLICK {}", &self.label);
-//                 if let Some(msg) = &self.on_click {
-//                     event.emit(Box::new(msg.clone()));
-//                 }
-//             }
-
-//             fn render(&mut self, context: RenderContext) -> Option<Vec<Renderable>> {
-//                 Some(vec![Renderable::Inc {
-//                     repr: self.label.clone(),
-//                     i: context.prev_state.map_or(1, |r| match r[0] {
-//                         Renderable::Inc { i, .. } => i + 1,
-//                         _ => panic!(),
-//                     }),
-//                 }])
-//             }
-//         }
-//     }
-
-//     pub fn container(key: u64) -> Node {
-//         Node::new(Box::new(container::Container {}), key, Layout::default())
-//     }
-
-//     mod widget {
-//         use super::*;
-//         #[derive(Debug)]
-//         pub struct Widget {
-//             pub prop: usize,
-//             pub state: Option<WidgetState>,
-//         }
-//         #[derive(Debug)]
-//         pub struct WidgetState {
-//             pub bar: usize,
-//         }
-//         #[derive(Debug, Clone)]
-//         pub enum Msg {
-//             APressed,
-//             BPressed,
-//         }
-//         impl Widget {
-//             pub fn new(prop: usize) -> Self {
-//                 Self { prop, state: None }
-//             }
-//         }
-
-//         impl Component for Widget {
-//             fn view(&self) -> Option<Node> {
-//                 Some(
-//                     container(0)
-//                         .push(Node::new(
-//                             Box::new(
-//                                 test_button::TestButton::new("Button A").on_click(Msg::APressed),
-//                             ),
-//                             0,
-//                             Layout::default(),
-//                         ))
-//                         .push(Node::new(
-//                             Box::new(
-//                                 test_button::TestButton::new("Button B").on_click(Msg::BPressed),
-//                             ),
-//                             1,
-//                             Layout::default(),
-//                         )),
-//                 )
-//             }
-
-//             fn update(&mut self, message: Message) -> Vec<Message> {
-//                 let msg = match message.downcast_ref::<Msg>().unwrap() {
-//                     Msg::APressed => test_app::AppMessage::IncFoo(2),
-//                     Msg::BPressed => test_app::AppMessage::DecFoo(1),
-//                 };
-//                 vec![Box::new(msg)]
-//             }
-
-//             fn replace_state(&mut self, other_state: State) {
-//                 let s = other_state.downcast::<WidgetState>().unwrap();
-//                 self.state = Some(*s);
-//             }
-
-//             fn take_state(&mut self) -> Option<State> {
-//                 if let Some(s) = self.state.take() {
-//                     Some(Box::new(s))
-//                 } else {
-//                     None
-//                 }
-//             }
-//         }
-//     }
-
-//     mod test_app {
-//         use super::*;
-
-//         #[derive(Debug, Default)]
-//         pub struct TestApp {
-//             pub state: Option<AppState>,
-//         }
-
-//         #[derive(Debug)]
-//         pub struct AppState {
-//             pub foo: usize,
-//         }
-
-//         #[derive(Debug)]
-//         pub enum AppMessage {
-//             IncFoo(usize),
-//             DecFoo(usize),
-//         }
-
-//         impl TestApp {
-//             fn state_mut(&mut self) -> &mut AppState {
-//                 self.state.as_mut().unwrap()
-//             }
-//         }
-
-//         impl Component for TestApp {
-//             fn init(&mut self) {
-//                 self.state = Some(test_app::AppState { foo: 1 });
-//             }
-
-//             fn view(&self) -> Option<Node> {
-//                 let foo = self.state.as_ref().unwrap().foo;
-//                 Some(container(0).push(Node::new(
-//                     Box::new(widget::Widget::new(foo)),
-//                     0,
-//                     Layout::default(),
-//                 )))
-//             }
-
-//             fn update(&mut self, message: Message) -> Vec<Message> {
-//                 match message.downcast_ref::<AppMessage>().unwrap() {
-//                     AppMessage::IncFoo(x) => self.state_mut().foo += x,
-//                     AppMessage::DecFoo(x) => self.state_mut().foo -= x,
-//                 };
-//                 vec![]
-//             }
-
-//             fn replace_state(&mut self, other_state: State) {
-//                 let s = other_state.downcast::<AppState>().unwrap();
-//                 self.state = Some(*s);
-//             }
-
-//             fn take_state(&mut self) -> Option<State> {
-//                 if let Some(s) = self.state.take() {
-//                     Some(Box::new(s))
-//                 } else {
-//                     None
-//                 }
-//             }
-
-//             fn render(&mut self, context: RenderContext) -> Option<Vec<Renderable>> {
-//                 Some(vec![Renderable::Inc {
-//                     repr: format!("{}", self.state.as_ref().map(|s| s.foo).unwrap()),
-//                     i: context.prev_state.map_or(1, |r| match r[0] {
-//                         Renderable::Inc { i, .. } => i + 1,
-//                         _ => panic!(),
-//                     }),
-//                 }])
-//             }
-
-//             fn render_hash(&self, hasher: &mut ComponentHasher) {
-//                 if let Some(s) = self.state.as_ref() {
-//                     s.foo.hash(hasher)
-//                 }
-//             }
-//         }
-//     }
-
-//     #[test]
-//     fn test_caching() {
-//         let renderer = TestRenderer {};
-//         let mut n = Node::new(Box::new(test_app::TestApp::default()), 0, Layout::default());
-//         n.view(None, &mut vec![]);
-//         //n.layout();
-//         n.render(renderer.caches(), None, 1.0);
-//         //println!("{:#?}", n);
-//         assert_eq!(
-//             n.render_cache,
-//             Some(vec![Renderable::Inc {
-//                 repr: "1".to_string(),
-//                 i: 1
-//             }])
-//         );
-//         assert_eq!(
-//             n.children[0].children[0].children[0].children[0].render_cache,
-//             Some(vec![Renderable::Inc {
-//                 repr: "Button A".to_string(),
-//                 i: 1
-//             }])
-//         );
-
-//         assert_eq!(n.iter_renderables().count(), 3);
-
-//         let mut event = Event::new(
-//             event::Click(crate::input::MouseButton::Left),
-//             &crate::event::EventCache::new(1.0),
-//         );
-//         n.click(&mut event);
-
-//         let mut new_n = Node::new(Box::new(test_app::TestApp::default()), 0, Layout::default());
-//         new_n.view(Some(&mut n), &mut vec![]);
-//         assert_eq!(n.id, new_n.id);
-//         assert_eq!(n.children[0].id, new_n.children[0].id);
-
-//         //new_n.layout();
-//         new_n.render(renderer.caches(), Some(&mut n), 1.0);
-//         //println!("{:#?}", new_n);
-//         assert_eq!(
-//             new_n.render_cache,
-//             Some(vec![Renderable::Inc {
-//                 repr: "2".to_string(),
-//                 i: 2
-//             }])
-//         );
-//         // Button did not need to be re-rendered
-//         assert_eq!(
-//             new_n.children[0].children[0].children[0].children[0].render_cache,
-//             Some(vec![Renderable::Inc {
-//                 repr: "Button A".to_string(),
-//                 i: 1
-//             }])
-//         );
-//     }
-
-//     mod test_scroll_app {
-//         use super::*;
-
-//         #[derive(Debug)]
-//         pub struct Div {
-//             name: String,
-//             scrollable: bool,
-//         }
-
-//         impl Component for Div {
-//             fn render(&mut self, context: RenderContext) -> Option<Vec<Renderable>> {
-//                 Some(vec![Renderable::Inc {
-//                     repr: format!("Div {}", &self.name),
-//                     i: context.prev_state.map_or(1, |r| match r[0] {
-//                         Renderable::Inc { i, .. } => i + 1,
-//                         _ => panic!(),
-//                     }),
-//                 }])
-//             }
-
-//             fn scroll_position(&self) -> Option<ScrollPosition> {
-//                 if self.scrollable {
-//                     Some(ScrollPosition {
-//                         x: Some(0.0),
-//                         y: Some(50.0),
-//                     })
-//                 } else {
-//                     None
-//                 }
-//             }
-//         }
-
-//         #[derive(Debug, Default)]
-//         pub struct TestApp {}
-
-//         impl Component for TestApp {
-//             fn view(&self) -> Option<Node> {
-//                 Some(
-//                     Node::new(
-//                         Box::new(Div {
-//                             name: "Top".to_string(),
-//                             scrollable: false,
-//                         }),
-//                         0,
-//                         Layout::default(),
-//                     )
-//                     .push(
-//                         Node::new(
-//                             Box::new(Div {
-//                                 name: "Scroll".to_string(),
-//                                 scrollable: true,
-//                             }),
-//                             0,
-//                             Layout {
-//                                 size: Size {
-//                                     width: Dimension::Px(100.0),
-//                                     height: Dimension::Px(100.0),
-//                                 },
-//                                 direction: Direction::Row,
-//                                 ..Default::default()
-//                             },
-//                         )
-//                         .push(
-//                             Node::new(
-//                                 Box::new(Div {
-//                                     name: "Column A".to_string(),
-//                                     scrollable: false,
-//                                 }),
-//                                 0,
-//                                 Layout {
-//                                     size: Size {
-//                                         width: Dimension::Px(100.0),
-//                                         height: Dimension::Auto,
-//                                     },
-//                                     direction: Direction::Column,
-//                                     ..Default::default()
-//                                 },
-//                             )
-//                             .push(Node::new(
-//                                 Box::new(Div {
-//                                     name: "A1".to_string(),
-//                                     scrollable: false,
-//                                 }),
-//                                 0,
-//                                 Layout {
-//                                     size: Size {
-//                                         width: Dimension::Auto,
-//                                         height: Dimension::Px(75.0),
-//                                     },
-//                                     ..Default::default()
-//                                 },
-//                             ))
-//                             .push(Node::new(
-//                                 Box::new(Div {
-//                                     name: "A2".to_string(),
-//                                     scrollable: false,
-//                                 }),
-//                                 1,
-//                                 Layout {
-//                                     size: Size {
-//                                         width: Dimension::Auto,
-//                                         height: Dimension::Px(75.0),
-//                                     },
-//                                     ..Default::default()
-//                                 },
-//                             )),
-//                         )
-//                         .push(
-//                             Node::new(
-//                                 Box::new(Div {
-//                                     name: "Column B".to_string(),
-//                                     scrollable: false,
-//                                 }),
-//                                 0,
-//                                 Layout {
-//                                     size: Size {
-//                                         width: Dimension::Px(100.0),
-//                                         height: Dimension::Auto,
-//                                     },
-//                                     direction: Direction::Column,
-//                                     ..Default::default()
-//                                 },
-//                             )
-//                             .push(Node::new(
-//                                 Box::new(Div {
-//                                     name: "B1".to_string(),
-//                                     scrollable: false,
-//                                 }),
-//                                 0,
-//                                 Layout {
-//                                     size: Size {
-//                                         width: Dimension::Auto,
-//                                         height: Dimension::Px(75.0),
-//                                     },
-//                                     ..Default::default()
-//                                 },
-//                             ))
-//                             .push(Node::new(
-//                                 Box::new(Div {
-//                                     name: "B2".to_string(),
-//                                     scrollable: false,
-//                                 }),
-//                                 1,
-//                                 Layout {
-//                                     size: Size {
-//                                         width: Dimension::Auto,
-//                                         height: Dimension::Px(75.0),
-//                                     },
-//                                     ..Default::default()
-//                                 },
-//                             )),
-//                         ),
-//                     ),
-//                 )
-//             }
-
-//             fn render(&mut self, context: RenderContext) -> Option<Vec<Renderable>> {
-//                 Some(vec![Renderable::Inc {
-//                     repr: "ScrollApp".to_string(),
-//                     i: context.prev_state.map_or(1, |r| match r[0] {
-//                         Renderable::Inc { i, .. } => i + 1,
-//                         _ => panic!(),
-//                     }),
-//                 }])
-//             }
-//         }
-//     }
-
-//     #[test]
-//     fn test_scroll() {
-//         let renderer = TestRenderer {};
-//         let m = Node::new(
-//             Box::new(test_scroll_app::TestApp::default()),
-//             0,
-//             Layout::default(),
-//         );
-//         let mut n = Node::new(
-//             Box::new(test_scroll_app::TestApp::default()),
-//             0,
-//             lay!(size: size!(300.0)),
-//         );
-//         n.view(None, &mut vec![]);
-//         n.layout(&m, &renderer.caches().font.read().unwrap(), 1.0);
-
-//         // Expect the inner_scale to be a real size
-//         let scroll_node = &mut n.children[0].children[0];
-//         assert_eq!(scroll_node.aabb.size(), [100.0, 100.0].into());
-//         assert_eq!(scroll_node.inner_scale.unwrap(), [200.0, 150.0].into());
-
-//         // Expect renderables to be laid out in the right order, with the correct Frames
-//         n.render(renderer.caches(), None, 1.0);
-//         let renderables = n.iter_renderables().collect::<Vec<_>>();
-//         assert_eq!(renderables.len(), 9);
-//         // First three (App, Top Div, Scroll Div) do not have Frames
-//         assert_eq!(renderables[0].2.len(), 0);
-//         assert_eq!(renderables[2].2.len(), 0);
-//         // The rest have Frames
-//         assert_eq!(renderables[3].2.len(), 1);
-//         assert_eq!(renderables[8].2.len(), 1);
-//     }
-
-//     mod test_registration_app {
-//         use super::*;
-
-//         #[derive(Debug)]
-//         pub struct Registerer {
-//             registration: event::Register,
-//         }
-
-//         impl Component for Registerer {
-//             fn register(&mut self) -> Vec<event::Register> {
-//                 vec![self.registration]
-//             }
-//         }
-
-//         #[derive(Debug, Default)]
-//         pub struct TestApp {}
-
-//         impl Component for TestApp {
-//             fn view(&self) -> Option<Node> {
-//                 Some(
-//                     node!(Registerer {
-//                         registration: event::Register::KeyDown
-//                     })
-//                     .push(node!(Registerer {
-//                         registration: event::Register::KeyUp,
-//                     }))
-//                     .push(node!(Registerer {
-//                         registration: event::Register::KeyPress,
-//                     })),
-//                 )
-//             }
-//         }
-//     }
-
-//     #[test]
-//     fn test_registration() {
-//         let mut n = Node::new(
-//             Box::new(test_registration_app::TestApp::default()),
-//             0,
-//             Layout::default(),
-//         );
-
-//         let mut registrations: Vec<(event::Register, u64)> = vec![];
-//         n.view(None, &mut registrations);
-//         assert_eq!(registrations.len(), 3);
-//         assert_eq!(registrations[0].0, event::Register::KeyUp);
-//         assert_eq!(registrations[1].0, event::Register::KeyPress);
-//         assert_eq!(registrations[2].0, event::Register::KeyDown);
-//     }
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::{TestHarness, TestRoot, TestRootParams};
+    use crate::renderables::Rect;
+    use crate::{lay, node, size};
+    use std::sync::atomic::AtomicUsize;
+
+    mod test_button {
+        use super::*;
+
+        #[derive(Debug)]
+        pub struct TestButton<M> {
+            pub label: String,
+            pub on_click: M,
+        }
+
+        impl<M: 'static + fmt::Debug + Clone + Send + Sync> Component for TestButton<M> {
+            fn on_click(&mut self, event: &mut Event<event::Click>) {
+                event.stop_bubbling();
+                event.focus();
+                event.emit(Box::new(self.on_click.clone()));
+            }
+        }
+    }
+
+    mod widget {
+        use super::*;
+
+        #[derive(Debug, Clone)]
+        pub enum Msg {
+            APressed,
+            BPressed,
+        }
+
+        #[derive(Debug, PartialEq)]
+        pub enum WidgetMessage {
+            Inc(usize),
+            Dec(usize),
+        }
+
+        #[derive(Debug)]
+        pub struct Widget {}
+
+        impl Component for Widget {
+            fn view(&self) -> Option<Node> {
+                Some(
+                    node!(Container {}, lay![size: [100., 50.]])
+                        .push(node!(
+                            test_button::TestButton {
+                                label: "Button A".to_string(),
+                                on_click: Msg::APressed,
+                            },
+                            lay![size: [50., 50.]],
+                            0
+                        ))
+                        .push(node!(
+                            test_button::TestButton {
+                                label: "Button B".to_string(),
+                                on_click: Msg::BPressed,
+                            },
+                            lay![size: [50., 50.]],
+                            1
+                        )),
+                )
+            }
+
+            fn update(&mut self, message: Message) -> Vec<Message> {
+                let msg = match message.downcast_ref::<Msg>().unwrap() {
+                    Msg::APressed => WidgetMessage::Inc(2),
+                    Msg::BPressed => WidgetMessage::Dec(1),
+                };
+                vec![Box::new(msg)]
+            }
+        }
+
+        #[derive(Debug)]
+        pub struct Container {}
+
+        impl Component for Container {}
+    }
+
+    #[test]
+    fn test_messages_and_focus() {
+        let mut harness = TestHarness::new(100, 100, || node!(widget::Widget {}));
+        let root_id = harness.root().id();
+        assert_eq!(harness.focus(), root_id);
+
+        // TestRoot -> Widget -> Container -> [Button A, Button B]
+        let button_b = harness.root().children()[0].children()[0].children()[1].id();
+        let aabb = harness.aabb(button_b).unwrap();
+        assert_eq!((aabb.pos.x, aabb.pos.y), (50.0, 0.0));
+        assert_eq!(aabb.size(), [50.0, 50.0].into());
+
+        harness.click(75.0, 25.0);
+        let messages = harness.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            messages[0].downcast_ref::<widget::WidgetMessage>(),
+            Some(&widget::WidgetMessage::Dec(1))
+        );
+        assert_eq!(harness.focus(), button_b);
+
+        // Ids survive a redraw
+        harness.update(Box::new(()));
+        let button_b_after = harness.root().children()[0].children()[0].children()[1].id();
+        assert_eq!(button_b, button_b_after);
+        // Messages sent straight to the root are recorded too
+        assert!(harness.messages()[0].downcast_ref::<()>().is_some());
+    }
+
+    static COUNTER_RENDERS: AtomicUsize = AtomicUsize::new(0);
+
+    mod counter {
+        use super::*;
+        use mctk_macros::{component, state_component_impl};
+
+        #[derive(Debug, Default)]
+        pub struct CounterState {
+            pub count: usize,
+        }
+
+        #[component(State = "CounterState", Internal)]
+        #[derive(Debug, Default)]
+        pub struct Counter {}
+
+        #[state_component_impl(CounterState)]
+        impl Component for Counter {
+            fn init(&mut self) {
+                self.state = Some(CounterState::default());
+            }
+
+            fn on_click(&mut self, _event: &mut Event<event::Click>) {
+                self.state_mut().count += 1;
+            }
+
+            fn render_hash(&self, hasher: &mut ComponentHasher) {
+                self.state_ref().count.hash(hasher)
+            }
+
+            fn render(&mut self, context: RenderContext) -> Option<Vec<Renderable>> {
+                COUNTER_RENDERS.fetch_add(1, Ordering::SeqCst);
+                Some(vec![Renderable::Rect(Rect::new(
+                    context.aabb.pos,
+                    context.aabb.size(),
+                    Color::BLACK,
+                ))])
+            }
+        }
+    }
+
+    #[test]
+    fn test_caching() {
+        let mut harness = TestHarness::new(100, 100, || {
+            node!(widget::Container {}, lay![size: [100., 100.]]).push(node!(
+                counter::Counter::default(),
+                lay![size: [50., 50.]],
+                0
+            ))
+        });
+        assert_eq!(COUNTER_RENDERS.load(Ordering::SeqCst), 1);
+
+        // Nothing changed, so nothing is rendered again
+        harness.update(Box::new(()));
+        assert_eq!(COUNTER_RENDERS.load(Ordering::SeqCst), 1);
+
+        // State is carried over to the new graph, and only the changed Node is rendered again
+        harness.click(25.0, 25.0);
+        assert_eq!(COUNTER_RENDERS.load(Ordering::SeqCst), 2);
+        harness.click(75.0, 75.0);
+        assert_eq!(COUNTER_RENDERS.load(Ordering::SeqCst), 2);
+
+        let frame = harness.render();
+        assert_eq!((frame.width(), frame.height()), (100, 100));
+        // Black inside the counter, cleared outside of it
+        assert_eq!(frame.pixmap().pixel(10, 10).unwrap().alpha(), 255);
+        assert_eq!(frame.pixmap().pixel(90, 90).unwrap().alpha(), 0);
+    }
+
+    static LABEL_VIEWS: AtomicUsize = AtomicUsize::new(0);
+    static LABEL_PROPS_HASHES: AtomicUsize = AtomicUsize::new(0);
+
+    mod test_recycle_app {
+        use super::*;
+        use mctk_macros::{component, state_component_impl};
+
+        #[derive(Debug, Default)]
+        pub struct SwitchState {
+            pub on: bool,
+        }
+
+        #[component(State = "SwitchState", Internal)]
+        #[derive(Debug, Default)]
+        pub struct Switch {}
+
+        #[state_component_impl(SwitchState)]
+        impl Component for Switch {
+            fn init(&mut self) {
+                self.state = Some(SwitchState::default());
+            }
+
+            fn on_click(&mut self, _event: &mut Event<event::Click>) {
+                let on = self.state_ref().on;
+                self.state_mut().on = !on;
+            }
+
+            fn render_hash(&self, hasher: &mut ComponentHasher) {
+                self.state_ref().on.hash(hasher)
+            }
+
+            fn render(&mut self, context: RenderContext) -> Option<Vec<Renderable>> {
+                let color = if self.state_ref().on {
+                    Color::BLACK
+                } else {
+                    Color::WHITE
+                };
+                Some(vec![Renderable::Rect(Rect::new(
+                    context.aabb.pos,
+                    context.aabb.size(),
+                    color,
+                ))])
+            }
+        }
+
+        #[derive(Debug)]
+        pub struct Label {}
+
+        impl Component for Label {
+            fn props_hash(&self, _hasher: &mut ComponentHasher) {
+                LABEL_PROPS_HASHES.fetch_add(1, Ordering::SeqCst);
+            }
+
+            fn view(&self) -> Option<Node> {
+                LABEL_VIEWS.fetch_add(1, Ordering::SeqCst);
+                Some(node!(widget::Container {}, lay![size: [50., 20.]]))
+            }
+        }
+
+        pub fn view() -> Node {
+            node!(widget::Container {}, lay![size: [100., 100.]])
+                .push(node!(Switch::default(), lay![size: [50., 50.]], 0))
+                .push(node!(Label {}, lay![size: [50., 50.]], 1))
+        }
+    }
+
+    #[test]
+    fn test_recycling() {
+        let mut harness = TestHarness::new(100, 100, test_recycle_app::view);
+        assert_eq!(LABEL_VIEWS.load(Ordering::SeqCst), 1);
+        assert_eq!(LABEL_PROPS_HASHES.load(Ordering::SeqCst), 1);
+        assert_eq!(harness.render().pixmap().pixel(10, 10).unwrap().red(), 255);
+
+        // TestRoot -> Container -> [Switch, Label]
+        let label = harness.root().children()[0].children()[1].id();
+        let label_aabb = harness.aabb(label).unwrap();
+
+        // Only the Switch is viewed again, the Label is moved over along with its layout
+        harness.click(25.0, 25.0);
+        assert_eq!(LABEL_VIEWS.load(Ordering::SeqCst), 1);
+        assert_eq!(LABEL_PROPS_HASHES.load(Ordering::SeqCst), 1);
+        assert_eq!(harness.root().children()[0].children()[1].id(), label);
+        assert_eq!(harness.aabb(label), Some(label_aabb));
+        assert!(harness.root().find(|n| n.dirty).is_none());
+        assert_eq!(harness.render().pixmap().pixel(10, 10).unwrap().red(), 0);
+
+        // Dirtying the root views everything again
+        harness.update(Box::new(()));
+        assert_eq!(LABEL_VIEWS.load(Ordering::SeqCst), 2);
+        assert_eq!(harness.aabb(label), Some(label_aabb));
+    }
+
+    #[test]
+    fn test_layer_caching() {
+        let mut harness = TestHarness::new(100, 100, || {
+            node!(widget::Container {}, lay![size: [100., 100.]])
+                .push(node!(
+                    test_recycle_app::Switch::default(),
+                    lay![size: [50., 50.]],
+                    0
+                ))
+                .push(
+                    node!(
+                        widgets::Div::new().bg(Color::RED).cache_layer(true),
+                        lay![size: [50., 50.]],
+                        1
+                    )
+                    .push(node!(
+                        test_recycle_app::Switch::default(),
+                        lay![size: [20., 20.]],
+                        0
+                    )),
+                )
+        });
+        type Harness = TestHarness<TestRoot, TestRootParams>;
+        fn pixel(harness: &mut Harness, x: f32, y: f32) -> (u8, u8) {
+            let p = harness.render().pixmap().pixel(x as u32, y as u32).unwrap();
+            (p.red(), p.green())
+        }
+        fn layer_hash(harness: &Harness) -> Option<u64> {
+            harness.root().children()[0].children()[1].layer_hash
+        }
+
+        // The Div and its Switch are drawn as a single layer
+        let layer = harness.root().children()[0].children()[1].id();
+        let bounds = harness.aabb(layer).unwrap();
+        let (x, y) = (bounds.pos.x, bounds.pos.y);
+        let layers = harness
+            .root()
+            .iter_layered()
+            .filter(|(item, _, _)| matches!(item, RenderItem::Layer(_)))
+            .count();
+        assert_eq!(layers, 1);
+        assert_eq!(pixel(&mut harness, x + 10., y + 10.), (255, 255));
+        assert_eq!(pixel(&mut harness, x + 40., y + 40.), (255, 0));
+        let hash = layer_hash(&harness);
+        assert!(hash.is_some());
+
+        // Changes outside of the layer leave it as is
+        harness.click(10.0, 10.0);
+        assert_eq!(layer_hash(&harness), hash);
+        assert_eq!(pixel(&mut harness, 10., 10.), (0, 0));
+        assert_eq!(pixel(&mut harness, x + 10., y + 10.), (255, 255));
+
+        // Changes inside of it draw the layer again
+        harness.click(x + 10., y + 10.);
+        assert_ne!(layer_hash(&harness), hash);
+        assert_eq!(pixel(&mut harness, x + 10., y + 10.), (0, 0));
+        assert_eq!(pixel(&mut harness, x + 40., y + 40.), (255, 0));
+    }
+
+    #[test]
+    fn test_transform() {
+        let mut harness = TestHarness::new(100, 100, || {
+            node!(widget::Container {}, lay![size: [100., 100.]]).push(
+                node!(
+                    test_recycle_app::Switch::default(),
+                    lay![size: [20., 20.]],
+                    0
+                )
+                .transform(Transform::translate(50.0, 50.0)),
+            )
+        });
+        // Layout is unaffected
+        let switch = harness.root().children()[0].children()[0].id();
+        let aabb = harness.aabb(switch).unwrap();
+        assert_eq!((aabb.pos.x, aabb.pos.y), (0.0, 0.0));
+
+        // It is drawn where it was moved to
+        let pixel = harness.render().pixmap().pixel(60, 60).unwrap();
+        assert_eq!((pixel.red(), pixel.alpha()), (255, 255));
+        assert_eq!(harness.render().pixmap().pixel(10, 10).unwrap().alpha(), 0);
+
+        // And it is hit where it is drawn
+        harness.click(10.0, 10.0);
+        assert_eq!(harness.render().pixmap().pixel(60, 60).unwrap().red(), 255);
+        harness.click(60.0, 60.0);
+        assert_eq!(harness.render().pixmap().pixel(60, 60).unwrap().red(), 0);
+    }
+
+    #[test]
+    fn test_opacity() {
+        let mut harness = TestHarness::new(100, 100, || {
+            node!(widget::Container {}, lay![size: [100., 100.]]).push(
+                node!(
+                    widgets::Div::new().bg(Color::WHITE),
+                    lay![size: [50., 50.]],
+                    0
+                )
+                .push(node!(
+                    test_recycle_app::Switch::default(),
+                    lay![size: [20., 20.]],
+                    0
+                ))
+                .opacity(0.5),
+            )
+        });
+        let alpha = harness.render().pixmap().pixel(40, 40).unwrap().alpha();
+        assert!((127..=128).contains(&alpha));
+        // The Switch and the Div beneath it are blended as one
+        assert_eq!(
+            harness.render().pixmap().pixel(10, 10).unwrap().alpha(),
+            alpha
+        );
+    }
+
+    #[test]
+    fn test_overflow_into_view() {
+        use crate::renderables::rect::BoxShadow;
+
+        // The Div is below the window, but its shadow reaches into it
+        let mut harness = TestHarness::new(100, 100, || {
+            node!(
+                widget::Container {},
+                lay![size: [100., 200.], direction: Direction::Column]
+            )
+            .push(node!(widgets::Div::new(), lay![size: [100., 110.]], 0))
+            .push(node!(
+                widgets::Div::new().bg(Color::WHITE).shadow(BoxShadow::new(
+                    Color::BLACK,
+                    (0., -30.),
+                    0.,
+                    0.
+                )),
+                lay![size: [50., 50.]],
+                1
+            ))
+        });
+        assert_eq!(
+            harness.render().pixmap().pixel(25, 90).unwrap().alpha(),
+            255
+        );
+        // Once its overflow is known, it is still drawn
+        harness.tick();
+        assert_eq!(
+            harness.render().pixmap().pixel(25, 90).unwrap().alpha(),
+            255
+        );
+        assert_eq!(harness.render().pixmap().pixel(75, 90).unwrap().alpha(), 0);
+    }
+
+    #[test]
+    fn test_clip() {
+        let mut harness = TestHarness::new(100, 100, || {
+            node!(widget::Container {}, lay![size: [100., 100.]]).push(
+                node!(
+                    widgets::Div::new().bg(Color::BLUE).clip(Clip::rounded(20.)),
+                    lay![size: [50., 50.]],
+                    0
+                )
+                .push(node!(
+                    widgets::Div::new().bg(Color::RED),
+                    lay![size: [50., 50.]],
+                    0
+                )),
+            )
+        });
+        let pixmap = harness.render().pixmap();
+        // The child is clipped to the rounded corners, while the Div itself is not
+        let corner = pixmap.pixel(1, 1).unwrap();
+        assert_eq!((corner.red(), corner.blue()), (0, 255));
+        let center = pixmap.pixel(25, 25).unwrap();
+        assert_eq!((center.red(), center.blue()), (255, 0));
+        let edge = pixmap.pixel(25, 1).unwrap();
+        assert_eq!((edge.red(), edge.blue()), (255, 0));
+    }
+
+    mod test_scroll_app {
+        use super::*;
+
+        #[derive(Debug)]
+        pub struct Div {
+            pub name: String,
+            pub scrollable: bool,
+        }
+
+        impl Component for Div {
+            fn render(&mut self, context: RenderContext) -> Option<Vec<Renderable>> {
+                Some(vec![Renderable::Rect(Rect::new(
+                    context.aabb.pos,
+                    context.aabb.size(),
+                    Color::BLACK,
+                ))])
+            }
+
+            fn scroll_position(&self) -> Option<ScrollPosition> {
+                if self.scrollable {
+                    Some(ScrollPosition {
+                        x: Some(0.0),
+                        y: Some(50.0),
+                    })
+                } else {
+                    None
+                }
+            }
+        }
+
+        fn div(name: &str, scrollable: bool) -> Div {
+            Div {
+                name: name.to_string(),
+                scrollable,
+            }
+        }
+
+        pub fn view() -> Node {
+            node!(div("Top", false)).push(
+                node!(
+                    div("Scroll", true),
+                    lay![size: [100., 100.], direction: Direction::Row]
+                )
+                .push(
+                    node!(
+                        div("Column A", false),
+                        lay![size: [100., Auto], direction: Direction::Column],
+                        0
+                    )
+                    .push(node!(div("A1", false), lay![size: [Auto, 75.]], 0))
+                    .push(node!(div("A2", false), lay![size: [Auto, 75.]], 1)),
+                )
+                .push(
+                    node!(
+                        div("Column B", false),
+                        lay![size: [100., Auto], direction: Direction::Column],
+                        1
+                    )
+                    .push(node!(div("B1", false), lay![size: [Auto, 75.]], 0))
+                    .push(node!(div("B2", false), lay![size: [Auto, 75.]], 1)),
+                ),
+            )
+        }
+    }
+
+    #[test]
+    fn test_scroll() {
+        let harness = TestHarness::new(300, 300, test_scroll_app::view);
+        let root = harness.root();
+
+        // Expect the inner_scale to be a real size
+        let scroll_node = &root.children[0].children[0];
+        assert_eq!(scroll_node.aabb.size(), [100.0, 100.0].into());
+        assert_eq!(scroll_node.inner_scale.unwrap(), [200.0, 150.0].into());
+
+        // Children are offset by the scroll position
+        let a1 = &scroll_node.children[0].children[0];
+        assert_eq!(a1.aabb().pos.y, -50.0);
+
+        // Column B lies outside of the scroll frame, so it is neither rendered nor iterated
+        let column_b = &scroll_node.children[1];
+        assert_eq!(column_b.aabb().pos.x, 100.0);
+        assert!(column_b.render_cache.is_none());
+        assert!(column_b.children[0].render_cache.is_none());
+
+        // Expect renderables to be laid out in the right order, with the correct Frames
+        let renderables = root.iter_renderables().collect::<Vec<_>>();
+        assert_eq!(renderables.len(), 5);
+        // First two (Top Div, Scroll Div) do not have Frames
+        assert_eq!(renderables[0].2.len(), 0);
+        assert_eq!(renderables[1].2.len(), 0);
+        // The rest have Frames
+        assert_eq!(renderables[2].2.len(), 1);
+        assert_eq!(renderables[4].2.len(), 1);
+    }
+
+    #[test]
+    fn test_culling() {
+        let mut harness = TestHarness::new(100, 100, || {
+            let mut list = node!(
+                test_scroll_app::Div {
+                    name: "List".to_string(),
+                    scrollable: false,
+                },
+                lay![size: [100., Auto], direction: Direction::Column]
+            );
+            for i in 0..20 {
+                list = list.push(node!(
+                    test_scroll_app::Div {
+                        name: format!("Row {}", i),
+                        scrollable: false,
+                    },
+                    lay![size: [100., 10.]],
+                    i
+                ));
+            }
+            list
+        });
+
+        // Only the rows inside of the window are rendered
+        let rendered = |harness: &TestHarness<_, _>| {
+            let root = harness.root();
+            root.children[0]
+                .children
+                .iter()
+                .filter(|row| row.render_cache.is_some())
+                .count()
+        };
+        assert_eq!(rendered(&harness), 10);
+        // The list and its visible rows
+        assert_eq!(harness.root().iter_renderables().count(), 11);
+
+        // Rows that come into view are rendered
+        harness.resize(100, 150);
+        assert_eq!(rendered(&harness), 15);
+    }
+
+    mod test_registration_app {
+        use super::*;
+
+        #[derive(Debug)]
+        pub struct Registerer {
+            pub registration: event::Register,
+        }
+
+        impl Component for Registerer {
+            fn register(&mut self) -> Vec<event::Register> {
+                vec![self.registration]
+            }
+        }
+
+        #[derive(Debug, Default)]
+        pub struct TestApp {}
+
+        impl Component for TestApp {
+            fn view(&self) -> Option<Node> {
+                Some(
+                    node!(Registerer {
+                        registration: event::Register::KeyDown
+                    })
+                    .push(node!(Registerer {
+                        registration: event::Register::KeyUp,
+                    }))
+                    .push(node!(Registerer {
+                        registration: event::Register::KeyPress,
+                    })),
+                )
+            }
+        }
+    }
+
+    #[test]
+    fn test_registration() {
+        let mut n = Node::new(
+            Box::new(test_registration_app::TestApp::default()),
+            0,
+            Layout::default(),
+        );
+
+        let mut registrations: Vec<(event::Register, u64)> = vec![];
+        n.view(None, &mut registrations);
+        assert_eq!(registrations.len(), 3);
+        assert_eq!(registrations[0].0, event::Register::KeyUp);
+        assert_eq!(registrations[1].0, event::Register::KeyPress);
+        assert_eq!(registrations[2].0, event::Register::KeyDown);
+    }
+}
@@ -0,0 +1,176 @@
+use super::types::Canvas;
+use crate::renderer::software::{rounded_rect_path, SoftwareCanvas};
+use crate::types::{Color, Pos, Scale, Transform, AABB};
+use derive_builder::Builder;
+use femtovg::{ImageFilter, ImageFlags, ImageId, Paint, Path, PixelFormat, RenderTarget};
+use resvg::tiny_skia;
+
+/// Blurs whatever was drawn beneath a rounded rect, then tints it, for a frosted glass look.
+/// Only what was drawn into the same surface is blurred: inside of a layer, that is what the layer drew beneath the rect.
+#[derive(Clone, Copy, Debug, PartialEq, Builder)]
+pub struct Instance {
+    pub pos: Pos,
+    pub scale: Scale,
+    #[builder(default = "(0., 0., 0., 0.)")]
+    pub radius: (f32, f32, f32, f32),
+    /// Standard deviation of the blur, in physical pixels.
+    #[builder(default = "10.")]
+    pub blur: f32,
+    #[builder(default = "Color::TRANSPARENT")]
+    pub tint: Color,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Backdrop {
+    pub instance_data: Instance,
+}
+
+impl Backdrop {
+    pub fn new(pos: Pos, scale: Scale, blur: f32) -> Self {
+        Self {
+            instance_data: Instance {
+                pos,
+                scale,
+                radius: (0., 0., 0., 0.),
+                blur,
+                tint: Color::TRANSPARENT,
+            },
+        }
+    }
+
+    pub fn from_instance_data(instance_data: Instance) -> Self {
+        Self { instance_data }
+    }
+
+    /// How far past the rect the blur samples what is beneath it.
+    pub(crate) fn margin(&self) -> f32 {
+        (self.instance_data.blur * 3.).ceil()
+    }
+
+    /// Draw the backdrop, blurring the image `source` that is being drawn into. Without one, only the tint is drawn.
+    /// `transform` is the one that the canvas is currently drawing with.
+    /// Returns the images that were used along the way: they can only be deleted once the canvas has been flushed.
+    pub fn render(
+        &self,
+        canvas: &mut Canvas,
+        source: Option<ImageId>,
+        transform: Option<Transform>,
+    ) -> Vec<ImageId> {
+        let Instance {
+            pos,
+            scale,
+            radius,
+            blur,
+            tint,
+        } = self.instance_data;
+        let mut scratch = vec![];
+
+        if let Some(source) = source.filter(|_| blur > 0.) {
+            let transform = transform.unwrap_or_default();
+            let (source_width, source_height) = canvas
+                .image_size(source)
+                .map(|(w, h)| (w as f32, h as f32))
+                .unwrap_or((canvas.width() as f32, canvas.height() as f32));
+            // Rotated backdrops blur their bounding box
+            let rect = transform.apply_aabb(AABB::new(pos, scale));
+            let frame = AABB::new(Pos::default(), Scale::new(source_width, source_height));
+            if let Some(bounds) = rect.expand(self.margin()).intersection(&frame) {
+                let bounds = bounds.round();
+                let (width, height) = (bounds.width() as usize, bounds.height() as usize);
+                let flags = ImageFlags::FLIP_Y | ImageFlags::PREMULTIPLIED;
+                if let (Ok(crop), Ok(blurred)) = (
+                    canvas.create_image_empty(width, height, PixelFormat::Rgba8, flags),
+                    canvas.create_image_empty(width, height, PixelFormat::Rgba8, flags),
+                ) {
+                    scratch = vec![crop, blurred];
+
+                    canvas.save();
+                    canvas.reset_transform();
+                    canvas.reset_scissor();
+                    canvas.set_render_target(RenderTarget::Image(crop));
+                    let mut path = Path::new();
+                    path.rect(0., 0., width as f32, height as f32);
+                    let paint = Paint::image(
+                        source,
+                        -bounds.pos.x,
+                        -bounds.pos.y,
+                        source_width,
+                        source_height,
+                        0.0,
+                        1.0,
+                    );
+                    canvas.fill_path(&path, &paint);
+                    canvas.filter_image(blurred, ImageFilter::GaussianBlur { sigma: blur }, crop);
+                    canvas.set_render_target(RenderTarget::Image(source));
+                    canvas.restore();
+
+                    // The blurred image lines up with the window, not the Node
+                    let scale = (transform.a * transform.d - transform.b * transform.c)
+                        .abs()
+                        .sqrt();
+                    let mut path = Path::new();
+                    path.rounded_rect_varying(
+                        rect.pos.x,
+                        rect.pos.y,
+                        rect.width(),
+                        rect.height(),
+                        radius.0 * scale,
+                        radius.1 * scale,
+                        radius.2 * scale,
+                        radius.3 * scale,
+                    );
+                    let paint = Paint::image(
+                        blurred,
+                        bounds.pos.x,
+                        bounds.pos.y,
+                        width as f32,
+                        height as f32,
+                        0.0,
+                        1.0,
+                    );
+                    canvas.save();
+                    canvas.reset_transform();
+                    canvas.global_composite_operation(femtovg::CompositeOperation::DestinationOut);
+                    canvas.fill_path(&path, &Paint::color(Color::BLACK.into()));
+                    canvas.global_composite_operation(femtovg::CompositeOperation::SourceOver);
+                    canvas.fill_path(&path, &paint);
+                    canvas.restore();
+                }
+            }
+        }
+
+        let mut path = Path::new();
+        path.rounded_rect_varying(
+            pos.x,
+            pos.y,
+            scale.width,
+            scale.height,
+            radius.0,
+            radius.1,
+            radius.2,
+            radius.3,
+        );
+        canvas.fill_path(&path, &Paint::color(tint.into()));
+        scratch
+    }
+
+    pub fn render_software(&self, canvas: &mut SoftwareCanvas) {
+        let Instance {
+            pos,
+            scale,
+            radius,
+            blur,
+            tint,
+        } = self.instance_data;
+        let Some(path) = rounded_rect_path(pos.x, pos.y, scale.width, scale.height, radius) else {
+            return;
+        };
+
+        canvas.blur_behind(&path, blur);
+
+        let mut paint = tiny_skia::Paint::default();
+        paint.set_color(tint.into());
+        paint.anti_alias = true;
+        canvas.fill_path(&path, &paint);
+    }
+}
@@ -1,9 +1,11 @@
+use crate::renderer::software::SoftwareCanvas;
 use crate::{Color, Pos};
 
 use super::types;
 use super::types::Canvas;
 use derive_builder::Builder;
 use femtovg::{ImageId, Paint, Path};
+use resvg::tiny_skia;
 
 #[derive(Clone, Copy, Default, Debug, PartialEq, Builder)]
 pub struct Instance {
@@ -68,4 +70,36 @@ impl Circle {
             canvas.stroke_path(&path, &stroke);
         }
     }
+
+    pub fn render_software(&self, canvas: &mut SoftwareCanvas) {
+        let Instance {
+            origin,
+            radius,
+            color,
+            border_color,
+            border_width,
+            ..
+        } = self.instance_data;
+        let Some(path) = tiny_skia::PathBuilder::from_circle(origin.x, origin.y, radius) else {
+            return;
+        };
+
+        if let Some(color) = color {
+            let mut paint = tiny_skia::Paint::default();
+            paint.set_color(color.into());
+            paint.anti_alias = true;
+            canvas.fill_path(&path, &paint);
+        }
+
+        if let Some(color) = border_color {
+            let mut paint = tiny_skia::Paint::default();
+            paint.set_color(color.into());
+            paint.anti_alias = true;
+            let stroke = tiny_skia::Stroke {
+                width: border_width,
+                ..Default::default()
+            };
+            canvas.stroke_path(&path, &paint, &stroke);
+        }
+    }
 }
@@ -1,9 +1,11 @@
+use crate::renderer::software::SoftwareCanvas;
 use crate::{Color, Point, Pos};
 
 use super::types;
 use super::types::Canvas;
 use derive_builder::Builder;
 use femtovg::{LineCap, LineJoin, Paint, Path};
+use resvg::tiny_skia;
 
 #[derive(Clone, Default, Debug, PartialEq, Builder)]
 pub struct Instance {
@@ -78,4 +80,47 @@ impl Curve {
         }
         canvas.stroke_path(&path, &line);
     }
+
+    pub fn render_software(&self, canvas: &mut SoftwareCanvas) {
+        let Instance {
+            anchors,
+            color,
+            width,
+            anchor_width,
+            anchor_color,
+        } = &self.instance_data;
+
+        if anchors.len() <= 1 {
+            return;
+        }
+
+        //draw anchors
+        let mut paint = tiny_skia::Paint::default();
+        paint.set_color((*anchor_color).into());
+        paint.anti_alias = true;
+        for anchor in anchors {
+            if let Some(path) =
+                tiny_skia::PathBuilder::from_circle(anchor.x, anchor.y, *anchor_width)
+            {
+                canvas.fill_path(&path, &paint);
+            }
+        }
+
+        //draw curve
+        let mut pb = tiny_skia::PathBuilder::new();
+        pb.move_to(anchors[0].x, anchors[0].y);
+        for anchor in &anchors[1..] {
+            pb.line_to(anchor.x, anchor.y);
+        }
+        if let Some(path) = pb.finish() {
+            let mut paint = tiny_skia::Paint::default();
+            paint.set_color((*color).into());
+            paint.anti_alias = true;
+            let stroke = tiny_skia::Stroke {
+                width: *width,
+                ..Default::default()
+            };
+            canvas.stroke_path(&path, &paint, &stroke);
+        }
+    }
 }
@@ -1,15 +1,56 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use crate::{Pos, Scale};
+use crate::assets::{self, RuntimeImages};
+use crate::{Color, Pos, Scale, AABB};
 
 use super::types;
 use super::types::Canvas;
+use crate::renderer::software::{
+    load_image_file, pixmap_from_image, rounded_rect_path, SoftwareCanvas,
+};
 use derive_builder::Builder;
-use femtovg::{CompositeOperation, ImageFlags, ImageId, Paint, Path};
+use femtovg::{CompositeOperation, ImageFlags, ImageId, ImageSource, Paint, Path};
+use image::DynamicImage;
+use resvg::tiny_skia::{self, Pixmap};
 
 type Point = types::Point<f32>;
 type Size = types::Size<f32>;
 
+/// How an image is sized to the rect that it is drawn in, like CSS's `object-fit`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ImageFit {
+    /// Stretched to fill the rect.
+    #[default]
+    Fill,
+    /// Scaled to fit within the rect, keeping its aspect ratio.
+    Contain,
+    /// Scaled to cover the rect, keeping its aspect ratio. What falls outside of the rect is cut off.
+    Cover,
+    /// Drawn at its own size, one image pixel per physical pixel.
+    None,
+    /// Like `None`, or `Contain` when that is smaller.
+    ScaleDown,
+}
+
+/// Stretches the center of an image while keeping its borders intact, e.g. for skinnable backgrounds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NinePatch {
+    /// Where the stretched center starts, from the top, right, bottom and left edges of the image, in image pixels.
+    pub insets: (f32, f32, f32, f32),
+    /// How many physical pixels a pixel of the borders is drawn with, e.g. the scale factor of the window.
+    pub scale: f32,
+}
+
+/// A part of the source image, that is drawn into a part of the frame.
+#[derive(Debug, PartialEq)]
+struct Cell {
+    src: AABB,
+    dst: AABB,
+    /// The part of `dst` that is drawn.
+    visible: AABB,
+}
+
 #[derive(Clone, Debug, PartialEq, Builder)]
 pub struct Instance {
     pub name: String,
@@ -21,6 +62,27 @@ pub struct Instance {
     pub radius: f32,
     #[builder(default = "None")]
     pub dynamic_load_from: Option<String>,
+    /// Decoded pixels that are loaded under `name` the first time it is drawn, e.g. the frames of an animation.
+    #[builder(default = "None")]
+    pub data: Option<Arc<DynamicImage>>,
+    #[builder(default = "ImageFit::Fill")]
+    pub fit: ImageFit,
+    /// Where the image is placed when it doesn't fill the rect exactly, from 0.0 (left/top) to 1.0 (right/bottom).
+    #[builder(default = "(0.5, 0.5)")]
+    pub align: (f32, f32),
+    /// The part of the image that is drawn, in image pixels. The whole image when `None`.
+    #[builder(default = "None")]
+    pub crop: Option<AABB>,
+    #[builder(default = "false")]
+    pub flip_x: bool,
+    #[builder(default = "false")]
+    pub flip_y: bool,
+    /// Multiplies the colors of the image.
+    #[builder(default = "None")]
+    pub tint: Option<Color>,
+    /// Draw the image as a nine-patch, which always fills the rect. `fit` and `align` are then ignored.
+    #[builder(default = "None")]
+    pub nine_patch: Option<NinePatch>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -38,6 +100,14 @@ impl Image {
                 composite_operation: CompositeOperation::SourceOver,
                 radius: Default::default(),
                 dynamic_load_from: Default::default(),
+                data: Default::default(),
+                fit: ImageFit::Fill,
+                align: (0.5, 0.5),
+                crop: None,
+                flip_x: false,
+                flip_y: false,
+                tint: None,
+                nine_patch: None,
             },
         }
     }
@@ -47,41 +117,472 @@ impl Image {
         self
     }
 
-    pub fn render(&self, canvas: &mut Canvas, assets: &mut HashMap<String, ImageId>) {
+    /// Where the parts of an image of the given size are drawn.
+    fn cells(&self, image_width: f32, image_height: f32) -> Vec<Cell> {
         let Instance {
             pos,
             scale,
+            fit,
+            align,
+            crop,
+            flip_x,
+            flip_y,
+            nine_patch,
+            ..
+        } = self.instance_data;
+        let rect = AABB::new(pos, scale);
+        let src = crop
+            .and_then(|crop| {
+                crop.intersection(&AABB::new(
+                    Pos::default(),
+                    Scale::new(image_width, image_height),
+                ))
+            })
+            .unwrap_or(AABB::new(
+                Pos::default(),
+                Scale::new(image_width, image_height),
+            ));
+        if src.is_empty() || rect.is_empty() {
+            return vec![];
+        }
+
+        if let Some(NinePatch {
+            insets: (top, right, bottom, left),
+            scale: border_scale,
+        }) = nine_patch
+        {
+            // Borders shrink when the rect is too small for them
+            let shrink = |a: f32, b: f32, size: f32| {
+                let total = (a + b) * border_scale;
+                if total > size && total > 0. {
+                    size / total * border_scale
+                } else {
+                    border_scale
+                }
+            };
+            let kx = shrink(left, right, rect.width());
+            let ky = shrink(top, bottom, rect.height());
+            let columns = [
+                (
+                    src.pos.x,
+                    src.pos.x + left,
+                    rect.pos.x,
+                    rect.pos.x + left * kx,
+                ),
+                (
+                    src.pos.x + left,
+                    src.bottom_right.x - right,
+                    rect.pos.x + left * kx,
+                    rect.bottom_right.x - right * kx,
+                ),
+                (
+                    src.bottom_right.x - right,
+                    src.bottom_right.x,
+                    rect.bottom_right.x - right * kx,
+                    rect.bottom_right.x,
+                ),
+            ];
+            let rows = [
+                (
+                    src.pos.y,
+                    src.pos.y + top,
+                    rect.pos.y,
+                    rect.pos.y + top * ky,
+                ),
+                (
+                    src.pos.y + top,
+                    src.bottom_right.y - bottom,
+                    rect.pos.y + top * ky,
+                    rect.bottom_right.y - bottom * ky,
+                ),
+                (
+                    src.bottom_right.y - bottom,
+                    src.bottom_right.y,
+                    rect.bottom_right.y - bottom * ky,
+                    rect.bottom_right.y,
+                ),
+            ];
+            // Every cell is flipped where it is drawn, so flipped cells trade places as well
+            let aabb = |x0: f32, x1: f32, y0: f32, y1: f32| {
+                AABB::new(Pos::new(x0, y0, pos.z), Scale::new(x1 - x0, y1 - y0))
+            };
+            let mirror = |a: f32, b: f32, lo: f32, hi: f32, flip: bool| {
+                if flip {
+                    (lo + hi - b, lo + hi - a)
+                } else {
+                    (a, b)
+                }
+            };
+            return rows
+                .iter()
+                .flat_map(|&(sy0, sy1, dy0, dy1)| {
+                    columns.iter().map(move |&(sx0, sx1, dx0, dx1)| {
+                        let (dx0, dx1) = mirror(dx0, dx1, rect.pos.x, rect.bottom_right.x, flip_x);
+                        let (dy0, dy1) = mirror(dy0, dy1, rect.pos.y, rect.bottom_right.y, flip_y);
+                        let dst = aabb(dx0, dx1, dy0, dy1);
+                        Cell {
+                            src: aabb(sx0, sx1, sy0, sy1),
+                            dst,
+                            visible: dst,
+                        }
+                    })
+                })
+                .filter(|cell| !cell.src.is_empty() && !cell.dst.is_empty())
+                .collect();
+        }
+
+        let (sx, sy) = (rect.width() / src.width(), rect.height() / src.height());
+        let (sx, sy) = match fit {
+            ImageFit::Fill => (sx, sy),
+            ImageFit::Contain => (sx.min(sy), sx.min(sy)),
+            ImageFit::Cover => (sx.max(sy), sx.max(sy)),
+            ImageFit::None => (1., 1.),
+            ImageFit::ScaleDown => (sx.min(sy).min(1.), sx.min(sy).min(1.)),
+        };
+        let size = Scale::new(src.width() * sx, src.height() * sy);
+        let dst = AABB::new(
+            Pos::new(
+                pos.x + (scale.width - size.width) * align.0,
+                pos.y + (scale.height - size.height) * align.1,
+                pos.z,
+            ),
+            size,
+        );
+        match dst.intersection(&rect) {
+            Some(visible) => vec![Cell { src, dst, visible }],
+            None => vec![],
+        }
+    }
+
+    /// The image to draw: one that the renderer was created with, or else one that is loaded at runtime,
+    /// from `data`, the [registry][crate::assets] or `dynamic_load_from`, in that order.
+    fn runtime_source(&self) -> (Option<Arc<DynamicImage>>, u64) {
+        match &self.instance_data.data {
+            Some(data) => (Some(data.clone()), 0),
+            None => assets::image(&self.instance_data.name)
+                .map_or((None, 0), |(image, version)| (Some(image), version)),
+        }
+    }
+
+    pub(crate) fn render(
+        &self,
+        canvas: &mut Canvas,
+        assets: &mut HashMap<String, ImageId>,
+        runtime_images: &mut RuntimeImages<ImageId>,
+    ) {
+        let Instance {
+            name,
             composite_operation,
             radius,
             dynamic_load_from,
             ..
-        } = self.instance_data.clone();
+        } = &self.instance_data;
 
-        canvas.global_composite_operation(composite_operation);
+        let (data, version) = self.runtime_source();
+        let image_id = match assets.get(name) {
+            Some(image_id) => Some(*image_id),
+            None => match runtime_images.get(name, version) {
+                Some(image_id) => Some(*image_id),
+                None => {
+                    let image_id = match (data, dynamic_load_from) {
+                        (Some(data), _) => ImageSource::try_from(data.as_ref())
+                            .ok()
+                            .and_then(|src| canvas.create_image(src, ImageFlags::empty()).ok()),
+                        //Load image dynamically
+                        (None, Some(path)) => {
+                            canvas.load_image_file(path, ImageFlags::empty()).ok()
+                        }
+                        (None, None) => return,
+                    };
+                    match image_id {
+                        Some(image_id) => {
+                            let (width, height) = canvas.image_size(image_id).unwrap_or_default();
+                            runtime_images.insert(name, version, image_id, width * height * 4);
+                        }
+                        None => println!("Error while creating image {:?}", name),
+                    }
+                    image_id
+                }
+            },
+        };
 
-        //Load image dynamically
-        if assets.get(&self.instance_data.name).is_none() && dynamic_load_from.is_some() {
-            let path = dynamic_load_from.unwrap();
-            let image_load_r = canvas.load_image_file(path, ImageFlags::empty());
-            if let Ok(image_id) = image_load_r {
-                assets.insert(self.instance_data.name.clone(), image_id);
-            }
-        }
+        let Some(image_id) = image_id else {
+            return;
+        };
+        let Ok((image_width, image_height)) = canvas.image_size(image_id) else {
+            return;
+        };
+        let (image_width, image_height) = (image_width as f32, image_height as f32);
+        let Instance {
+            flip_x,
+            flip_y,
+            tint,
+            ..
+        } = self.instance_data;
 
-        if let Some(image_id) = assets.get(&self.instance_data.name) {
-            let Pos { x, y, z } = pos;
-            let Scale { width, height } = scale;
+        canvas.global_composite_operation(*composite_operation);
+        for Cell { src, dst, visible } in self.cells(image_width, image_height) {
+            let (kx, ky) = (dst.width() / src.width(), dst.height() / src.height());
+            let (cx, cy) = (
+                (dst.pos.x + dst.bottom_right.x) / 2.,
+                (dst.pos.y + dst.bottom_right.y) / 2.,
+            );
+            // Flips mirror the canvas around the center of the cell, so the visible part is mirrored to match
+            let mirror = |a: f32, b: f32, c: f32, flip: bool| {
+                if flip {
+                    (2. * c - b, 2. * c - a)
+                } else {
+                    (a, b)
+                }
+            };
+            let (x0, x1) = mirror(visible.pos.x, visible.bottom_right.x, cx, flip_x);
+            let (y0, y1) = mirror(visible.pos.y, visible.bottom_right.y, cy, flip_y);
 
-            let paint = Paint::image(*image_id, x, y, width, height, 0.0, 1.0);
+            canvas.save();
+            if flip_x || flip_y {
+                canvas.translate(cx, cy);
+                canvas.scale(if flip_x { -1. } else { 1. }, if flip_y { -1. } else { 1. });
+                canvas.translate(-cx, -cy);
+            }
+            let (px, py, pw, ph) = (
+                dst.pos.x - src.pos.x * kx,
+                dst.pos.y - src.pos.y * ky,
+                image_width * kx,
+                image_height * ky,
+            );
+            let paint = match tint {
+                Some(tint) => Paint::image_tint(image_id, px, py, pw, ph, 0.0, tint.into()),
+                None => Paint::image(image_id, px, py, pw, ph, 0.0, 1.0),
+            };
             let mut path = Path::new();
-            path.rounded_rect(x, y, width, height, radius);
+            let radius = if self.instance_data.nine_patch.is_some() {
+                0.
+            } else {
+                *radius
+            };
+            path.rounded_rect(x0, y0, x1 - x0, y1 - y0, radius);
             canvas.fill_path(&path, &paint);
+            canvas.restore();
         }
-
         canvas.global_composite_operation(CompositeOperation::SourceOver);
     }
 
     pub fn from_instance_data(instance_data: Instance) -> Self {
         Self { instance_data }
     }
+
+    pub(crate) fn render_software(
+        &self,
+        canvas: &mut SoftwareCanvas,
+        assets: &mut HashMap<String, Pixmap>,
+        runtime_images: &mut RuntimeImages<Pixmap>,
+    ) {
+        let Instance {
+            name,
+            composite_operation,
+            radius,
+            dynamic_load_from,
+            ..
+        } = self.instance_data.clone();
+
+        let (data, version) = self.runtime_source();
+        if !assets.contains_key(&name) && runtime_images.get(&name, version).is_none() {
+            let pixmap = match (data, &dynamic_load_from) {
+                (Some(data), _) => pixmap_from_image(&data),
+                //Load image dynamically
+                (None, Some(path)) => load_image_file(path),
+                (None, None) => None,
+            };
+            if let Some(pixmap) = pixmap {
+                let bytes = pixmap.data().len();
+                runtime_images.insert(&name, version, pixmap, bytes);
+            }
+        }
+
+        // Tinted copies are kept along with the images that were loaded while drawing, rather than tinted on every draw
+        let tinted_name = self
+            .instance_data
+            .tint
+            .map(|tint| format!("{}#tint{:08x}", name, u32::from(tint)));
+        if let (Some(tint), Some(tinted_name)) = (self.instance_data.tint, &tinted_name) {
+            if runtime_images.get(tinted_name, version).is_none() {
+                let source = match assets.get(&name) {
+                    Some(pixmap) => Some(pixmap),
+                    None => runtime_images.get(&name, 0),
+                };
+                if let Some(tinted) = source.map(|pixmap| tint_pixmap(pixmap, tint)) {
+                    let bytes = tinted.data().len();
+                    runtime_images.insert(tinted_name, version, tinted, bytes);
+                }
+            }
+        }
+
+        let name = tinted_name.as_ref().unwrap_or(&name);
+        let pixmap = match assets.get(name) {
+            Some(pixmap) => pixmap,
+            None => match runtime_images.get(name, 0) {
+                Some(pixmap) => pixmap,
+                None => return,
+            },
+        };
+        let radius = if self.instance_data.nine_patch.is_some() {
+            0.
+        } else {
+            radius
+        };
+
+        canvas.global_composite_operation(composite_operation);
+        for Cell { src, dst, visible } in self.cells(pixmap.width() as f32, pixmap.height() as f32)
+        {
+            let Some(path) = rounded_rect_path(
+                visible.pos.x,
+                visible.pos.y,
+                visible.width(),
+                visible.height(),
+                (radius, radius, radius, radius),
+            ) else {
+                continue;
+            };
+            let (kx, ky) = (dst.width() / src.width(), dst.height() / src.height());
+            let (sx, tx) = if self.instance_data.flip_x {
+                (-kx, dst.bottom_right.x + src.pos.x * kx)
+            } else {
+                (kx, dst.pos.x - src.pos.x * kx)
+            };
+            let (sy, ty) = if self.instance_data.flip_y {
+                (-ky, dst.bottom_right.y + src.pos.y * ky)
+            } else {
+                (ky, dst.pos.y - src.pos.y * ky)
+            };
+
+            let mut paint = tiny_skia::Paint::default();
+            paint.shader = tiny_skia::Pattern::new(
+                pixmap.as_ref(),
+                tiny_skia::SpreadMode::Pad,
+                tiny_skia::FilterQuality::Bilinear,
+                1.0,
+                tiny_skia::Transform::from_row(sx, 0., 0., sy, tx, ty),
+            );
+            paint.anti_alias = true;
+            canvas.fill_path(&path, &paint);
+        }
+        canvas.global_composite_operation(CompositeOperation::SourceOver);
+    }
+}
+
+/// A copy of `pixmap` with its colors multiplied by `tint`.
+fn tint_pixmap(pixmap: &Pixmap, tint: Color) -> Pixmap {
+    let mut tinted = pixmap.clone();
+    // Premultiplied colors are multiplied by the alpha of the tint as well
+    let factors = [
+        tint.r / 255. * tint.a,
+        tint.g / 255. * tint.a,
+        tint.b / 255. * tint.a,
+        tint.a,
+    ];
+    for pixel in tinted.data_mut().chunks_exact_mut(4) {
+        for (c, f) in pixel.iter_mut().zip(factors) {
+            *c = (*c as f32 * f).round() as u8;
+        }
+    }
+    tinted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::TestHarness;
+    use crate::widgets;
+    use crate::{lay, node, size};
+
+    #[test]
+    fn test_image_fit() {
+        use crate::assets::AssetState;
+        use crate::style::{HorizontalPosition, VerticalPosition};
+        use image::{ImageOutputFormat, Rgba, RgbaImage};
+
+        let register = |name: &str, colors: &[[u8; 4]]| {
+            let mut image = RgbaImage::new(colors.len() as u32, 1);
+            for (x, color) in colors.iter().enumerate() {
+                image.put_pixel(x as u32, 0, Rgba(*color));
+            }
+            let mut png = vec![];
+            DynamicImage::ImageRgba8(image)
+                .write_to(&mut std::io::Cursor::new(&mut png), ImageOutputFormat::Png)
+                .unwrap();
+            assets::register_bytes(name, png);
+            for _ in 0..500 {
+                if assets::state(name) == Some(AssetState::Loaded) {
+                    return;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+            panic!("{} did not load", name);
+        };
+        const RED: [u8; 4] = [255, 0, 0, 255];
+        const GREEN: [u8; 4] = [0, 255, 0, 255];
+        const BLUE: [u8; 4] = [0, 0, 255, 255];
+        register("test_fit", &[RED, BLUE]);
+        register("test_fit_nine_patch", &[RED, GREEN, BLUE]);
+
+        // The pixels at the given points of a 40x20 Image
+        let draw = |image: fn() -> widgets::Image, points: &[(u32, u32)]| {
+            let mut harness = TestHarness::single(move || node!(image(), lay![size: [40., 20.]]));
+            let pixmap = harness.render().pixmap();
+            points
+                .iter()
+                .map(|&(x, y)| {
+                    let p = pixmap.pixel(x, y).unwrap();
+                    [p.red(), p.green(), p.blue(), p.alpha()]
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            draw(|| widgets::Image::new("test_fit"), &[(5, 10), (35, 10)]),
+            vec![RED, BLUE]
+        );
+        // Contained in the top left corner, at 20x10
+        assert_eq!(
+            draw(
+                || widgets::Image::new("test_fit")
+                    .fit(ImageFit::Contain)
+                    .align(HorizontalPosition::Left, VerticalPosition::Top),
+                &[(2, 5), (17, 5), (30, 15)]
+            ),
+            vec![RED, BLUE, [0, 0, 0, 0]]
+        );
+        assert_eq!(
+            draw(
+                || widgets::Image::new("test_fit").flip_x(true),
+                &[(5, 10), (35, 10)]
+            ),
+            vec![BLUE, RED]
+        );
+        assert_eq!(
+            draw(
+                || widgets::Image::new("test_fit")
+                    .crop(AABB::new(Pos::new(1., 0., 0.), Scale::new(1., 1.))),
+                &[(20, 10), (35, 10)]
+            ),
+            vec![BLUE, BLUE]
+        );
+        let tinted = draw(
+            || widgets::Image::new("test_fit").tint(Color::rgba(255., 255., 255., 0.5)),
+            &[(5, 10)],
+        )[0];
+        assert_eq!((tinted[1], tinted[2]), (0, 0));
+        assert!((120..136).contains(&tinted[3]));
+        // The 1px borders keep their size, while the middle is stretched
+        assert_eq!(
+            draw(
+                || widgets::Image::new("test_fit_nine_patch").nine_patch(0., 1., 0., 1.),
+                &[(0, 10), (20, 10), (39, 10)]
+            ),
+            vec![RED, GREEN, BLUE]
+        );
+
+        assets::unregister("test_fit");
+        assets::unregister("test_fit_nine_patch");
+    }
 }
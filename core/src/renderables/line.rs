@@ -1,9 +1,11 @@
+use crate::renderer::software::SoftwareCanvas;
 use crate::{Color, Pos};
 
 use super::types;
 use super::types::Canvas;
 use derive_builder::Builder;
 use femtovg::{LineCap, LineJoin, Paint, Path};
+use resvg::tiny_skia;
 
 #[derive(Clone, Copy, Default, Debug, PartialEq, Builder)]
 pub struct Instance {
@@ -54,4 +56,30 @@ impl Line {
         paint.set_line_width(width);
         canvas.stroke_path(&path, &paint);
     }
+
+    pub fn render_software(&self, canvas: &mut SoftwareCanvas) {
+        let Instance {
+            from,
+            to,
+            color,
+            width,
+        } = self.instance_data;
+        let mut pb = tiny_skia::PathBuilder::new();
+        pb.move_to(from.x, from.y);
+        pb.line_to(to.x, to.y);
+        let Some(path) = pb.finish() else {
+            return;
+        };
+
+        let mut paint = tiny_skia::Paint::default();
+        paint.set_color(color.into());
+        paint.anti_alias = true;
+        let stroke = tiny_skia::Stroke {
+            width,
+            line_cap: tiny_skia::LineCap::Round,
+            line_join: tiny_skia::LineJoin::Miter,
+            ..Default::default()
+        };
+        canvas.stroke_path(&path, &paint, &stroke);
+    }
 }
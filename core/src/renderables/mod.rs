@@ -1,21 +1,27 @@
+pub mod backdrop;
 pub mod circle;
 pub mod curve;
 pub mod image;
 pub mod line;
+pub mod path;
 pub mod radial_gradient;
 pub mod rect;
 pub mod svg;
 pub mod text;
+pub mod texture;
 pub mod types;
 
+pub use backdrop::Backdrop;
 pub use circle::Circle;
 pub use curve::Curve;
 pub use image::Image;
 pub use line::Line;
+pub use path::Path;
 pub use radial_gradient::RadialGradient;
 pub use rect::Rect;
 pub use svg::Svg;
 pub use text::Text;
+pub use texture::Texture;
 
 #[derive(Debug, Clone)]
 pub enum Renderable {
@@ -27,4 +33,23 @@ pub enum Renderable {
     Svg(Svg),
     RadialGradient(RadialGradient),
     Curve(Curve),
+    Path(Path),
+    Backdrop(Backdrop),
+    Texture(Texture),
+}
+
+impl Renderable {
+    /// How far the renderable can draw past the [`AABB`][crate::types::AABB] of its Node, e.g. with a shadow.
+    pub(crate) fn overflow(&self) -> f32 {
+        match self {
+            Renderable::Rect(rect) => rect
+                .instance_data
+                .shadows
+                .iter()
+                .map(|s| s.extent())
+                .fold(0., f32::max),
+            Renderable::Text(text) => text.instance_data.shadow.map_or(0., |s| s.extent()),
+            _ => 0.,
+        }
+    }
 }
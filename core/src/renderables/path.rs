@@ -0,0 +1,337 @@
+use super::rect::Gradient;
+use super::types::Canvas;
+use crate::renderer::software::{path_from_commands, SoftwareCanvas};
+use crate::types::{Color, PathCommand, Point};
+use derive_builder::Builder;
+use femtovg::Paint;
+use resvg::tiny_skia;
+
+/// Which areas are inside of a [`Path`] that crosses itself.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FillRule {
+    #[default]
+    NonZero,
+    EvenOdd,
+}
+
+/// How the ends of a stroke are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+/// How the corners of a stroke are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LineJoin {
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+/// What a [`Path`] is filled or stroked with.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathPaint {
+    Color(Color),
+    /// A gradient in physical pixels, like the path itself.
+    Gradient(Gradient),
+}
+
+impl From<Color> for PathPaint {
+    fn from(c: Color) -> Self {
+        Self::Color(c)
+    }
+}
+
+impl From<Gradient> for PathPaint {
+    fn from(g: Gradient) -> Self {
+        Self::Gradient(g)
+    }
+}
+
+impl PathPaint {
+    fn to_paint(&self) -> Paint {
+        match self {
+            PathPaint::Color(c) => Paint::color((*c).into()),
+            PathPaint::Gradient(g) => g.to_paint(),
+        }
+    }
+
+    fn to_software_paint(&self) -> tiny_skia::Paint<'static> {
+        let mut paint = tiny_skia::Paint::default();
+        match self {
+            PathPaint::Color(c) => paint.set_color((*c).into()),
+            PathPaint::Gradient(g) => {
+                if let Some(shader) = g.to_shader() {
+                    paint.shader = shader;
+                }
+            }
+        }
+        paint.anti_alias = true;
+        paint
+    }
+}
+
+/// The outline of a [`Path`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stroke {
+    pub paint: PathPaint,
+    pub width: f32,
+    pub cap: LineCap,
+    pub join: LineJoin,
+    pub miter_limit: f32,
+    /// Alternating lengths of dashes and gaps. The stroke is solid when this is empty.
+    pub dash: Vec<f32>,
+    /// How far into the dash pattern the stroke starts.
+    pub dash_offset: f32,
+}
+
+impl Stroke {
+    pub fn new<P: Into<PathPaint>>(paint: P, width: f32) -> Self {
+        Self {
+            paint: paint.into(),
+            width,
+            cap: LineCap::default(),
+            join: LineJoin::default(),
+            miter_limit: 4.,
+            dash: vec![],
+            dash_offset: 0.,
+        }
+    }
+
+    pub fn cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    pub fn miter_limit(mut self, miter_limit: f32) -> Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    pub fn dash(mut self, dash: Vec<f32>, offset: f32) -> Self {
+        self.dash = dash;
+        self.dash_offset = offset;
+        self
+    }
+
+    fn to_software_stroke(&self) -> tiny_skia::Stroke {
+        tiny_skia::Stroke {
+            width: self.width,
+            miter_limit: self.miter_limit,
+            line_cap: match self.cap {
+                LineCap::Butt => tiny_skia::LineCap::Butt,
+                LineCap::Round => tiny_skia::LineCap::Round,
+                LineCap::Square => tiny_skia::LineCap::Square,
+            },
+            line_join: match self.join {
+                LineJoin::Miter => tiny_skia::LineJoin::Miter,
+                LineJoin::Round => tiny_skia::LineJoin::Round,
+                LineJoin::Bevel => tiny_skia::LineJoin::Bevel,
+            },
+            dash: tiny_skia::StrokeDash::new(self.dash.clone(), self.dash_offset),
+        }
+    }
+}
+
+/// An arbitrary vector shape, built out of [`PathCommand`]s in physical pixels, that is filled, stroked, or both.
+#[derive(Clone, Debug, PartialEq, Builder)]
+pub struct Instance {
+    pub commands: Vec<PathCommand>,
+    #[builder(default = "None")]
+    pub fill: Option<PathPaint>,
+    #[builder(default = "FillRule::NonZero")]
+    pub fill_rule: FillRule,
+    #[builder(default = "None")]
+    pub stroke: Option<Stroke>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Path {
+    pub instance_data: Instance,
+}
+
+impl Path {
+    pub fn new(commands: Vec<PathCommand>) -> Self {
+        Self {
+            instance_data: Instance {
+                commands,
+                fill: None,
+                fill_rule: FillRule::NonZero,
+                stroke: None,
+            },
+        }
+    }
+
+    pub fn from_instance_data(instance_data: Instance) -> Self {
+        Self { instance_data }
+    }
+
+    pub fn render(&self, canvas: &mut Canvas) {
+        let Instance {
+            commands,
+            fill,
+            fill_rule,
+            stroke,
+        } = &self.instance_data;
+
+        if let Some(fill) = fill {
+            let mut paint = fill.to_paint();
+            paint.set_fill_rule(match fill_rule {
+                FillRule::NonZero => femtovg::FillRule::NonZero,
+                FillRule::EvenOdd => femtovg::FillRule::EvenOdd,
+            });
+            canvas.fill_path(&femtovg_path(commands), &paint);
+        }
+
+        if let Some(stroke) = stroke {
+            // femtovg has no dashes of its own, so the dashed outline is built by tiny-skia
+            let path = if stroke.dash.is_empty() {
+                Some(femtovg_path(commands))
+            } else {
+                path_from_commands(commands)
+                    .and_then(|p| p.dash(&stroke.to_software_stroke().dash?, 1.))
+                    .map(|p| femtovg_path(&commands_from_path(&p)))
+            };
+            let Some(path) = path else {
+                return;
+            };
+            let mut paint = stroke.paint.to_paint();
+            paint.set_line_width(stroke.width);
+            paint.set_miter_limit(stroke.miter_limit);
+            paint.set_line_cap(match stroke.cap {
+                LineCap::Butt => femtovg::LineCap::Butt,
+                LineCap::Round => femtovg::LineCap::Round,
+                LineCap::Square => femtovg::LineCap::Square,
+            });
+            paint.set_line_join(match stroke.join {
+                LineJoin::Miter => femtovg::LineJoin::Miter,
+                LineJoin::Round => femtovg::LineJoin::Round,
+                LineJoin::Bevel => femtovg::LineJoin::Bevel,
+            });
+            canvas.stroke_path(&path, &paint);
+        }
+    }
+
+    pub fn render_software(&self, canvas: &mut SoftwareCanvas) {
+        let Instance {
+            commands,
+            fill,
+            fill_rule,
+            stroke,
+        } = &self.instance_data;
+        let Some(path) = path_from_commands(commands) else {
+            return;
+        };
+
+        if let Some(fill) = fill {
+            let fill_rule = match fill_rule {
+                FillRule::NonZero => tiny_skia::FillRule::Winding,
+                FillRule::EvenOdd => tiny_skia::FillRule::EvenOdd,
+            };
+            canvas.fill_path_with_rule(&path, &fill.to_software_paint(), fill_rule);
+        }
+
+        if let Some(stroke) = stroke {
+            canvas.stroke_path(
+                &path,
+                &stroke.paint.to_software_paint(),
+                &stroke.to_software_stroke(),
+            );
+        }
+    }
+}
+
+/// Build a femtovg path out of [`PathCommand`]s.
+pub(crate) fn femtovg_path(commands: &[PathCommand]) -> femtovg::Path {
+    let mut path = femtovg::Path::new();
+    for command in PathCommand::expand_arcs(commands) {
+        match command {
+            PathCommand::MoveTo(p) => path.move_to(p.x, p.y),
+            PathCommand::LineTo(p) => path.line_to(p.x, p.y),
+            PathCommand::QuadTo(c, p) => path.quad_to(c.x, c.y, p.x, p.y),
+            PathCommand::CubicTo(c1, c2, p) => path.bezier_to(c1.x, c1.y, c2.x, c2.y, p.x, p.y),
+            PathCommand::Close => path.close(),
+            PathCommand::Arc { .. } => unreachable!("Arcs are expanded into curves"),
+        }
+    }
+    path
+}
+
+/// The [`PathCommand`]s that make up a tiny-skia path.
+fn commands_from_path(path: &tiny_skia::Path) -> Vec<PathCommand> {
+    let point = |p: tiny_skia::Point| Point::new(p.x, p.y);
+    path.segments()
+        .map(|segment| match segment {
+            tiny_skia::PathSegment::MoveTo(p) => PathCommand::MoveTo(point(p)),
+            tiny_skia::PathSegment::LineTo(p) => PathCommand::LineTo(point(p)),
+            tiny_skia::PathSegment::QuadTo(c, p) => PathCommand::QuadTo(point(c), point(p)),
+            tiny_skia::PathSegment::CubicTo(c1, c2, p) => {
+                PathCommand::CubicTo(point(c1), point(c2), point(p))
+            }
+            tiny_skia::PathSegment::Close => PathCommand::Close,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::{Component, RenderContext};
+    use crate::headless::TestHarness;
+    use crate::renderables::Renderable;
+    use crate::{lay, node, size};
+
+    #[test]
+    fn test_path() {
+        #[derive(Debug)]
+        struct Ring;
+
+        impl Component for Ring {
+            fn render(&mut self, context: RenderContext) -> Option<Vec<Renderable>> {
+                let center = Point::new(
+                    context.aabb.pos.x + context.aabb.width() / 2.0,
+                    context.aabb.pos.y + context.aabb.height() / 2.0,
+                );
+                let circle = |radius| PathCommand::Arc {
+                    center,
+                    radius,
+                    start: 0.0,
+                    end: std::f32::consts::TAU,
+                };
+                let instance = InstanceBuilder::default()
+                    .commands(vec![
+                        circle(20.0),
+                        PathCommand::Close,
+                        circle(10.0),
+                        PathCommand::Close,
+                    ])
+                    .fill(Some(Color::BLUE.into()))
+                    .fill_rule(FillRule::EvenOdd)
+                    .stroke(Some(Stroke::new(Color::RED, 2.0).dash(vec![4.0, 4.0], 0.0)))
+                    .build()
+                    .unwrap();
+                Some(vec![Renderable::Path(Path::from_instance_data(instance))])
+            }
+        }
+
+        let mut harness = TestHarness::single(|| node!(Ring, lay![size: [100., 100.]]));
+        let pixmap = harness.render().pixmap();
+        // The inner circle is a hole in the ring
+        assert_eq!(pixmap.pixel(50, 50).unwrap().alpha(), 0);
+        let ring = pixmap.pixel(65, 50).unwrap();
+        assert_eq!((ring.blue(), ring.alpha()), (255, 255));
+        assert_eq!(pixmap.pixel(80, 80).unwrap().alpha(), 0);
+        // The dashed outline starts with a dash, followed by a gap
+        assert_eq!(pixmap.pixel(70, 52).unwrap().red(), 255);
+        assert_eq!(pixmap.pixel(69, 56).unwrap().red(), 0);
+    }
+}
@@ -1,9 +1,12 @@
-use crate::{Color, Pos};
+use crate::renderer::software::SoftwareCanvas;
+use crate::{Color, Point, Pos};
 
+use super::rect::radial_shader;
 use super::types;
 use super::types::Canvas;
 use derive_builder::Builder;
 use femtovg::{ImageId, Paint, Path};
+use resvg::tiny_skia;
 
 #[derive(Clone, Default, Debug, PartialEq, Builder)]
 pub struct Instance {
@@ -51,4 +54,23 @@ impl RadialGradient {
         canvas.fill_path(&path, &bg);
         // canvas.stroke_path(&path, &paint);
     }
+
+    pub fn render_software(&self, canvas: &mut SoftwareCanvas) {
+        let Instance {
+            origin,
+            radius,
+            colors,
+        } = &self.instance_data;
+        let Some(shader) = radial_shader(Point::new(origin.x, origin.y), *radius, colors) else {
+            return;
+        };
+        let Some(path) = tiny_skia::PathBuilder::from_circle(origin.x, origin.y, radius.1) else {
+            return;
+        };
+
+        let mut paint = tiny_skia::Paint::default();
+        paint.shader = shader;
+        paint.anti_alias = true;
+        canvas.fill_path(&path, &paint);
+    }
 }
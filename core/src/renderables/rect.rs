@@ -1,11 +1,17 @@
 use super::types::Canvas;
 use super::types::{self, Corners, Edges};
+use crate::renderer::software::{blur, clip_to_path, rounded_rect_path, SoftwareCanvas};
 use crate::types::{Color, Point, Pos, Scale, AABB};
 use bytemuck::{Pod, Zeroable};
 use derive_builder::Builder;
-use femtovg::{Color as fem_color, CompositeOperation, ImageId, Paint, Path};
+use femtovg::{Color as fem_color, CompositeOperation, ImageId, Paint, Path, Solidity};
+use resvg::tiny_skia;
+use std::hash::{Hash, Hasher};
 
-#[derive(Debug, Clone)]
+/// A gradient that fills a [`Rect`], in physical pixels.
+///
+/// Gradients given to widgets and styles instead use fractions of the rect that they fill, see [`fit`][Gradient::fit].
+#[derive(Debug, Clone, PartialEq)]
 pub enum Gradient {
     Linear {
         start: Point,
@@ -19,6 +25,350 @@ pub enum Gradient {
     },
 }
 
+impl Gradient {
+    /// A linear gradient across a rect, pointing `angle` degrees clockwise from the top, like CSS's `linear-gradient`.
+    /// Stops are `(offset, color)` pairs, with offsets from 0 to 1.
+    pub fn linear(angle: f32, stops: Vec<(f32, Color)>) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Gradient::Linear {
+            start: Point::new(0.5 - sin / 2., 0.5 + cos / 2.),
+            end: Point::new(0.5 + sin / 2., 0.5 - cos / 2.),
+            stops,
+        }
+    }
+
+    /// A radial gradient from the center of a rect, out to the middle of its shorter sides.
+    /// Stops are `(offset, color)` pairs, with offsets from 0 to 1.
+    pub fn radial(stops: Vec<(f32, Color)>) -> Self {
+        Gradient::Radial {
+            center: Point::new(0.5, 0.5),
+            radius: (0., 0.5),
+            stops,
+        }
+    }
+
+    /// Place a gradient given in fractions of a rect -- where points are fractions of its width and height,
+    /// and radii fractions of its shorter side -- onto `aabb`.
+    pub fn fit(&self, aabb: AABB) -> Self {
+        let (width, height) = (aabb.width(), aabb.height());
+        let place = |p: &Point| Point::new(aabb.pos.x + p.x * width, aabb.pos.y + p.y * height);
+        match self {
+            Gradient::Linear { start, end, stops } => Gradient::Linear {
+                start: place(start),
+                end: place(end),
+                stops: stops.clone(),
+            },
+            Gradient::Radial {
+                center,
+                radius,
+                stops,
+            } => {
+                let side = width.min(height);
+                Gradient::Radial {
+                    center: place(center),
+                    radius: (radius.0 * side, radius.1 * side),
+                    stops: stops.clone(),
+                }
+            }
+        }
+    }
+
+    /// The equivalent femtovg paint.
+    pub(crate) fn to_paint(&self) -> Paint {
+        let to_stops = |stops: &Vec<(f32, Color)>| -> Vec<(f32, fem_color)> {
+            stops
+                .iter()
+                .map(|(pos, color)| (*pos, (*color).into()))
+                .collect()
+        };
+
+        match self {
+            Gradient::Linear { start, end, stops } => {
+                Paint::linear_gradient_stops(start.x, start.y, end.x, end.y, to_stops(stops))
+            }
+            Gradient::Radial {
+                center,
+                radius,
+                stops,
+            } => Paint::radial_gradient_stops(
+                center.x,
+                center.y,
+                radius.0,
+                radius.1,
+                to_stops(stops),
+            ),
+        }
+    }
+
+    /// The equivalent tiny-skia shader, used by the software renderer.
+    pub(crate) fn to_shader(&self) -> Option<tiny_skia::Shader<'static>> {
+        let to_stops = |stops: &Vec<(f32, Color)>| -> Vec<tiny_skia::GradientStop> {
+            stops
+                .iter()
+                .map(|(pos, color)| tiny_skia::GradientStop::new(*pos, (*color).into()))
+                .collect()
+        };
+
+        match self {
+            Gradient::Linear { start, end, stops } => tiny_skia::LinearGradient::new(
+                tiny_skia::Point::from_xy(start.x, start.y),
+                tiny_skia::Point::from_xy(end.x, end.y),
+                to_stops(stops),
+                tiny_skia::SpreadMode::Pad,
+                tiny_skia::Transform::identity(),
+            ),
+            Gradient::Radial {
+                center,
+                radius,
+                stops,
+            } => radial_shader(*center, *radius, stops),
+        }
+    }
+}
+
+/// A radial gradient shader between an inner and outer radius, matching the femtovg semantics.
+pub(crate) fn radial_shader(
+    center: Point,
+    radius: (f32, f32),
+    stops: &[(f32, Color)],
+) -> Option<tiny_skia::Shader<'static>> {
+    let (inner, outer) = radius;
+    if outer <= 0. {
+        return None;
+    }
+    // tiny-skia gradients start at the center, so rescale the stops into the ring
+    let stops = stops
+        .iter()
+        .map(|(pos, color)| {
+            tiny_skia::GradientStop::new((inner + pos * (outer - inner)) / outer, (*color).into())
+        })
+        .collect();
+    let center = tiny_skia::Point::from_xy(center.x, center.y);
+    tiny_skia::RadialGradient::new(
+        center,
+        center,
+        outer,
+        stops,
+        tiny_skia::SpreadMode::Pad,
+        tiny_skia::Transform::identity(),
+    )
+}
+
+/// A shadow cast by a [`Rect`], like the CSS `box-shadow`.
+/// Outer shadows are drawn around the rect, and inset shadows inside of it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoxShadow {
+    pub color: Color,
+    /// How far the shadow is moved from the rect.
+    pub offset: Point,
+    /// How far the edge of the shadow is blurred.
+    pub blur: f32,
+    /// How much larger (or, for inset shadows, smaller) the shadow is than the rect. May be negative.
+    pub spread: f32,
+    pub inset: bool,
+}
+
+impl BoxShadow {
+    pub fn new<C: Into<Color>>(color: C, offset: (f32, f32), blur: f32, spread: f32) -> Self {
+        Self {
+            color: color.into(),
+            offset: Point::new(offset.0, offset.1),
+            blur: blur.max(0.),
+            spread,
+            inset: false,
+        }
+    }
+
+    /// Draw the shadow inside of the rect instead.
+    pub fn inset(mut self) -> Self {
+        self.inset = true;
+        self
+    }
+
+    /// Scale the offset, blur and spread, e.g. from logical to physical pixels.
+    pub fn scale(self, scale_factor: f32) -> Self {
+        Self {
+            offset: Point::new(self.offset.x * scale_factor, self.offset.y * scale_factor),
+            blur: self.blur * scale_factor,
+            spread: self.spread * scale_factor,
+            ..self
+        }
+    }
+
+    /// How far an outer shadow can reach past the rect.
+    pub(crate) fn extent(&self) -> f32 {
+        if self.inset {
+            return 0.;
+        }
+        self.offset.x.abs().max(self.offset.y.abs()) + self.spread.max(0.) + self.blur * 1.5
+    }
+
+    /// The rect that casts the shadow, before it is blurred: `(x, y, width, height, radius)`.
+    fn shape(
+        &self,
+        origin: Pos,
+        size: Scale,
+        radius: (f32, f32, f32, f32),
+    ) -> (f32, f32, f32, f32, (f32, f32, f32, f32)) {
+        // Outer shadows grow with the spread, inset shadows shrink
+        let spread = if self.inset {
+            -self.spread
+        } else {
+            self.spread
+        };
+        let r = |r: f32| (r + spread).max(0.);
+        (
+            origin.x + self.offset.x - spread,
+            origin.y + self.offset.y - spread,
+            (size.width + 2. * spread).max(0.),
+            (size.height + 2. * spread).max(0.),
+            (r(radius.0), r(radius.1), r(radius.2), r(radius.3)),
+        )
+    }
+
+    fn render(&self, canvas: &mut Canvas, origin: Pos, size: Scale, radius: (f32, f32, f32, f32)) {
+        let (x, y, width, height, shadow_radius) = self.shape(origin, size, radius);
+        let corner = shadow_radius
+            .0
+            .max(shadow_radius.1)
+            .max(shadow_radius.2)
+            .max(shadow_radius.3);
+        let color: fem_color = self.color.into();
+        let transparent = fem_color::rgbaf(color.r, color.g, color.b, 0.);
+        // femtovg feathers across the edge of the box
+        let feather = self.blur.max(0.5);
+
+        let mut path = Path::new();
+        let paint = if self.inset {
+            path.rounded_rect_varying(
+                origin.x,
+                origin.y,
+                size.width,
+                size.height,
+                radius.0,
+                radius.1,
+                radius.2,
+                radius.3,
+            );
+            Paint::box_gradient(x, y, width, height, corner, feather, transparent, color)
+        } else {
+            path.rect(
+                x - self.blur,
+                y - self.blur,
+                width + 2. * self.blur,
+                height + 2. * self.blur,
+            );
+            // Outer shadows are not drawn beneath the rect
+            path.rounded_rect_varying(
+                origin.x,
+                origin.y,
+                size.width,
+                size.height,
+                radius.0,
+                radius.1,
+                radius.2,
+                radius.3,
+            );
+            path.solidity(Solidity::Hole);
+            Paint::box_gradient(x, y, width, height, corner, feather, color, transparent)
+        };
+        canvas.fill_path(&path, &paint);
+    }
+
+    fn render_software(
+        &self,
+        canvas: &mut SoftwareCanvas,
+        origin: Pos,
+        size: Scale,
+        radius: (f32, f32, f32, f32),
+    ) {
+        let (x, y, width, height, shadow_radius) = self.shape(origin, size, radius);
+        let sigma = self.blur / 2.;
+        let margin = (sigma * 3.).ceil();
+
+        // Draw the shadow into a pixmap of its own, that covers everything that the blur reaches
+        let bounds = if self.inset {
+            AABB::new(origin, size)
+        } else {
+            AABB::new(Pos::new(x, y, 0.), Scale::new(width, height))
+        }
+        .expand(margin);
+        let (left, top) = (bounds.pos.x.floor(), bounds.pos.y.floor());
+        let Some(mut pixmap) = tiny_skia::Pixmap::new(
+            (bounds.bottom_right.x.ceil() - left) as u32,
+            (bounds.bottom_right.y.ceil() - top) as u32,
+        ) else {
+            return;
+        };
+        let to_pixmap = tiny_skia::Transform::from_translate(-left, -top);
+        let rect = rounded_rect_path(origin.x, origin.y, size.width, size.height, radius);
+        let shape = rounded_rect_path(x, y, width, height, shadow_radius);
+
+        let mut paint = tiny_skia::Paint::default();
+        paint.set_color(self.color.into());
+        paint.anti_alias = true;
+        if self.inset {
+            // Everything but the shape casts the shadow
+            pixmap.fill(self.color.into());
+            if let Some(shape) = &shape {
+                paint.blend_mode = tiny_skia::BlendMode::Clear;
+                pixmap.fill_path(shape, &paint, tiny_skia::FillRule::Winding, to_pixmap, None);
+            }
+        } else if let Some(shape) = &shape {
+            pixmap.fill_path(shape, &paint, tiny_skia::FillRule::Winding, to_pixmap, None);
+        }
+
+        blur(&mut pixmap, sigma);
+
+        // Only keep what lies outside of the rect, for outer shadows, or inside of it, for inset ones
+        if let Some(rect) = &rect {
+            if self.inset {
+                clip_to_path(&mut pixmap, rect, to_pixmap);
+            } else {
+                paint.blend_mode = tiny_skia::BlendMode::DestinationOut;
+                paint.set_color(tiny_skia::Color::BLACK);
+                pixmap.fill_path(rect, &paint, tiny_skia::FillRule::Winding, to_pixmap, None);
+            }
+        }
+
+        canvas.draw_pixmap(left as i32, top as i32, pixmap.as_ref());
+    }
+}
+
+impl Hash for Gradient {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let (points, radius, stops) = match self {
+            Gradient::Linear { start, end, stops } => ([start, end], (0., 0.), stops),
+            Gradient::Radial {
+                center,
+                radius,
+                stops,
+            } => ([center, center], *radius, stops),
+        };
+        std::mem::discriminant(self).hash(state);
+        for p in points {
+            p.x.to_bits().hash(state);
+            p.y.to_bits().hash(state);
+        }
+        radius.0.to_bits().hash(state);
+        radius.1.to_bits().hash(state);
+        for (pos, color) in stops.iter() {
+            pos.to_bits().hash(state);
+            color.hash(state);
+        }
+    }
+}
+
+impl Hash for BoxShadow {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.color.hash(state);
+        self.offset.hash(state);
+        self.blur.to_bits().hash(state);
+        self.spread.to_bits().hash(state);
+        self.inset.hash(state);
+    }
+}
+
 #[derive(Clone, Debug, Builder)]
 pub struct Instance {
     pub pos: Pos,
@@ -35,10 +385,15 @@ pub struct Instance {
     pub bg_image: Option<ImageId>,
     #[builder(default = "None")]
     pub gradient: Option<Gradient>,
+    /// Draws the borders in place of `border_color`.
+    #[builder(default = "None")]
+    pub border_gradient: Option<Gradient>,
     #[builder(default = "CompositeOperation::SourceOver")]
     pub composite_operation: CompositeOperation,
     #[builder(default = "None")]
     pub scissor: Option<bool>,
+    #[builder(default = "vec![]")]
+    pub shadows: Vec<BoxShadow>,
 }
 
 #[derive(Debug, Clone)]
@@ -58,8 +413,10 @@ impl Rect {
                 border_color: Color::TRANSPARENT,
                 border_size: (0., 0., 0., 0.),
                 gradient: None,
+                border_gradient: None,
                 composite_operation: CompositeOperation::SourceOver,
                 scissor: None,
+                shadows: vec![],
             },
         }
     }
@@ -78,12 +435,18 @@ impl Rect {
             border_color,
             border_size,
             gradient,
+            border_gradient,
             composite_operation,
             scissor,
+            shadows,
         } = self.instance_data.clone();
         let origin = pos;
         let size = scale;
 
+        for shadow in shadows.iter().filter(|s| !s.inset) {
+            shadow.render(canvas, origin, size, radius);
+        }
+
         canvas.global_composite_operation(composite_operation);
         let mut path = Path::new();
         path.rounded_rect_varying(
@@ -112,6 +475,19 @@ impl Rect {
         };
         canvas.fill_path(&path, &background);
 
+        if let Some(gradient) = &gradient {
+            canvas.fill_path(&path, &gradient.to_paint());
+        }
+
+        for shadow in shadows.iter().filter(|s| s.inset) {
+            shadow.render(canvas, origin, size, radius);
+        }
+
+        let border_paint = match &border_gradient {
+            Some(gradient) => gradient.to_paint(),
+            None => Paint::color(border_color.into()),
+        };
+
         // let mut paint = Paint::color(border_color.into());
         // paint.set_line_width(border_size);
 
@@ -123,7 +499,7 @@ impl Rect {
             let mut path = Path::new();
             path.move_to(origin.x, origin.y);
             path.line_to(origin.x + size.width, origin.y);
-            let mut paint = Paint::color(border_color.into());
+            let mut paint = border_paint.clone();
             paint.set_line_width(border_size.0);
             canvas.stroke_path(&path, &paint);
         }
@@ -133,7 +509,7 @@ impl Rect {
             let mut path = Path::new();
             path.move_to(origin.x, origin.y);
             path.line_to(origin.x, origin.y + size.height);
-            let mut paint = Paint::color(border_color.into());
+            let mut paint = border_paint.clone();
             paint.set_line_width(border_size.1);
             canvas.stroke_path(&path, &paint);
         }
@@ -143,7 +519,7 @@ impl Rect {
             let mut path = Path::new();
             path.move_to(origin.x, origin.y + size.height);
             path.line_to(origin.x + size.width, origin.y + size.height);
-            let mut paint = Paint::color(border_color.into());
+            let mut paint = border_paint.clone();
             paint.set_line_width(border_size.2);
             canvas.stroke_path(&path, &paint);
         }
@@ -153,7 +529,7 @@ impl Rect {
             let mut path = Path::new();
             path.move_to(origin.x + size.width, origin.y);
             path.line_to(origin.x + size.width, origin.y + size.height);
-            let mut paint = Paint::color(border_color.into());
+            let mut paint = border_paint.clone();
             paint.set_line_width(border_size.3);
             canvas.stroke_path(&path, &paint);
         }
@@ -182,25 +558,94 @@ impl Rect {
             }
             None => (),
         }
+    }
 
-        //Add gradient
-        // match gradient {
-        //     Some(gradient_type) => match gradient_type {
-        //         Gradient::Linear { start, end, stops } => {
-        //             let paint = Paint::linear_gradient_stops(start.x, start.y, end.x, end.y, stops);
-        //             canvas.fill_path(&path, &paint);
-        //         }
-        //         Gradient::Radial {
-        //             center,
-        //             radius,
-        //             stops,
-        //         } => {
-        //             let paint =
-        //                 Paint::radial_gradient_stops(center.x, center.y, radius.0, radius.1, stops);
-        //             canvas.fill_path(&path, &paint);
-        //         }
-        //     },
-        //     None => (),
-        // }
+    pub fn render_software(&self, canvas: &mut SoftwareCanvas) {
+        let Instance {
+            pos,
+            scale,
+            color,
+            radius,
+            border_color,
+            border_size,
+            gradient,
+            border_gradient,
+            composite_operation,
+            scissor,
+            shadows,
+            ..
+        } = self.instance_data.clone();
+        let origin = pos;
+        let size = scale;
+
+        for shadow in shadows.iter().filter(|s| !s.inset) {
+            shadow.render_software(canvas, origin, size, radius);
+        }
+
+        canvas.global_composite_operation(composite_operation);
+
+        // `bg_image` refers to a GPU image, so only the color is drawn here
+        if let Some(path) = rounded_rect_path(origin.x, origin.y, size.width, size.height, radius) {
+            let mut paint = tiny_skia::Paint::default();
+            paint.set_color(color.into());
+            paint.anti_alias = true;
+            canvas.fill_path(&path, &paint);
+
+            if let Some(gradient) = gradient {
+                if let Some(shader) = gradient.to_shader() {
+                    let mut paint = tiny_skia::Paint::default();
+                    paint.shader = shader;
+                    paint.anti_alias = true;
+                    canvas.fill_path(&path, &paint);
+                }
+            }
+        }
+
+        for shadow in shadows.iter().filter(|s| s.inset) {
+            shadow.render_software(canvas, origin, size, radius);
+        }
+
+        //Add borders
+        let (left, top) = (origin.x, origin.y);
+        let (right, bottom) = (origin.x + size.width, origin.y + size.height);
+        let borders = [
+            (border_size.0, (left, top), (right, top)),
+            (border_size.1, (left, top), (left, bottom)),
+            (border_size.2, (left, bottom), (right, bottom)),
+            (border_size.3, (right, top), (right, bottom)),
+        ];
+        for (width, from, to) in borders {
+            if width <= 0. {
+                continue;
+            }
+            let mut pb = tiny_skia::PathBuilder::new();
+            pb.move_to(from.0, from.1);
+            pb.line_to(to.0, to.1);
+            if let Some(path) = pb.finish() {
+                let mut paint = tiny_skia::Paint::default();
+                match border_gradient.as_ref().and_then(Gradient::to_shader) {
+                    Some(shader) => paint.shader = shader,
+                    None => paint.set_color(border_color.into()),
+                }
+                paint.anti_alias = true;
+                let stroke = tiny_skia::Stroke {
+                    width,
+                    ..Default::default()
+                };
+                canvas.stroke_path(&path, &paint, &stroke);
+            }
+        }
+
+        canvas.global_composite_operation(CompositeOperation::SourceOver);
+
+        match scissor {
+            Some(true) => {
+                canvas.scissor(origin.x, origin.y, size.width, size.height);
+            }
+            Some(false) => {
+                canvas.reset_scissor();
+            }
+            None => (),
+        }
     }
 }
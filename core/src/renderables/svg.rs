@@ -1,10 +1,12 @@
 use super::types::Canvas;
 use crate::{
-    renderer::svg::{load_svg_path, SvgData},
-    Pos, Scale,
+    renderer::software::{load_svg_tree, SoftwareCanvas},
+    renderer::svg::{load_svg_path, SvgData, SvgNode, SvgPaint},
+    Color, Pos, Scale,
 };
 use derive_builder::Builder;
-use femtovg::Transform2D;
+use femtovg::{ImageFlags, ImageSource, Paint, Path, Transform2D};
+use resvg::tiny_skia;
 use std::collections::HashMap;
 use usvg::fontdb::Database;
 
@@ -14,6 +16,9 @@ pub struct Instance {
     pub pos: Pos,
     pub scale: Scale,
     pub dynamic_load_from: Option<String>,
+    /// Recolor the SVG, keeping only its alpha, e.g. to draw a monochrome icon in the color of a theme.
+    #[builder(default = "None")]
+    pub color: Option<Color>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -29,6 +34,7 @@ impl Svg {
                 scale,
                 name: name.into(),
                 dynamic_load_from: None,
+                color: None,
             },
         }
     }
@@ -38,6 +44,7 @@ impl Svg {
             pos,
             scale,
             dynamic_load_from,
+            color,
             ..
         } = self.instance_data.clone();
 
@@ -61,36 +68,92 @@ impl Svg {
 
         canvas.scale(width / svg_data.scale.width, height / svg_data.scale.height);
 
-        for (path, fill, stroke, transform) in &mut svg_data.paths {
-            canvas.save();
-            // canvas.set_transform(
-            //     transform.a as f32,
-            //     transform.b as f32,
-            //     transform.c as f32,
-            //     transform.d as f32,
-            //     transform.e as f32,
-            //     transform.f as f32,
-            // );
-            let canvas_transform: Transform2D = Transform2D([
-                transform.sx,
-                transform.kx,
-                transform.ky,
-                transform.sy,
-                transform.tx,
-                transform.ty,
-            ]);
-            canvas.set_transform(&canvas_transform);
-
-            if let Some(fill) = fill {
-                fill.set_anti_alias(true);
-                canvas.fill_path(&path, &fill);
+        // The paint of a fill or stroke, or a solid one in `color` with the same opacity
+        let paint = |p: &SvgPaint| {
+            let mut paint = p.paint.clone();
+            if let Some(color) = color {
+                let mut color: femtovg::Color = color.into();
+                color.a *= p.opacity;
+                paint.set_color(color);
             }
+            paint
+        };
 
-            if let Some(stroke) = stroke {
-                stroke.set_anti_alias(true);
-                canvas.stroke_path(&path, &stroke);
+        for node in &mut svg_data.nodes {
+            canvas.save();
+            match node {
+                SvgNode::Path {
+                    path,
+                    fill,
+                    stroke,
+                    stroke_first,
+                    transform,
+                } => {
+                    canvas.set_transform(&to_transform_2d(transform));
+                    let fill = fill.as_ref().map(paint);
+                    let stroke = stroke.as_ref().map(paint);
+                    if *stroke_first {
+                        if let Some(stroke) = &stroke {
+                            canvas.stroke_path(path, stroke);
+                        }
+                    }
+                    if let Some(fill) = &fill {
+                        canvas.fill_path(path, fill);
+                    }
+                    if !*stroke_first {
+                        if let Some(stroke) = &stroke {
+                            canvas.stroke_path(path, stroke);
+                        }
+                    }
+                }
+                SvgNode::Image {
+                    image,
+                    id,
+                    view_box,
+                    opacity,
+                    transform,
+                } => {
+                    if id.is_none() {
+                        *id = ImageSource::try_from(image.as_ref())
+                            .ok()
+                            .and_then(|src| canvas.create_image(src, ImageFlags::empty()).ok());
+                    }
+                    let Some(id) = *id else {
+                        canvas.restore();
+                        continue;
+                    };
+                    canvas.set_transform(&to_transform_2d(transform));
+
+                    let rect = view_box.rect;
+                    let (image_width, image_height) = (image.width() as f32, image.height() as f32);
+                    let (sx, sy) = (rect.width() / image_width, rect.height() / image_height);
+                    let (sx, sy) = match view_box.aspect {
+                        usvg::AspectRatio {
+                            align: usvg::Align::None,
+                            ..
+                        } => (sx, sy),
+                        usvg::AspectRatio { slice: true, .. } => (sx.max(sy), sx.max(sy)),
+                        _ => (sx.min(sy), sx.min(sy)),
+                    };
+                    let (width, height) = (image_width * sx, image_height * sy);
+                    let (x, y) = (
+                        rect.x() + (rect.width() - width) / 2.,
+                        rect.y() + (rect.height() - height) / 2.,
+                    );
+
+                    let paint = match color {
+                        Some(color) => {
+                            let mut color: femtovg::Color = color.into();
+                            color.a *= *opacity;
+                            Paint::image_tint(id, x, y, width, height, 0., color)
+                        }
+                        None => Paint::image(id, x, y, width, height, 0., *opacity),
+                    };
+                    let mut path = Path::new();
+                    path.rect(rect.x(), rect.y(), rect.width(), rect.height());
+                    canvas.fill_path(&path, &paint);
+                }
             }
-
             canvas.restore();
         }
 
@@ -100,4 +163,78 @@ impl Svg {
     pub fn from_instance_data(instance_data: Instance) -> Self {
         Self { instance_data }
     }
+
+    pub fn render_software(
+        &self,
+        canvas: &mut SoftwareCanvas,
+        svgs: &mut HashMap<String, usvg::Tree>,
+        fonts: &Database,
+    ) {
+        let Instance {
+            pos,
+            scale,
+            dynamic_load_from,
+            color,
+            ..
+        } = self.instance_data.clone();
+
+        if svgs.get(&self.instance_data.name).is_none() && dynamic_load_from.is_some() {
+            if let Some(tree) = load_svg_tree(&dynamic_load_from.unwrap(), fonts) {
+                svgs.insert(self.instance_data.name.clone(), tree);
+            }
+        }
+
+        let Some(tree) = svgs.get(&self.instance_data.name) else {
+            println!("error: svg not found {:?}", self.instance_data.name);
+            return;
+        };
+
+        let Pos { x, y, .. } = pos;
+        let Scale { width, height } = scale;
+        let size = tree.size();
+
+        // Render into a layer first, so that the canvas scissor applies
+        let (left, top) = (x.floor(), y.floor());
+        let Some(mut layer) = tiny_skia::Pixmap::new(
+            (width + x - left).ceil().max(1.) as u32,
+            (height + y - top).ceil().max(1.) as u32,
+        ) else {
+            return;
+        };
+        let transform = tiny_skia::Transform::from_row(
+            width / size.width(),
+            0.,
+            0.,
+            height / size.height(),
+            x - left,
+            y - top,
+        );
+        resvg::render(tree, transform, &mut layer.as_mut());
+        if let Some(color) = color {
+            recolor(&mut layer, color);
+        }
+        canvas.draw_pixmap(left as i32, top as i32, layer.as_ref());
+    }
+}
+
+fn to_transform_2d(transform: &usvg::Transform) -> Transform2D {
+    Transform2D([
+        transform.sx,
+        transform.ky,
+        transform.kx,
+        transform.sy,
+        transform.tx,
+        transform.ty,
+    ])
+}
+
+/// Paint every pixel of `pixmap` in `color`, keeping its alpha.
+fn recolor(pixmap: &mut tiny_skia::Pixmap, color: Color) {
+    for pixel in pixmap.data_mut().chunks_exact_mut(4) {
+        let alpha = pixel[3] as f32 / 255. * color.a;
+        pixel[0] = (color.r * alpha).round() as u8;
+        pixel[1] = (color.g * alpha).round() as u8;
+        pixel[2] = (color.b * alpha).round() as u8;
+        pixel[3] = (alpha * 255.).round() as u8;
+    }
 }
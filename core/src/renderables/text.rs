@@ -1,13 +1,206 @@
 use super::types::Canvas;
 use crate::{
-    renderer::text::TextRenderer,
-    style::FontWeight,
-    types::{Color, Pos},
+    renderer::{
+        software::{blur, SoftwareCanvas},
+        text::TextRenderer,
+    },
+    style::{FontStretch, FontStyle, FontWeight},
+    types::{Color, Point, Pos, AABB},
     Scale,
 };
 use cosmic_text::FontSystem;
 use derive_builder::Builder;
-use femtovg::{Align, Paint};
+use femtovg::{Align, ImageFilter, ImageFlags, ImageId, Paint, Path, PixelFormat, RenderTarget};
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+/// Where text breaks onto a new line when it doesn't fit the width of its box.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextWrap {
+    /// Only at line breaks in the text.
+    #[default]
+    None,
+    /// Between words. Words that are wider than the box overflow it.
+    Word,
+    /// Between any two glyphs.
+    Glyph,
+}
+
+impl From<TextWrap> for cosmic_text::Wrap {
+    fn from(wrap: TextWrap) -> Self {
+        match wrap {
+            TextWrap::None => cosmic_text::Wrap::None,
+            TextWrap::Word => cosmic_text::Wrap::Word,
+            TextWrap::Glyph => cosmic_text::Wrap::Glyph,
+        }
+    }
+}
+
+/// Where a [`TextDecoration`] line is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextDecorationLine {
+    #[default]
+    Underline,
+    Overline,
+    LineThrough,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextDecorationStyle {
+    #[default]
+    Solid,
+    Double,
+    Dotted,
+    Dashed,
+}
+
+/// A line drawn under, over or through every line of text, like the CSS `text-decoration`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextDecoration {
+    pub line: TextDecorationLine,
+    pub style: TextDecorationStyle,
+    /// The color of the text, if `None`.
+    pub color: Option<Color>,
+    /// The thickness that the font recommends, if `None`.
+    pub thickness: Option<f32>,
+}
+
+impl TextDecoration {
+    pub fn new(line: TextDecorationLine) -> Self {
+        Self {
+            line,
+            ..Default::default()
+        }
+    }
+
+    pub fn style(mut self, style: TextDecorationStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn color<C: Into<Color>>(mut self, color: C) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    pub fn thickness(mut self, thickness: f32) -> Self {
+        self.thickness = Some(thickness);
+        self
+    }
+
+    /// Scale the thickness, e.g. from logical to physical pixels.
+    pub fn scale(self, scale_factor: f32) -> Self {
+        Self {
+            thickness: self.thickness.map(|t| t * scale_factor),
+            ..self
+        }
+    }
+}
+
+impl Hash for TextDecoration {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.line.hash(state);
+        self.style.hash(state);
+        self.color.hash(state);
+        self.thickness.map(f32::to_bits).hash(state);
+    }
+}
+
+/// A blurred copy of text, drawn beneath it, like the CSS `text-shadow`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextShadow {
+    pub color: Color,
+    /// How far the shadow is moved from the text.
+    pub offset: Point,
+    /// How far the shadow is blurred.
+    pub blur: f32,
+}
+
+impl TextShadow {
+    pub fn new<C: Into<Color>>(color: C, offset: (f32, f32), blur: f32) -> Self {
+        Self {
+            color: color.into(),
+            offset: Point::new(offset.0, offset.1),
+            blur: blur.max(0.),
+        }
+    }
+
+    /// Scale the offset and blur, e.g. from logical to physical pixels.
+    pub fn scale(self, scale_factor: f32) -> Self {
+        Self {
+            offset: Point::new(self.offset.x * scale_factor, self.offset.y * scale_factor),
+            blur: self.blur * scale_factor,
+            ..self
+        }
+    }
+
+    /// How far the shadow can reach past the box of the text.
+    pub(crate) fn extent(&self) -> f32 {
+        self.offset.x.abs().max(self.offset.y.abs()) + self.blur * 1.5
+    }
+}
+
+impl Hash for TextShadow {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.color.hash(state);
+        self.offset.hash(state);
+        self.blur.to_bits().hash(state);
+    }
+}
+
+/// An OpenType feature of a font, e.g. `tnum` for tabular numbers, and the value it is set to.
+///
+/// The version of cosmic-text that text is shaped with doesn't pass features on to the shaper, so they are
+/// applied after shaping instead. Only `tnum` is, for now: digits are given the advance of the widest one of them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FontFeature {
+    pub tag: [u8; 4],
+    pub value: u32,
+}
+
+impl FontFeature {
+    pub const TABULAR_NUMBERS: Self = Self::new(*b"tnum", 1);
+
+    pub const fn new(tag: [u8; 4], value: u32) -> Self {
+        Self { tag, value }
+    }
+
+    /// Parse a list of features like `"tnum ss01=2 liga=0"`. A feature without a value is turned on.
+    /// Tags that aren't four bytes long, and values that aren't numbers, are skipped.
+    pub fn parse(features: &str) -> Vec<Self> {
+        features
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter_map(|feature| {
+                let (tag, value) = feature.split_once('=').unwrap_or((feature, "1"));
+                Some(Self::new(
+                    tag.as_bytes().try_into().ok()?,
+                    value.parse().ok()?,
+                ))
+            })
+            .collect()
+    }
+}
+
+/// A run of the text of an [`Instance`] that is drawn differently from the rest of it.
+/// Attributes that are `None` are those of the `Instance`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextSpan {
+    /// The byte range of the text that the span covers.
+    pub range: Range<usize>,
+    pub color: Option<Color>,
+    pub weight: Option<FontWeight>,
+    pub italic: bool,
+    pub font: Option<String>,
+    /// The color drawn behind the glyphs of the span.
+    pub background: Option<Color>,
+}
+
+impl TextSpan {
+    /// Whether the span changes how its glyphs are shaped or colored, rather than only what is drawn behind them.
+    pub fn changes_glyphs(&self) -> bool {
+        self.color.is_some() || self.weight.is_some() || self.italic || self.font.is_some()
+    }
+}
 
 #[derive(Clone, Debug, PartialEq, Builder)]
 pub struct Instance {
@@ -17,6 +210,15 @@ pub struct Instance {
     pub font: Option<String>,
     #[builder(default = "FontWeight::Normal")]
     pub weight: FontWeight,
+    #[builder(default = "FontStyle::Normal")]
+    pub font_style: FontStyle,
+    #[builder(default = "FontStretch::Normal")]
+    pub stretch: FontStretch,
+    /// Space added after every glyph. It isn't taken into account when text is wrapped.
+    #[builder(default = "0.0")]
+    pub letter_spacing: f32,
+    #[builder(default = "vec![]")]
+    pub features: Vec<FontFeature>,
     #[builder(default = "Default::default()")]
     pub color: Color,
     #[builder(default = "12.0")]
@@ -27,6 +229,20 @@ pub struct Instance {
     pub align: Align,
     #[builder(default = "String::new()")]
     pub text: String,
+    #[builder(default = "TextWrap::None")]
+    pub wrap: TextWrap,
+    /// The most lines that are shown. The rest of the text is cut off.
+    #[builder(default = "None")]
+    pub max_lines: Option<usize>,
+    /// End text that is cut off, by `max_lines` or by overflowing the width of the box, with an ellipsis.
+    #[builder(default = "false")]
+    pub ellipsis: bool,
+    #[builder(default = "vec![]")]
+    pub spans: Vec<TextSpan>,
+    #[builder(default = "None")]
+    pub decoration: Option<TextDecoration>,
+    #[builder(default = "None")]
+    pub shadow: Option<TextShadow>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -48,9 +264,19 @@ impl Text {
                 font_size: 12.0,
                 font: None,
                 weight: FontWeight::Normal,
+                font_style: FontStyle::Normal,
+                stretch: FontStretch::Normal,
+                letter_spacing: 0.0,
+                features: vec![],
                 line_height: 18.0,
                 align: Align::Left,
                 text: text.into(),
+                wrap: TextWrap::None,
+                max_lines: None,
+                ellipsis: false,
+                spans: vec![],
+                decoration: None,
+                shadow: None,
             },
         }
     }
@@ -59,14 +285,160 @@ impl Text {
         Self { instance_data }
     }
 
-    pub fn render(&self, canvas: &mut Canvas, text_renderer: &mut TextRenderer) {
-        let Instance { color, .. } = self.instance_data;
+    /// Draw the text, above its shadow. `target` is the image that is being drawn into, if not the screen.
+    /// Returns the images that were used for the shadow: they can only be deleted once the canvas has been flushed.
+    pub fn render(
+        &self,
+        canvas: &mut Canvas,
+        text_renderer: &mut TextRenderer,
+        target: Option<ImageId>,
+    ) -> Vec<ImageId> {
+        let scratch = match self.instance_data.shadow {
+            Some(shadow) => self.render_shadow(canvas, text_renderer, shadow, target),
+            None => vec![],
+        };
 
-        if let Ok(draw_commands) = text_renderer.draw_text(canvas, self.instance_data.clone()) {
-            for (_, cmds) in draw_commands.into_iter() {
-                let temp_paint = Paint::color(color.into());
+        if let Ok(commands) = text_renderer.draw_text(canvas, self.instance_data.clone()) {
+            fill_rects(canvas, commands.backgrounds, None);
+            // Glyphs are grouped by the color of the span they are in. Color glyphs alone have none.
+            for (color, cmds) in commands.glyphs.into_iter() {
+                let temp_paint = if color.0 == 0 {
+                    Paint::color(self.instance_data.color.into())
+                } else {
+                    Paint::color(femtovg::Color::rgba(
+                        color.r(),
+                        color.g(),
+                        color.b(),
+                        color.a(),
+                    ))
+                };
                 canvas.draw_glyph_commands(cmds, &temp_paint, 1.0);
             }
+            fill_rects(canvas, commands.decorations, None);
         }
+        scratch
+    }
+
+    /// The text that casts the shadow: its glyphs and decorations, without highlighted backgrounds.
+    fn shadow_instance(&self) -> Instance {
+        let mut instance = self.instance_data.clone();
+        instance.shadow = None;
+        for span in instance.spans.iter_mut() {
+            span.background = None;
+        }
+        instance
+    }
+
+    /// The box that the shadow is drawn into before it is moved by its offset, which fits everything that the blur reaches.
+    fn shadow_bounds(&self, shadow: TextShadow) -> AABB {
+        let Instance { pos, scale, .. } = self.instance_data;
+        AABB::new(pos, scale)
+            .expand((shadow.blur * 1.5).ceil())
+            .round()
+    }
+
+    fn render_shadow(
+        &self,
+        canvas: &mut Canvas,
+        text_renderer: &mut TextRenderer,
+        shadow: TextShadow,
+        target: Option<ImageId>,
+    ) -> Vec<ImageId> {
+        let bounds = self.shadow_bounds(shadow);
+        let (width, height) = (bounds.width() as usize, bounds.height() as usize);
+        let flags = ImageFlags::FLIP_Y | ImageFlags::PREMULTIPLIED;
+        let (Ok(layer), Ok(blurred)) = (
+            canvas.create_image_empty(width, height, PixelFormat::Rgba8, flags),
+            canvas.create_image_empty(width, height, PixelFormat::Rgba8, flags),
+        ) else {
+            return vec![];
+        };
+
+        // Draw the text in the color of the shadow into an image of its own, and blur it
+        canvas.save();
+        canvas.reset_transform();
+        canvas.reset_scissor();
+        canvas.set_render_target(RenderTarget::Image(layer));
+        canvas.clear_rect(0, 0, width as u32, height as u32, Color::TRANSPARENT.into());
+        canvas.translate(-bounds.pos.x, -bounds.pos.y);
+        if let Ok(commands) = text_renderer.draw_text(canvas, self.shadow_instance()) {
+            let paint = Paint::color(shadow.color.into());
+            for (_, cmds) in commands.glyphs.into_iter() {
+                canvas.draw_glyph_commands(cmds, &paint, 1.0);
+            }
+            fill_rects(canvas, commands.decorations, Some(shadow.color));
+        }
+        let image = if shadow.blur > 0. {
+            canvas.filter_image(
+                blurred,
+                ImageFilter::GaussianBlur {
+                    sigma: shadow.blur / 2.,
+                },
+                layer,
+            );
+            blurred
+        } else {
+            layer
+        };
+        canvas.set_render_target(target.map_or(RenderTarget::Screen, RenderTarget::Image));
+        canvas.restore();
+
+        let (x, y) = (
+            bounds.pos.x + shadow.offset.x,
+            bounds.pos.y + shadow.offset.y,
+        );
+        let mut path = Path::new();
+        path.rect(x, y, width as f32, height as f32);
+        let paint = Paint::image(image, x, y, width as f32, height as f32, 0.0, 1.0);
+        canvas.fill_path(&path, &paint);
+        vec![layer, blurred]
+    }
+
+    pub fn render_software(&self, canvas: &mut SoftwareCanvas, text_renderer: &mut TextRenderer) {
+        if let Some(shadow) = self.instance_data.shadow {
+            self.render_shadow_software(canvas, text_renderer, shadow);
+        }
+        text_renderer.draw_text_to_pixels(self.instance_data.clone(), |x, y, w, h, color| {
+            for py in y..y + h as i32 {
+                for px in x..x + w as i32 {
+                    canvas.blend_pixel(px, py, [color.r(), color.g(), color.b(), color.a()]);
+                }
+            }
+        });
+    }
+
+    fn render_shadow_software(
+        &self,
+        canvas: &mut SoftwareCanvas,
+        text_renderer: &mut TextRenderer,
+        shadow: TextShadow,
+    ) {
+        let bounds = self.shadow_bounds(shadow);
+        let mut layer = SoftwareCanvas::new(bounds.width() as u32, bounds.height() as u32);
+        layer.translate(-bounds.pos.x, -bounds.pos.y);
+        let c = shadow.color;
+        text_renderer.draw_text_to_pixels(self.shadow_instance(), |x, y, w, h, color| {
+            let alpha = (color.a() as f32 * c.a) as u8;
+            for py in y..y + h as i32 {
+                for px in x..x + w as i32 {
+                    layer.blend_pixel(px, py, [c.r as u8, c.g as u8, c.b as u8, alpha]);
+                }
+            }
+        });
+        blur(layer.pixmap_mut(), shadow.blur / 2.);
+        canvas.draw_pixmap(
+            (bounds.pos.x + shadow.offset.x).round() as i32,
+            (bounds.pos.y + shadow.offset.y).round() as i32,
+            layer.pixmap().as_ref(),
+        );
+    }
+}
+
+/// Fill each of the `rects` with its color, or with `color` if one is given.
+fn fill_rects(canvas: &mut Canvas, rects: Vec<(AABB, Color)>, color: Option<Color>) {
+    for (aabb, rect_color) in rects {
+        let mut path = Path::new();
+        path.rect(aabb.pos.x, aabb.pos.y, aabb.width(), aabb.height());
+        canvas.fill_path(&path, &Paint::color(color.unwrap_or(rect_color).into()));
     }
 }
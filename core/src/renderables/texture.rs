@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+
+use super::types::Canvas;
+use crate::renderer::software::{rounded_rect_path, SoftwareCanvas};
+use crate::texture::{self, TextureFrame};
+use crate::{Pos, Scale};
+use derive_builder::Builder;
+use femtovg::{ImageFlags, ImageId, ImageSource, Paint, Path};
+use imgref::Img;
+use resvg::tiny_skia::{self, IntSize, Pixmap};
+use rgb::FromSlice;
+
+/// The latest frame of a streaming [`Texture`][crate::texture::Texture], stretched over a rounded rect.
+/// Nothing is drawn until a frame has been pushed into the texture.
+#[derive(Clone, Debug, PartialEq, Builder)]
+pub struct Instance {
+    pub texture: texture::Texture,
+    pub pos: Pos,
+    pub scale: Scale,
+    #[builder(default = "0.0")]
+    pub radius: f32,
+    /// The [generation][crate::texture::Texture#method.generation] of the texture when this was rendered.
+    /// Only used to tell renders apart, the latest frame is always drawn.
+    #[builder(default = "0")]
+    pub generation: u64,
+}
+
+/// The renderer's copy of a streaming texture.
+pub(crate) struct GlTexture {
+    /// The texture that this is a copy of, since a texture that was removed can be replaced by another one of the same name.
+    texture: texture::Texture,
+    image: ImageId,
+    width: u32,
+    height: u32,
+    generation: u64,
+    /// Whether it was drawn since the last [`drop_undrawn_gl`].
+    drawn: bool,
+}
+
+/// The software renderer's copy of a streaming texture.
+pub(crate) struct SoftwareTexture {
+    texture: texture::Texture,
+    pixmap: Pixmap,
+    generation: u64,
+    drawn: bool,
+}
+
+/// Delete the copies of the textures that were not drawn since this was last called, at the end of every frame.
+pub(crate) fn drop_undrawn_gl(canvas: &mut Canvas, textures: &mut HashMap<String, GlTexture>) {
+    textures.retain(|_, t| {
+        if !std::mem::take(&mut t.drawn) {
+            canvas.delete_image(t.image);
+            return false;
+        }
+        true
+    });
+}
+
+/// Drop the copies of the textures that were not drawn since this was last called, at the end of every frame.
+pub(crate) fn drop_undrawn_software(textures: &mut HashMap<String, SoftwareTexture>) {
+    textures.retain(|_, t| std::mem::take(&mut t.drawn));
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Texture {
+    pub instance_data: Instance,
+}
+
+impl Texture {
+    pub fn new(pos: Pos, scale: Scale, texture: texture::Texture) -> Self {
+        Self {
+            instance_data: Instance {
+                generation: texture.generation(),
+                texture,
+                pos,
+                scale,
+                radius: 0.0,
+            },
+        }
+    }
+
+    pub fn from_instance_data(instance_data: Instance) -> Self {
+        Self { instance_data }
+    }
+
+    /// Upload the latest frame when it is new: in place, unless its size changed.
+    fn update_gl(
+        &self,
+        canvas: &mut Canvas,
+        textures: &mut HashMap<String, GlTexture>,
+    ) -> Option<ImageId> {
+        let texture = &self.instance_data.texture;
+        let frame = texture.frame();
+        if frame.generation == 0 {
+            return None;
+        }
+        let src = ImageSource::from(Img::new(
+            frame.data.as_rgba(),
+            frame.width as usize,
+            frame.height as usize,
+        ));
+
+        match textures.get_mut(texture.name()) {
+            Some(t) if t.texture == *texture && t.generation == frame.generation => {
+                t.drawn = true;
+                return Some(t.image);
+            }
+            Some(t)
+                if t.texture == *texture && (t.width, t.height) == (frame.width, frame.height) =>
+            {
+                if let Err(e) = canvas.update_image(t.image, src, 0, 0) {
+                    println!(
+                        "Error while updating texture {:?} error: {:?}",
+                        texture.name(),
+                        e
+                    );
+                }
+                t.generation = frame.generation;
+                t.drawn = true;
+                return Some(t.image);
+            }
+            _ => (),
+        }
+
+        if let Some(t) = textures.remove(texture.name()) {
+            canvas.delete_image(t.image);
+        }
+        match canvas.create_image(src, ImageFlags::empty()) {
+            Ok(image) => {
+                textures.insert(
+                    texture.name().to_string(),
+                    GlTexture {
+                        texture: texture.clone(),
+                        image,
+                        width: frame.width,
+                        height: frame.height,
+                        generation: frame.generation,
+                        drawn: true,
+                    },
+                );
+                Some(image)
+            }
+            Err(e) => {
+                println!(
+                    "Error while creating texture {:?} error: {:?}",
+                    texture.name(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    pub(crate) fn render(&self, canvas: &mut Canvas, textures: &mut HashMap<String, GlTexture>) {
+        let Some(image) = self.update_gl(canvas, textures) else {
+            return;
+        };
+        let Instance {
+            pos, scale, radius, ..
+        } = self.instance_data;
+
+        let paint = Paint::image(image, pos.x, pos.y, scale.width, scale.height, 0.0, 1.0);
+        let mut path = Path::new();
+        path.rounded_rect(pos.x, pos.y, scale.width, scale.height, radius);
+        canvas.fill_path(&path, &paint);
+    }
+
+    /// Copy the latest frame when it is new: in place, unless its size changed.
+    fn update_software<'a>(
+        &self,
+        textures: &'a mut HashMap<String, SoftwareTexture>,
+    ) -> Option<&'a Pixmap> {
+        let texture = &self.instance_data.texture;
+        let frame = texture.frame();
+        if frame.generation == 0 {
+            return None;
+        }
+
+        let same_texture = |t: &SoftwareTexture| t.texture == *texture;
+        let mut t = match textures.remove(texture.name()) {
+            Some(t) if same_texture(&t) && t.generation == frame.generation => t,
+            Some(mut t)
+                if same_texture(&t)
+                    && (t.pixmap.width(), t.pixmap.height()) == (frame.width, frame.height) =>
+            {
+                premultiply_into(&frame, t.pixmap.data_mut());
+                t.generation = frame.generation;
+                t
+            }
+            _ => {
+                let size = IntSize::from_wh(frame.width, frame.height)?;
+                let mut data = vec![0; frame.data.len()];
+                premultiply_into(&frame, &mut data);
+                SoftwareTexture {
+                    texture: texture.clone(),
+                    pixmap: Pixmap::from_vec(data, size)?,
+                    generation: frame.generation,
+                    drawn: false,
+                }
+            }
+        };
+        t.drawn = true;
+        let t = textures.entry(texture.name().to_string()).or_insert(t);
+        Some(&t.pixmap)
+    }
+
+    pub(crate) fn render_software(
+        &self,
+        canvas: &mut SoftwareCanvas,
+        textures: &mut HashMap<String, SoftwareTexture>,
+    ) {
+        let Some(pixmap) = self.update_software(textures) else {
+            return;
+        };
+        let Instance {
+            pos, scale, radius, ..
+        } = self.instance_data;
+        let Some(path) = rounded_rect_path(
+            pos.x,
+            pos.y,
+            scale.width,
+            scale.height,
+            (radius, radius, radius, radius),
+        ) else {
+            return;
+        };
+
+        let mut paint = tiny_skia::Paint::default();
+        paint.shader = tiny_skia::Pattern::new(
+            pixmap.as_ref(),
+            tiny_skia::SpreadMode::Pad,
+            tiny_skia::FilterQuality::Bilinear,
+            1.0,
+            tiny_skia::Transform::from_row(
+                scale.width / pixmap.width() as f32,
+                0.,
+                0.,
+                scale.height / pixmap.height() as f32,
+                pos.x,
+                pos.y,
+            ),
+        );
+        paint.anti_alias = true;
+        canvas.fill_path(&path, &paint);
+    }
+}
+
+fn premultiply_into(frame: &TextureFrame, data: &mut [u8]) {
+    for (dst, src) in data.chunks_exact_mut(4).zip(frame.data.chunks_exact(4)) {
+        let a = src[3] as u32;
+        for c in 0..3 {
+            dst[c] = (src[c] as u32 * a / 255) as u8;
+        }
+        dst[3] = src[3];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture::TextureFormat;
+
+    #[test]
+    fn test_texture_copies() {
+        let mut canvas = SoftwareCanvas::new(4, 4);
+        let mut textures = HashMap::new();
+        let draw = |canvas: &mut SoftwareCanvas, textures: &mut _, texture| {
+            Texture::new(Pos::default(), Scale::new(4., 4.), texture)
+                .render_software(canvas, textures);
+            let pixel = canvas.pixmap().pixel(2, 2).unwrap();
+            [pixel.red(), pixel.green(), pixel.blue()]
+        };
+
+        let red = texture::Texture::named("test_texture_copies");
+        red.push(1, 1, TextureFormat::Rgba8, &[255, 0, 0, 255])
+            .unwrap();
+        assert_eq!(draw(&mut canvas, &mut textures, red), [255, 0, 0]);
+
+        // A texture that replaced a removed one is copied again, though its generation is the same
+        texture::Texture::remove("test_texture_copies");
+        let blue = texture::Texture::named("test_texture_copies");
+        blue.push(1, 1, TextureFormat::Rgba8, &[0, 0, 255, 255])
+            .unwrap();
+        assert_eq!(draw(&mut canvas, &mut textures, blue), [0, 0, 255]);
+        texture::Texture::remove("test_texture_copies");
+
+        // Copies are dropped once a frame goes by without drawing them
+        drop_undrawn_software(&mut textures);
+        assert_eq!(textures.len(), 1);
+        drop_undrawn_software(&mut textures);
+        assert!(textures.is_empty());
+    }
+}
@@ -2,21 +2,29 @@ use super::gl::{init_gl, init_gl_canvas};
 use super::svg::{load_svg_paths, SvgData};
 use super::text::TextRenderer;
 use super::{Caches, RendererContext};
+use crate::assets::RuntimeImages;
+use crate::damage::Damage;
 use crate::font_cache::FontCache;
+use crate::node::{Node, RenderItem};
+use crate::renderables::path::femtovg_path;
+use crate::renderables::texture::{self, GlTexture};
 use crate::renderables::Renderable;
-use crate::{node::Node, types::PixelSize};
+use crate::types::{PixelSize, Transform};
+use crate::AABB;
 use crate::{AssetParams, ImgFilter};
 use femtovg::renderer::OpenGl;
-use femtovg::{Canvas, Color, ImageFlags, ImageId, ImageSource};
+use femtovg::{
+    Canvas, Color, ImageFlags, ImageId, ImageSource, Paint, Path, PixelFormat, RenderTarget,
+};
 use glutin::api::egl;
 use glutin::api::egl::context::PossiblyCurrentContext;
 use glutin::api::egl::surface::Surface;
 use glutin::context::{PossiblyCurrentContextGlSurfaceAccessor, PossiblyCurrentGlContext};
-use glutin::surface::{GlSurface, WindowSurface};
+use glutin::surface::{GlSurface, Rect as GlRect, WindowSurface};
 use image::DynamicImage;
 use raw_window_handle::{RawDisplayHandle, RawWindowHandle};
 use std::any::Any;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
 use std::num::NonZeroU32;
 use std::sync::{Arc, RwLock};
@@ -31,6 +39,31 @@ pub struct GlCanvasContext {
     pub images: HashMap<String, ImageId>,
 }
 
+impl GlCanvasContext {
+    /// Create the EGL context and surface for the window, along with a canvas that has `assets` loaded into it.
+    pub fn new(
+        raw_display_handle: RawDisplayHandle,
+        raw_window_handle: RawWindowHandle,
+        (width, height): (u32, u32),
+        scale_factor: f32,
+        assets: HashMap<String, AssetParams>,
+    ) -> anyhow::Result<Self> {
+        let (gl_display, gl_surface, gl_context) =
+            init_gl(raw_display_handle, raw_window_handle, (width, height))?;
+        let mut gl_canvas = init_gl_canvas(&gl_display, (width, height), scale_factor)?;
+
+        // load assets
+        let images = load_assets_to_canvas(&mut gl_canvas, assets);
+
+        Ok(Self {
+            gl_context,
+            gl_surface,
+            gl_canvas,
+            images,
+        })
+    }
+}
+
 impl RendererContext for GlCanvasContext {}
 
 pub fn load_assets_to_canvas(
@@ -90,11 +123,35 @@ pub fn load_assets_to_canvas(
     loaded_assets
 }
 
+/// How many frames of damage are kept, for back buffers that are older than the last frame.
+const MAX_BUFFER_AGE: usize = 3;
+
 pub struct CanvasRenderer {
     fonts: cosmic_text::fontdb::Database,
     text_renderer: TextRenderer,
     assets: HashMap<String, ImageId>,
     svgs: HashMap<String, SvgData>,
+    // damage of the last frames, newest first
+    damage_history: VecDeque<Damage>,
+    // cached layers, by Node ID
+    layers: HashMap<u64, GlLayer>,
+    // the image that is being drawn into, if not the screen
+    target: Option<ImageId>,
+    // frames with backdrops are drawn into this image first, since they read back what is beneath them
+    frame_image: Option<ImageId>,
+    // images that can only be deleted once the canvas is flushed
+    scratch: Vec<ImageId>,
+    // streaming textures, by name
+    textures: HashMap<String, GlTexture>,
+    // images that were loaded while drawing, e.g. from the asset registry
+    runtime_images: RuntimeImages<ImageId>,
+}
+
+/// A subtree that was drawn into its own image, see [`Component::cache_layer`][crate::component::Component#method.cache_layer].
+struct GlLayer {
+    hash: u64,
+    bounds: AABB,
+    image: ImageId,
 }
 
 unsafe impl Send for CanvasRenderer {}
@@ -107,6 +164,241 @@ impl fmt::Debug for CanvasRenderer {
     }
 }
 
+impl CanvasRenderer {
+    fn draw(
+        &mut self,
+        renderable: &Renderable,
+        transform: Option<Transform>,
+        canvas: &mut Canvas<OpenGl>,
+        images: &mut HashMap<String, ImageId>,
+    ) {
+        // Not saved and restored, since scissors set by rects must outlast them
+        if let Some(t) = transform {
+            canvas.reset_transform();
+            canvas.set_transform(&t.into());
+        }
+        match renderable {
+            Renderable::Rect(rect) => {
+                rect.render(canvas);
+            }
+            Renderable::Line(line) => {
+                line.render(canvas);
+            }
+            Renderable::Circle(circle) => {
+                circle.render(canvas);
+            }
+            Renderable::Image(image) => {
+                image.render(canvas, images, &mut self.runtime_images);
+            }
+            Renderable::Svg(svg) => {
+                svg.render(canvas, &mut self.svgs);
+            }
+            Renderable::Text(text) => {
+                let scratch = text.render(canvas, &mut self.text_renderer, self.target);
+                self.scratch.extend(scratch);
+            }
+            Renderable::RadialGradient(rg) => {
+                rg.render(canvas);
+            }
+            Renderable::Curve(curve) => {
+                curve.render(canvas);
+            }
+            Renderable::Path(path) => {
+                path.render(canvas);
+            }
+            Renderable::Backdrop(backdrop) => {
+                let scratch = backdrop.render(canvas, self.target, transform);
+                self.scratch.extend(scratch);
+            }
+            Renderable::Texture(texture) => {
+                texture.render(canvas, &mut self.textures);
+            }
+        }
+        if transform.is_some() {
+            canvas.reset_transform();
+        }
+    }
+
+    /// Composite the layer of `node`, first drawing it again if anything in it changed since it was cached.
+    /// `target` is the layer that is being drawn into, if not the screen.
+    fn draw_layer(
+        &mut self,
+        node: &Node,
+        target: Option<LayerTarget>,
+        frame: AABB,
+        canvas: &mut Canvas<OpenGl>,
+        images: &mut HashMap<String, ImageId>,
+        drawn: &mut Vec<u64>,
+    ) {
+        if node.opacity <= 0.0 {
+            return;
+        }
+        let Some(bounds) = node.layer_bounds(frame) else {
+            return;
+        };
+        let hash = node.layer_hash.unwrap();
+        let visible = node.transformed(bounds);
+        drawn.push(node.id);
+
+        let image = match self.layers.remove(&node.id) {
+            Some(layer) if layer.hash == hash && layer.bounds == bounds => layer.image,
+            cached => {
+                if let Some(layer) = cached {
+                    canvas.delete_image(layer.image);
+                }
+                // Render targets are drawn bottom-up
+                let image_r = canvas.create_image_empty(
+                    bounds.width() as usize,
+                    bounds.height() as usize,
+                    PixelFormat::Rgba8,
+                    ImageFlags::FLIP_Y | ImageFlags::PREMULTIPLIED,
+                );
+                let image = match image_r {
+                    Ok(image) => image,
+                    Err(e) => {
+                        println!("Error while creating layer image error: {:?}", e);
+                        // Draw the subtree without a layer, or its opacity
+                        for (item, n, _) in node.iter_layer(visible) {
+                            match item {
+                                RenderItem::Renderable(renderable) => {
+                                    let transform = LayerTarget::transform(target, n);
+                                    self.draw(renderable, transform, canvas, images)
+                                }
+                                RenderItem::Layer(inner) => {
+                                    self.draw_layer(inner, target, visible, canvas, images, drawn)
+                                }
+                            }
+                        }
+                        return;
+                    }
+                };
+
+                let layer_target = LayerTarget {
+                    node,
+                    offset: Transform::translate(-bounds.pos.x, -bounds.pos.y),
+                };
+                let outer = self.target.replace(image);
+                canvas.save();
+                canvas.set_render_target(RenderTarget::Image(image));
+                canvas.reset_scissor();
+                canvas.clear_rect(
+                    0,
+                    0,
+                    bounds.width() as u32,
+                    bounds.height() as u32,
+                    Color::rgba(0, 0, 0, 0),
+                );
+                // The children of a clipped Node are drawn into an image of their own, that is then filled into the clip
+                let clip = node.clip_path.as_ref().and_then(|clip| {
+                    let image = canvas
+                        .create_image_empty(
+                            bounds.width() as usize,
+                            bounds.height() as usize,
+                            PixelFormat::Rgba8,
+                            ImageFlags::FLIP_Y | ImageFlags::PREMULTIPLIED,
+                        )
+                        .ok()?;
+                    self.scratch.push(image);
+                    Some((femtovg_path(clip), image))
+                });
+                let mut drawing_children = false;
+                for (item, n, _) in node.iter_layer(visible) {
+                    if let (Some((_, children_image)), false) = (&clip, drawing_children) {
+                        if n.id != node.id {
+                            drawing_children = true;
+                            self.target = Some(*children_image);
+                            canvas.set_render_target(RenderTarget::Image(*children_image));
+                            canvas.clear_rect(
+                                0,
+                                0,
+                                bounds.width() as u32,
+                                bounds.height() as u32,
+                                Color::rgba(0, 0, 0, 0),
+                            );
+                        }
+                    }
+                    match item {
+                        RenderItem::Renderable(renderable) => {
+                            let transform = LayerTarget::transform(Some(layer_target), n);
+                            self.draw(renderable, transform, canvas, images);
+                        }
+                        RenderItem::Layer(inner) => self.draw_layer(
+                            inner,
+                            Some(layer_target),
+                            visible,
+                            canvas,
+                            images,
+                            drawn,
+                        ),
+                    }
+                }
+                if let (Some((path, children_image)), true) = (&clip, drawing_children) {
+                    self.target = Some(image);
+                    canvas.set_render_target(RenderTarget::Image(image));
+                    canvas.reset_scissor();
+                    canvas.set_transform(&layer_target.offset.into());
+                    let paint = Paint::image(
+                        *children_image,
+                        bounds.pos.x,
+                        bounds.pos.y,
+                        bounds.width(),
+                        bounds.height(),
+                        0.0,
+                        1.0,
+                    );
+                    canvas.fill_path(path, &paint);
+                    canvas.reset_transform();
+                }
+                canvas.restore();
+                self.target = outer;
+                canvas.set_render_target(outer.map_or(RenderTarget::Screen, RenderTarget::Image));
+                image
+            }
+        };
+
+        let (x, y, width, height) = (bounds.pos.x, bounds.pos.y, bounds.width(), bounds.height());
+        let mut path = Path::new();
+        path.rect(x, y, width, height);
+        if let Some(t) = LayerTarget::transform(target, node) {
+            canvas.set_transform(&t.into());
+        }
+        let paint = Paint::image(image, x, y, width, height, 0.0, node.opacity);
+        canvas.fill_path(&path, &paint);
+        canvas.reset_transform();
+
+        self.layers.insert(
+            node.id,
+            GlLayer {
+                hash,
+                bounds,
+                image,
+            },
+        );
+    }
+}
+
+/// A layer that is being drawn into.
+#[derive(Clone, Copy)]
+struct LayerTarget<'a> {
+    node: &'a Node,
+    // maps the space of `node` to the image
+    offset: Transform,
+}
+
+impl LayerTarget<'_> {
+    /// How `n` is transformed when it is drawn into `target`.
+    fn transform(target: Option<LayerTarget>, n: &Node) -> Option<Transform> {
+        match target {
+            Some(target) => Some(
+                n.transform_within(target.node)
+                    .unwrap_or_default()
+                    .then(target.offset),
+            ),
+            None => n.render_transform,
+        }
+    }
+}
+
 impl super::Renderer for CanvasRenderer {
     fn new<W: crate::window::Window>(w: Arc<RwLock<W>>) -> Self {
         let window = w.read().unwrap();
@@ -127,6 +419,13 @@ impl super::Renderer for CanvasRenderer {
             text_renderer,
             assets: HashMap::new(),
             svgs: loaded_svgs,
+            damage_history: VecDeque::new(),
+            layers: HashMap::new(),
+            target: None,
+            frame_image: None,
+            scratch: vec![],
+            textures: HashMap::new(),
+            runtime_images: RuntimeImages::new(),
         }
     }
 
@@ -136,13 +435,24 @@ impl super::Renderer for CanvasRenderer {
         // that were rendered by the canvas
         // but during resizing canvas is re-initialized
         self.text_renderer.clear();
+        self.damage_history.clear();
+        self.layers.clear();
+        self.frame_image = None;
+        self.scratch.clear();
+        self.textures.clear();
+        self.runtime_images.clear();
     }
 
-    fn render(&mut self, node: &Node, _physical_size: PixelSize, ctx: &mut (dyn Any + 'static)) {
+    fn render(
+        &mut self,
+        node: &Node,
+        _physical_size: PixelSize,
+        damage: &Damage,
+        ctx: &mut (dyn Any + 'static),
+    ) {
         let context = &mut ctx.downcast_mut::<GlCanvasContext>().unwrap();
         let canvas = &mut context.gl_canvas;
         let surface: &Surface<WindowSurface> = &context.gl_surface;
-        let text_renderer = &mut self.text_renderer;
 
         let gl_context = &context.gl_context;
 
@@ -150,49 +460,152 @@ impl super::Renderer for CanvasRenderer {
             .make_current(surface)
             .expect("Failed to make newly created OpenGL context current");
 
-        canvas.clear_rect(
-            0,
-            0,
-            canvas.width(),
-            canvas.width(),
-            Color::rgba(0, 0, 0, 0),
-        );
+        let (width, height) = (canvas.width(), canvas.height());
+        let frame = AABB::new(Default::default(), [width as f32, height as f32].into());
+        let damage = damage.clip(frame);
+        if damage.is_empty() {
+            return;
+        }
 
-        for (renderable, _, _) in node.iter_renderables() {
-            match renderable {
-                Renderable::Rect(rect) => {
-                    rect.render(canvas);
-                }
-                Renderable::Line(line) => {
-                    line.render(canvas);
-                }
-                Renderable::Circle(circle) => {
-                    circle.render(canvas);
-                }
-                Renderable::Image(image) => {
-                    image.render(canvas, &mut context.images);
-                }
-                Renderable::Svg(svg) => {
-                    svg.render(canvas, &mut self.svgs);
-                }
-                Renderable::Text(text) => {
-                    text.render(canvas, text_renderer);
+        // The back buffer holds the frame from `buffer_age` swaps ago,
+        // so it is also missing the damage of every frame since
+        let age = surface.buffer_age() as usize;
+        let mut repaint = damage.clone();
+        if age == 0 || age > self.damage_history.len() + 1 {
+            repaint = Damage::full().clip(frame);
+        } else {
+            for d in self.damage_history.iter().take(age - 1) {
+                repaint.merge(d);
+            }
+        }
+        self.damage_history.push_front(damage.clone());
+        self.damage_history.truncate(MAX_BUFFER_AGE);
+
+        self.runtime_images.next_frame();
+
+        // Backdrops blur what was drawn beneath them, which cannot be read back from the screen
+        let has_backdrop = node
+            .iter_renderables()
+            .any(|(r, _, _)| matches!(r, Renderable::Backdrop(_)));
+        match self.frame_image {
+            Some(image) if !has_backdrop => {
+                canvas.delete_image(image);
+                self.frame_image = None;
+            }
+            None if has_backdrop => {
+                match canvas.create_image_empty(
+                    width as usize,
+                    height as usize,
+                    PixelFormat::Rgba8,
+                    ImageFlags::FLIP_Y | ImageFlags::PREMULTIPLIED,
+                ) {
+                    Ok(image) => {
+                        self.frame_image = Some(image);
+                        // Nothing has been drawn into it yet
+                        repaint = Damage::full().clip(frame);
+                    }
+                    Err(e) => println!("Error while creating frame image error: {:?}", e),
                 }
-                Renderable::RadialGradient(rg) => {
-                    rg.render(canvas);
+            }
+            _ => (),
+        }
+
+        // femtovg can only scissor to a single rect
+        let bounds = if repaint.is_full() {
+            None
+        } else {
+            repaint.bounds()
+        };
+        let clear = bounds.unwrap_or(frame);
+        let clear = |canvas: &mut Canvas<OpenGl>| {
+            canvas.clear_rect(
+                clear.pos.x as u32,
+                clear.pos.y as u32,
+                clear.width() as u32,
+                clear.height() as u32,
+                Color::rgba(0, 0, 0, 0),
+            )
+        };
+        clear(canvas);
+        if let Some(image) = self.frame_image {
+            canvas.set_render_target(RenderTarget::Image(image));
+            clear(canvas);
+        }
+        self.target = self.frame_image;
+        if let Some(b) = bounds {
+            canvas.scissor(b.pos.x, b.pos.y, b.width(), b.height());
+        }
+
+        let mut drawn = vec![];
+        for (item, n, _) in node.iter_layered() {
+            match item {
+                RenderItem::Renderable(renderable) => {
+                    self.draw(renderable, n.render_transform, canvas, &mut context.images);
+
+                    // Keep scissors set by rects within the repainted region
+                    if let (Renderable::Rect(rect), Some(b)) = (renderable, bounds) {
+                        match rect.instance_data.scissor {
+                            Some(true) => {
+                                canvas.intersect_scissor(b.pos.x, b.pos.y, b.width(), b.height())
+                            }
+                            Some(false) => canvas.scissor(b.pos.x, b.pos.y, b.width(), b.height()),
+                            None => (),
+                        }
+                    }
                 }
-                Renderable::Curve(curve) => {
-                    curve.render(canvas);
+                RenderItem::Layer(node) => {
+                    self.draw_layer(node, None, frame, canvas, &mut context.images, &mut drawn)
                 }
             }
         }
+        // Drop the layers of Nodes that are gone, hidden, or inside of a layer that was reused
+        self.layers.retain(|id, layer| {
+            let keep = drawn.contains(id);
+            if !keep {
+                canvas.delete_image(layer.image);
+            }
+            keep
+        });
+
+        if let Some(image) = self.target.take() {
+            canvas.set_render_target(RenderTarget::Screen);
+            canvas.reset_scissor();
+            if let Some(b) = bounds {
+                canvas.scissor(b.pos.x, b.pos.y, b.width(), b.height());
+            }
+            let mut path = Path::new();
+            path.rect(0.0, 0.0, width as f32, height as f32);
+            let paint = Paint::image(image, 0.0, 0.0, width as f32, height as f32, 0.0, 1.0);
+            canvas.fill_path(&path, &paint);
+        }
 
         // Tell renderer to execute all drawing commands
         canvas.flush();
+        canvas.reset_scissor();
+        for image in self.scratch.drain(..) {
+            canvas.delete_image(image);
+        }
+        for image in self.runtime_images.take_evicted() {
+            canvas.delete_image(image);
+        }
+        texture::drop_undrawn_gl(canvas, &mut self.textures);
 
-        // Display what we've just rendered
+        // Display what we've just rendered, telling the compositor what changed.
+        // EGL damage rects have their origin at the bottom left.
+        let rects: Vec<GlRect> = damage
+            .rects()
+            .iter()
+            .map(|r| {
+                GlRect::new(
+                    r.pos.x as i32,
+                    height as i32 - r.bottom_right.y as i32,
+                    r.width() as i32,
+                    r.height() as i32,
+                )
+            })
+            .collect();
         surface
-            .swap_buffers(&gl_context)
+            .swap_buffers_with_damage(gl_context, &rects)
             .expect("Could not swap buffers");
     }
 
@@ -1,3 +1,4 @@
+use anyhow::Context;
 use femtovg::renderer::OpenGl;
 use femtovg::Canvas;
 use glutin::api::egl::context::PossiblyCurrentContext;
@@ -14,11 +15,10 @@ pub fn init_gl_surface_context(
     raw_window_handle: RawWindowHandle,
     (width, height): (u32, u32),
     gl_display: &Display,
-) -> (Surface<WindowSurface>, PossiblyCurrentContext) {
+) -> anyhow::Result<(Surface<WindowSurface>, PossiblyCurrentContext)> {
     let template = ConfigTemplateBuilder::new().with_alpha_size(8).build();
 
-    let config = unsafe { gl_display.find_configs(template) }
-        .unwrap()
+    let config = unsafe { gl_display.find_configs(template) }?
         .reduce(|config, acc| {
             if config.num_samples() > acc.num_samples() {
                 config
@@ -26,7 +26,7 @@ pub fn init_gl_surface_context(
                 acc
             }
         })
-        .expect("No available configs");
+        .context("No available configs")?;
 
     let context_attributes = ContextAttributesBuilder::new().build(Some(raw_window_handle));
 
@@ -36,62 +36,62 @@ pub fn init_gl_surface_context(
         .with_context_api(ContextApi::Gles(None))
         .build(Some(raw_window_handle));
     let mut not_current_gl_context = Some(unsafe {
-        gl_display
-            .create_context(&config, &context_attributes)
-            .unwrap_or_else(|_| {
-                gl_display
-                    .create_context(&config, &fallback_context_attributes)
-                    .expect("failed to create context")
-            })
+        match gl_display.create_context(&config, &context_attributes) {
+            Ok(context) => context,
+            Err(_) => gl_display
+                .create_context(&config, &fallback_context_attributes)
+                .context("failed to create context")?,
+        }
     });
 
     let attrs = SurfaceAttributesBuilder::<WindowSurface>::new().build(
         raw_window_handle,
-        NonZeroU32::new(width).unwrap(),
-        NonZeroU32::new(height).unwrap(),
+        NonZeroU32::new(width).context("Surface width is zero")?,
+        NonZeroU32::new(height).context("Surface height is zero")?,
     );
 
     let gl_surface = unsafe {
         gl_display
             .create_window_surface(&config, &attrs)
-            .expect("Failed to create OpenGl surface")
+            .context("Failed to create OpenGl surface")?
     };
 
     let gl_context = not_current_gl_context
         .take()
         .unwrap()
         .make_current(&gl_surface)
-        .expect("Failed to make newly created OpenGL context current");
+        .context("Failed to make newly created OpenGL context current")?;
 
-    (gl_surface, gl_context)
+    Ok((gl_surface, gl_context))
 }
 
 pub fn init_gl(
     raw_display_handle: RawDisplayHandle,
     raw_window_handle: RawWindowHandle,
     (width, height): (u32, u32),
-) -> (Display, Surface<WindowSurface>, PossiblyCurrentContext) {
+) -> anyhow::Result<(Display, Surface<WindowSurface>, PossiblyCurrentContext)> {
     let gl_display =
-        unsafe { Display::new(raw_display_handle).expect("Failed to create EGL Display") };
+        unsafe { Display::new(raw_display_handle).context("Failed to create EGL Display")? };
 
     let (gl_surface, gl_context) =
-        init_gl_surface_context(raw_window_handle, (width, height), &gl_display);
+        init_gl_surface_context(raw_window_handle, (width, height), &gl_display)?;
 
-    (gl_display, gl_surface, gl_context)
+    Ok((gl_display, gl_surface, gl_context))
 }
 
 pub fn init_gl_canvas(
     gl_display: &Display,
     (width, height): (u32, u32),
     scale_factor: f32,
-) -> Canvas<OpenGl> {
+) -> anyhow::Result<Canvas<OpenGl>> {
     let renderer =
         unsafe { OpenGl::new_from_function_cstr(|s| gl_display.get_proc_address(s) as *const _) }
-            .expect("cannot create opengl renderer");
+            .map_err(|e| anyhow::anyhow!("cannot create opengl renderer: {:?}", e))?;
 
     // create femtovg canvas
-    let mut canvas = Canvas::new(renderer).expect("Cannot create canvas");
+    let mut canvas =
+        Canvas::new(renderer).map_err(|e| anyhow::anyhow!("Cannot create canvas: {:?}", e))?;
     canvas.set_size(width, height, scale_factor);
 
-    canvas
+    Ok(canvas)
 }
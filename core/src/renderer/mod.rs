@@ -1,11 +1,13 @@
 pub mod canvas;
 pub mod gl;
+pub mod software;
 pub mod svg;
 pub mod text;
 
-use canvas::GlCanvasContext;
+use canvas::{CanvasRenderer, GlCanvasContext};
+use software::SoftwareRenderer;
 
-use crate::{font_cache::FontCache, window::Window, Node, PixelSize};
+use crate::{damage::Damage, font_cache::FontCache, window::Window, Node, PixelSize};
 use std::{
     any::Any,
     fmt,
@@ -33,9 +35,95 @@ pub trait RendererContext {}
 pub(crate) trait Renderer: fmt::Debug + std::marker::Sized + Send + Sync {
     fn new<W: Window>(window: Arc<RwLock<W>>) -> Self;
     fn configure<W: crate::window::Window>(&mut self, window: Arc<RwLock<W>>) {}
-    fn render(&mut self, _node: &Node, _physical_size: PixelSize, ctx: &mut (dyn Any + 'static)) {}
+    /// Paint the renderables of `node` into `ctx`. Only the regions in `damage` need to be repainted,
+    /// everything else in the frame is left as it was by the previous render.
+    fn render(
+        &mut self,
+        _node: &Node,
+        _physical_size: PixelSize,
+        _damage: &Damage,
+        ctx: &mut (dyn Any + 'static),
+    ) {
+    }
     fn resize(&mut self, width: u32, height: u32) {}
     // use this method to clear any saved references or caches
     fn clear(&mut self) {}
     fn caches(&self) -> Caches;
 }
+
+/// The [`Renderer`] implementations that a [`Window`] can choose between, with [`Window#renderer_type`][crate::window::Window#method.renderer_type].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RendererType {
+    /// Draws on the GPU with femtovg, through an EGL context.
+    #[default]
+    Canvas,
+    /// Draws on the CPU with tiny-skia, into an RGBA buffer that is handed to [`Window#present`][crate::window::Window#method.present].
+    Software,
+}
+
+/// The renderer used by the [`UI`][crate::ui::UI], selected at runtime from the [`RendererType`] of its window.
+#[derive(Debug)]
+pub(crate) enum ActiveRenderer {
+    Canvas(CanvasRenderer),
+    Software(SoftwareRenderer),
+}
+
+impl ActiveRenderer {
+    pub fn renderer_type(&self) -> RendererType {
+        match self {
+            Self::Canvas(_) => RendererType::Canvas,
+            Self::Software(_) => RendererType::Software,
+        }
+    }
+}
+
+impl Renderer for ActiveRenderer {
+    fn new<W: Window>(window: Arc<RwLock<W>>) -> Self {
+        let renderer_type = window.read().unwrap().renderer_type();
+        match renderer_type {
+            RendererType::Canvas => Self::Canvas(CanvasRenderer::new(window)),
+            RendererType::Software => Self::Software(SoftwareRenderer::new(window)),
+        }
+    }
+
+    fn configure<W: Window>(&mut self, window: Arc<RwLock<W>>) {
+        match self {
+            Self::Canvas(r) => r.configure(window),
+            Self::Software(r) => r.configure(window),
+        }
+    }
+
+    fn render(
+        &mut self,
+        node: &Node,
+        physical_size: PixelSize,
+        damage: &Damage,
+        ctx: &mut (dyn Any + 'static),
+    ) {
+        match self {
+            Self::Canvas(r) => r.render(node, physical_size, damage, ctx),
+            Self::Software(r) => r.render(node, physical_size, damage, ctx),
+        }
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        match self {
+            Self::Canvas(r) => r.resize(width, height),
+            Self::Software(r) => r.resize(width, height),
+        }
+    }
+
+    fn clear(&mut self) {
+        match self {
+            Self::Canvas(r) => r.clear(),
+            Self::Software(r) => r.clear(),
+        }
+    }
+
+    fn caches(&self) -> Caches {
+        match self {
+            Self::Canvas(r) => r.caches(),
+            Self::Software(r) => r.caches(),
+        }
+    }
+}
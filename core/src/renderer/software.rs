@@ -0,0 +1,769 @@
+use super::text::TextRenderer;
+use super::{Caches, RendererContext};
+use crate::assets::RuntimeImages;
+use crate::damage::Damage;
+use crate::font_cache::FontCache;
+use crate::node::{Node, RenderItem};
+use crate::renderables::texture::{self, SoftwareTexture};
+use crate::renderables::Renderable;
+use crate::types::{PathCommand, PixelSize, Pos, Scale};
+use crate::AABB;
+use crate::{AssetParams, ImgFilter};
+use femtovg::CompositeOperation;
+use image::DynamicImage;
+use resvg::tiny_skia::{
+    self, BlendMode, FillRule, IntRect, IntSize, Mask, Paint, Path, PathBuilder, Pixmap,
+    PixmapPaint, PixmapRef, Rect, Stroke, Transform,
+};
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+/// A CPU-backed drawing surface. Wraps a premultiplied RGBA8 [`Pixmap`] along with the current scissor.
+pub struct SoftwareCanvas {
+    pixmap: Pixmap,
+    scissor: Option<Mask>,
+    // the damaged regions being repainted, that every scissor is intersected with
+    clip: Option<Mask>,
+    composite_operation: CompositeOperation,
+    global_alpha: f32,
+    // offset applied to everything that is drawn
+    translation: (f32, f32),
+    // transform of the Node being drawn, applied before the translation
+    transform: Transform,
+}
+
+impl SoftwareCanvas {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            pixmap: Pixmap::new(width.max(1), height.max(1)).unwrap(),
+            scissor: None,
+            clip: None,
+            composite_operation: CompositeOperation::SourceOver,
+            global_alpha: 1.0,
+            translation: (0., 0.),
+            transform: Transform::identity(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.pixmap.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.pixmap.height()
+    }
+
+    /// The premultiplied RGBA8 pixels of the canvas, row by row.
+    pub fn data(&self) -> &[u8] {
+        self.pixmap.data()
+    }
+
+    pub fn pixmap(&self) -> &Pixmap {
+        &self.pixmap
+    }
+
+    pub fn pixmap_mut(&mut self) -> &mut Pixmap {
+        &mut self.pixmap
+    }
+
+    pub fn clear(&mut self) {
+        self.pixmap.fill(tiny_skia::Color::TRANSPARENT);
+        self.scissor = None;
+        self.clip = None;
+        self.composite_operation = CompositeOperation::SourceOver;
+        self.global_alpha = 1.0;
+    }
+
+    /// Offset everything that is drawn from now on by `(x, y)`, e.g. to draw part of a frame into a smaller canvas.
+    pub fn translate(&mut self, x: f32, y: f32) {
+        self.translation.0 += x;
+        self.translation.1 += y;
+    }
+
+    /// Transform everything that is drawn from now on, until it is set back to [`Transform::identity`].
+    /// Applies before any [`translate`][Self::translate].
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    fn transform(&self) -> Transform {
+        self.transform
+            .post_translate(self.translation.0, self.translation.1)
+    }
+
+    pub fn global_composite_operation(&mut self, composite_operation: CompositeOperation) {
+        self.composite_operation = composite_operation;
+    }
+
+    /// Multiply the alpha of everything that is drawn from now on by `alpha`.
+    pub fn set_global_alpha(&mut self, alpha: f32) {
+        self.global_alpha = alpha.clamp(0.0, 1.0);
+    }
+
+    /// Restrict all subsequent drawing to the given rect, until [`reset_scissor`][Self::reset_scissor] is called.
+    /// The scissor never extends past the current [`clip`][Self::clip].
+    pub fn scissor(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        let Some(rect) = Rect::from_xywh(x, y, width.max(0.), height.max(0.)) else {
+            self.scissor = Mask::new(self.width(), self.height());
+            return;
+        };
+        let path = PathBuilder::from_rect(rect);
+        let transform = self.transform();
+
+        self.scissor = match &self.clip {
+            Some(clip) => {
+                let mut mask = clip.clone();
+                mask.intersect_path(&path, FillRule::Winding, false, transform);
+                Some(mask)
+            }
+            None => Mask::new(self.width(), self.height()).map(|mut mask| {
+                mask.fill_path(&path, FillRule::Winding, false, transform);
+                mask
+            }),
+        };
+    }
+
+    pub fn reset_scissor(&mut self) {
+        self.scissor = self.clip.clone();
+    }
+
+    /// Clear the given (damaged) rects, and restrict all drawing to them until [`reset_clip`][Self::reset_clip] is called.
+    pub fn clip(&mut self, rects: &[AABB]) {
+        let Some(mut mask) = Mask::new(self.width(), self.height()) else {
+            return;
+        };
+
+        let mut clear = Paint::default();
+        clear.set_color(tiny_skia::Color::TRANSPARENT);
+        clear.blend_mode = BlendMode::Source;
+
+        for r in rects.iter() {
+            if let Some(rect) =
+                Rect::from_ltrb(r.pos.x, r.pos.y, r.bottom_right.x, r.bottom_right.y)
+            {
+                mask.fill_path(
+                    &PathBuilder::from_rect(rect),
+                    FillRule::Winding,
+                    false,
+                    Transform::identity(),
+                );
+                self.pixmap
+                    .fill_rect(rect, &clear, Transform::identity(), None);
+            }
+        }
+
+        self.clip = Some(mask);
+        self.scissor = self.clip.clone();
+    }
+
+    pub fn reset_clip(&mut self) {
+        self.clip = None;
+        self.scissor = None;
+    }
+
+    pub fn fill_path(&mut self, path: &Path, paint: &Paint) {
+        self.fill_path_with_rule(path, paint, FillRule::Winding);
+    }
+
+    /// Like [`fill_path`][Self::fill_path], with the given rule for which areas are inside of the path.
+    pub fn fill_path_with_rule(&mut self, path: &Path, paint: &Paint, fill_rule: FillRule) {
+        let mut paint = paint.clone();
+        paint.blend_mode = blend_mode(self.composite_operation);
+        paint.shader.apply_opacity(self.global_alpha);
+        self.pixmap.fill_path(
+            path,
+            &paint,
+            fill_rule,
+            self.transform(),
+            self.scissor.as_ref(),
+        );
+    }
+
+    pub fn stroke_path(&mut self, path: &Path, paint: &Paint, stroke: &Stroke) {
+        let mut paint = paint.clone();
+        paint.blend_mode = blend_mode(self.composite_operation);
+        paint.shader.apply_opacity(self.global_alpha);
+        self.pixmap.stroke_path(
+            path,
+            &paint,
+            stroke,
+            self.transform(),
+            self.scissor.as_ref(),
+        );
+    }
+
+    /// Draw `pixmap` with its top left at `(x, y)`, respecting the scissor.
+    pub fn draw_pixmap(&mut self, x: i32, y: i32, pixmap: PixmapRef) {
+        let paint = PixmapPaint {
+            blend_mode: blend_mode(self.composite_operation),
+            opacity: self.global_alpha,
+            ..Default::default()
+        };
+        self.pixmap.draw_pixmap(
+            x,
+            y,
+            pixmap,
+            &paint,
+            self.transform(),
+            self.scissor.as_ref(),
+        );
+    }
+
+    /// Replace what was drawn beneath `path` with a blurred copy of it, for a frosted glass look.
+    /// `sigma` is the standard deviation of the blur, which samples up to `3 * sigma` pixels past the path.
+    pub fn blur_behind(&mut self, path: &Path, sigma: f32) {
+        let transform = self.transform();
+        let Some(bounds) = path.clone().transform(transform).map(|p| p.bounds()) else {
+            return;
+        };
+        let margin = (sigma * 3.).ceil();
+        let Some(rect) = IntRect::from_ltrb(
+            ((bounds.left() - margin).floor() as i32).max(0),
+            ((bounds.top() - margin).floor() as i32).max(0),
+            ((bounds.right() + margin).ceil() as i32).min(self.width() as i32),
+            ((bounds.bottom() + margin).ceil() as i32).min(self.height() as i32),
+        ) else {
+            return;
+        };
+        let Some(mut backdrop) = self.pixmap.clone_rect(rect) else {
+            return;
+        };
+        blur(&mut backdrop, sigma);
+
+        // Only keep the blur within the path, then swap it in for what is beneath
+        clip_to_path(
+            &mut backdrop,
+            path,
+            transform.post_translate(-rect.x() as f32, -rect.y() as f32),
+        );
+        let mut paint = Paint::default();
+        paint.anti_alias = true;
+        paint.set_color_rgba8(0, 0, 0, (self.global_alpha * 255.) as u8);
+        paint.blend_mode = BlendMode::DestinationOut;
+        self.pixmap.fill_path(
+            path,
+            &paint,
+            FillRule::Winding,
+            transform,
+            self.scissor.as_ref(),
+        );
+        let paint = PixmapPaint {
+            opacity: self.global_alpha,
+            ..Default::default()
+        };
+        self.pixmap.draw_pixmap(
+            rect.x(),
+            rect.y(),
+            backdrop.as_ref(),
+            &paint,
+            Transform::identity(),
+            self.scissor.as_ref(),
+        );
+    }
+
+    /// Blend a single straight-alpha RGBA8 pixel onto the canvas with source-over.
+    pub fn blend_pixel(&mut self, x: i32, y: i32, color: [u8; 4]) {
+        let transform = self.transform();
+        if transform.sx != 1.0 || transform.sy != 1.0 || transform.kx != 0.0 || transform.ky != 0.0
+        {
+            // Pixels no longer line up, so draw a transformed quad instead
+            if let Some(rect) = Rect::from_xywh(x as f32, y as f32, 1.0, 1.0) {
+                let mut paint = Paint::default();
+                paint.set_color_rgba8(color[0], color[1], color[2], color[3]);
+                self.fill_path(&PathBuilder::from_rect(rect), &paint);
+            }
+            return;
+        }
+
+        let x = x + transform.tx.round() as i32;
+        let y = y + transform.ty.round() as i32;
+        let (width, height) = (self.width() as i32, self.height() as i32);
+        if x < 0 || y < 0 || x >= width || y >= height || color[3] == 0 {
+            return;
+        }
+
+        let i = (y * width + x) as usize;
+        let mut alpha = (color[3] as f32 * self.global_alpha) as u32;
+        if let Some(mask) = &self.scissor {
+            alpha = alpha * mask.data()[i] as u32 / 255;
+        }
+        let inv = 255 - alpha;
+
+        let data = self.pixmap.data_mut();
+        let pixel = &mut data[i * 4..i * 4 + 4];
+        for c in 0..3 {
+            pixel[c] = ((color[c] as u32 * alpha + pixel[c] as u32 * inv) / 255) as u8;
+        }
+        pixel[3] = (alpha + pixel[3] as u32 * inv / 255) as u8;
+    }
+}
+
+pub(crate) fn blend_mode(composite_operation: CompositeOperation) -> BlendMode {
+    match composite_operation {
+        CompositeOperation::SourceOver => BlendMode::SourceOver,
+        CompositeOperation::SourceIn => BlendMode::SourceIn,
+        CompositeOperation::SourceOut => BlendMode::SourceOut,
+        CompositeOperation::Atop => BlendMode::SourceAtop,
+        CompositeOperation::DestinationOver => BlendMode::DestinationOver,
+        CompositeOperation::DestinationIn => BlendMode::DestinationIn,
+        CompositeOperation::DestinationOut => BlendMode::DestinationOut,
+        CompositeOperation::DestinationAtop => BlendMode::DestinationAtop,
+        CompositeOperation::Lighter => BlendMode::Plus,
+        CompositeOperation::Copy => BlendMode::Source,
+        CompositeOperation::Xor => BlendMode::Xor,
+    }
+}
+
+/// Build a rect path with a separate radius for each corner, in the order
+/// top left, top right, bottom right, bottom left. See [`PathCommand::rounded_rect`].
+pub(crate) fn rounded_rect_path(
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    radius: (f32, f32, f32, f32),
+) -> Option<Path> {
+    let (tl, tr, br, bl) = radius;
+    if tl.max(tr).max(br).max(bl) <= 0. || width.min(height) <= 0. {
+        return Rect::from_xywh(x, y, width, height).map(PathBuilder::from_rect);
+    }
+
+    let aabb = AABB::new(Pos::new(x, y, 0.), Scale::new(width, height));
+    path_from_commands(&PathCommand::rounded_rect(aabb, radius))
+}
+
+/// Build a path out of [`PathCommand`]s, if they describe one.
+pub(crate) fn path_from_commands(commands: &[PathCommand]) -> Option<Path> {
+    let mut pb = PathBuilder::new();
+    for command in PathCommand::expand_arcs(commands) {
+        match command {
+            PathCommand::MoveTo(p) => pb.move_to(p.x, p.y),
+            PathCommand::LineTo(p) => pb.line_to(p.x, p.y),
+            PathCommand::QuadTo(c, p) => pb.quad_to(c.x, c.y, p.x, p.y),
+            PathCommand::CubicTo(c1, c2, p) => pb.cubic_to(c1.x, c1.y, c2.x, c2.y, p.x, p.y),
+            PathCommand::Close => pb.close(),
+            PathCommand::Arc { .. } => unreachable!("Arcs are expanded into curves"),
+        }
+    }
+    pb.finish()
+}
+
+/// Erase everything in `pixmap` that lies outside of `path`.
+pub(crate) fn clip_to_path(pixmap: &mut Pixmap, path: &Path, transform: Transform) {
+    if let Some(mut mask) = Mask::new(pixmap.width(), pixmap.height()) {
+        mask.fill_path(path, FillRule::Winding, true, transform);
+        pixmap.apply_mask(&mask);
+    }
+}
+
+/// Approximate a gaussian blur with a standard deviation of `sigma`, with three box blurs.
+/// Pixels beyond the edges are treated as transparent.
+pub(crate) fn blur(pixmap: &mut Pixmap, sigma: f32) {
+    if sigma <= 0. {
+        return;
+    }
+    let (width, height) = (pixmap.width() as usize, pixmap.height() as usize);
+    let mut data = pixmap.data().to_vec();
+    let mut pass = vec![0; data.len()];
+    for radius in box_blur_radii(sigma) {
+        box_blur(&data, &mut pass, width, height, 4, width * 4, radius);
+        box_blur(&pass, &mut data, height, width, width * 4, 4, radius);
+    }
+    pixmap.data_mut().copy_from_slice(&data);
+}
+
+/// The radii of three box blurs that add up to a gaussian blur.
+/// See Kovesi, "Fast Almost-Gaussian Filtering".
+fn box_blur_radii(sigma: f32) -> [usize; 3] {
+    let n = 3.;
+    let ideal = (12. * sigma * sigma / n + 1.).sqrt();
+    let mut lower = ideal.floor();
+    if lower % 2. == 0. {
+        lower -= 1.;
+    }
+    let upper = lower + 2.;
+    let m = ((12. * sigma * sigma - n * lower * lower - 4. * n * lower - 3. * n)
+        / (-4. * lower - 4.))
+        .round();
+
+    let mut radii = [0; 3];
+    for (i, radius) in radii.iter_mut().enumerate() {
+        let size = if (i as f32) < m { lower } else { upper };
+        *radius = ((size - 1.) / 2.).max(0.) as usize;
+    }
+    radii
+}
+
+/// Blur `lines` lines of `len` pixels each with a box of `radius` pixels on either side,
+/// where pixels are `step` bytes apart within a line, and lines are `line_step` bytes apart.
+fn box_blur(
+    src: &[u8],
+    dst: &mut [u8],
+    len: usize,
+    lines: usize,
+    step: usize,
+    line_step: usize,
+    radius: usize,
+) {
+    let size = (2 * radius + 1) as u32;
+    for line in 0..lines {
+        let start = line * line_step;
+        for c in 0..4 {
+            let at = |i: usize| src[start + i * step + c] as u32;
+            let mut sum: u32 = (0..=radius.min(len - 1)).map(at).sum();
+            for i in 0..len {
+                dst[start + i * step + c] = ((sum + size / 2) / size) as u8;
+                if i + radius + 1 < len {
+                    sum += at(i + radius + 1);
+                }
+                if i >= radius {
+                    sum -= at(i - radius);
+                }
+            }
+        }
+    }
+}
+
+/// Convert a decoded image into a premultiplied [`Pixmap`].
+pub(crate) fn pixmap_from_image(image: &DynamicImage) -> Option<Pixmap> {
+    let rgba = image.to_rgba8();
+    let size = IntSize::from_wh(rgba.width(), rgba.height())?;
+    let mut data = rgba.into_raw();
+    for pixel in data.chunks_exact_mut(4) {
+        let a = pixel[3] as u32;
+        for c in 0..3 {
+            pixel[c] = (pixel[c] as u32 * a / 255) as u8;
+        }
+    }
+    Pixmap::from_vec(data, size)
+}
+
+pub(crate) fn load_image_file(path: &str) -> Option<Pixmap> {
+    match image::open(path) {
+        Ok(image) => pixmap_from_image(&image),
+        Err(e) => {
+            println!("Error while opening image {:?} error: {:?}", path, e);
+            None
+        }
+    }
+}
+
+pub fn load_assets_to_pixmaps(assets: HashMap<String, AssetParams>) -> HashMap<String, Pixmap> {
+    let mut loaded_assets = HashMap::new();
+
+    for (name, params) in assets.into_iter() {
+        let AssetParams { path, filter, blur } = params;
+        let image_r = image::open(path);
+
+        if let Err(e) = image_r {
+            println!("Error while opening image {:?} error: {:?}", name, e);
+            continue;
+        }
+
+        let mut image = image_r.unwrap();
+
+        if let Some(sigma) = blur {
+            image = image.blur(sigma);
+        }
+
+        if let ImgFilter::GRAY = filter {
+            image = DynamicImage::ImageRgb8(image.grayscale().into_rgb8());
+        }
+
+        match pixmap_from_image(&image) {
+            Some(pixmap) => {
+                loaded_assets.insert(name, pixmap);
+            }
+            None => println!("Error while creating image {:?}", name),
+        }
+    }
+    loaded_assets
+}
+
+pub(crate) fn load_svg_tree(file_path: &str, fonts: &usvg::fontdb::Database) -> Option<usvg::Tree> {
+    let svg_data = match std::fs::read(file_path) {
+        Ok(file) => file,
+        Err(e) => {
+            println!("error {:?} path {:?}", e, file_path);
+            return None;
+        }
+    };
+
+    match usvg::Tree::from_data(&svg_data, &usvg::Options::default(), fonts) {
+        Ok(tree) => Some(tree),
+        Err(e) => {
+            println!("error {:?} path {:?}", e, file_path);
+            None
+        }
+    }
+}
+
+pub fn load_svg_trees(
+    svgs: HashMap<String, String>,
+    fonts: &usvg::fontdb::Database,
+) -> HashMap<String, usvg::Tree> {
+    let mut loaded_svgs = HashMap::new();
+
+    for (name, path) in svgs.into_iter() {
+        if let Some(tree) = load_svg_tree(&path, fonts) {
+            loaded_svgs.insert(name, tree);
+        }
+    }
+
+    loaded_svgs
+}
+
+pub struct SoftwareCanvasContext {
+    // the frame being drawn
+    pub canvas: SoftwareCanvas,
+    // canvas images
+    pub images: HashMap<String, Pixmap>,
+}
+
+impl SoftwareCanvasContext {
+    pub fn new(width: u32, height: u32, assets: HashMap<String, AssetParams>) -> Self {
+        Self {
+            canvas: SoftwareCanvas::new(width, height),
+            images: load_assets_to_pixmaps(assets),
+        }
+    }
+}
+
+impl RendererContext for SoftwareCanvasContext {}
+
+/// A [`Renderer`][super::Renderer] that draws on the CPU, using tiny-skia.
+/// Frames are drawn into the [`SoftwareCanvas`] of a [`SoftwareCanvasContext`].
+pub struct SoftwareRenderer {
+    fonts: cosmic_text::fontdb::Database,
+    text_renderer: TextRenderer,
+    svgs: HashMap<String, usvg::Tree>,
+    // cached layers, by Node ID
+    layers: HashMap<u64, SoftwareLayer>,
+    // streaming textures, by name
+    textures: HashMap<String, SoftwareTexture>,
+    // images that were loaded while drawing, e.g. from the asset registry
+    runtime_images: RuntimeImages<Pixmap>,
+}
+
+/// A subtree that was drawn into its own canvas, see [`Component::cache_layer`][crate::component::Component#method.cache_layer].
+struct SoftwareLayer {
+    hash: u64,
+    bounds: AABB,
+    canvas: SoftwareCanvas,
+}
+
+unsafe impl Send for SoftwareRenderer {}
+unsafe impl Sync for SoftwareRenderer {}
+
+impl fmt::Debug for SoftwareRenderer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SoftwareRenderer")?;
+        Ok(())
+    }
+}
+
+impl SoftwareRenderer {
+    fn draw(
+        &mut self,
+        renderable: &Renderable,
+        transform: Transform,
+        canvas: &mut SoftwareCanvas,
+        images: &mut HashMap<String, Pixmap>,
+    ) {
+        canvas.set_transform(transform);
+        match renderable {
+            Renderable::Rect(rect) => {
+                rect.render_software(canvas);
+            }
+            Renderable::Line(line) => {
+                line.render_software(canvas);
+            }
+            Renderable::Circle(circle) => {
+                circle.render_software(canvas);
+            }
+            Renderable::Image(image) => {
+                image.render_software(canvas, images, &mut self.runtime_images);
+            }
+            Renderable::Svg(svg) => {
+                svg.render_software(canvas, &mut self.svgs, &self.fonts);
+            }
+            Renderable::Text(text) => {
+                text.render_software(canvas, &mut self.text_renderer);
+            }
+            Renderable::RadialGradient(rg) => {
+                rg.render_software(canvas);
+            }
+            Renderable::Curve(curve) => {
+                curve.render_software(canvas);
+            }
+            Renderable::Path(path) => {
+                path.render_software(canvas);
+            }
+            Renderable::Backdrop(backdrop) => {
+                backdrop.render_software(canvas);
+            }
+            Renderable::Texture(texture) => {
+                texture.render_software(canvas, &mut self.textures);
+            }
+        }
+        canvas.set_transform(Transform::identity());
+    }
+
+    /// Composite the layer of `node`, first drawing it again if anything in it changed since it was cached.
+    /// `outer` is the Node whose layer `canvas` belongs to, if any.
+    fn draw_layer(
+        &mut self,
+        node: &Node,
+        outer: Option<&Node>,
+        frame: AABB,
+        canvas: &mut SoftwareCanvas,
+        images: &mut HashMap<String, Pixmap>,
+        drawn: &mut Vec<u64>,
+    ) {
+        if node.opacity <= 0.0 {
+            return;
+        }
+        let Some(bounds) = node.layer_bounds(frame) else {
+            return;
+        };
+        let hash = node.layer_hash.unwrap();
+        drawn.push(node.id);
+
+        let layer = match self.layers.remove(&node.id) {
+            Some(layer) if layer.hash == hash && layer.bounds == bounds => layer,
+            _ => {
+                let new_canvas = || {
+                    let mut canvas =
+                        SoftwareCanvas::new(bounds.width() as u32, bounds.height() as u32);
+                    canvas.translate(-bounds.pos.x, -bounds.pos.y);
+                    canvas
+                };
+                let mut layer_canvas = new_canvas();
+                // The children of a clipped Node are drawn apart from it, and then clipped
+                let mut children_canvas = node.clip_path.as_ref().map(|_| new_canvas());
+                let visible = node.transformed(bounds);
+                for (item, n, _) in node.iter_layer(visible) {
+                    let canvas = match &mut children_canvas {
+                        Some(children_canvas) if n.id != node.id => children_canvas,
+                        _ => &mut layer_canvas,
+                    };
+                    match item {
+                        RenderItem::Renderable(renderable) => {
+                            let transform = n
+                                .transform_within(node)
+                                .map_or(Transform::identity(), Into::into);
+                            self.draw(renderable, transform, canvas, images);
+                        }
+                        RenderItem::Layer(inner) => {
+                            self.draw_layer(inner, Some(node), visible, canvas, images, drawn)
+                        }
+                    }
+                }
+                if let (Some(mut children_canvas), Some(clip)) = (
+                    children_canvas,
+                    node.clip_path.as_deref().and_then(path_from_commands),
+                ) {
+                    let offset = Transform::from_translate(-bounds.pos.x, -bounds.pos.y);
+                    clip_to_path(children_canvas.pixmap_mut(), &clip, offset);
+                    layer_canvas.draw_pixmap(
+                        bounds.pos.x as i32,
+                        bounds.pos.y as i32,
+                        children_canvas.pixmap().as_ref(),
+                    );
+                }
+                SoftwareLayer {
+                    hash,
+                    bounds,
+                    canvas: layer_canvas,
+                }
+            }
+        };
+
+        let transform = match outer {
+            Some(outer) => node.transform_within(outer),
+            None => node.render_transform,
+        };
+        canvas.set_transform(transform.map_or(Transform::identity(), Into::into));
+        canvas.set_global_alpha(node.opacity);
+        canvas.draw_pixmap(
+            bounds.pos.x as i32,
+            bounds.pos.y as i32,
+            layer.canvas.pixmap().as_ref(),
+        );
+        canvas.set_global_alpha(1.0);
+        canvas.set_transform(Transform::identity());
+        self.layers.insert(node.id, layer);
+    }
+}
+
+impl super::Renderer for SoftwareRenderer {
+    fn new<W: crate::window::Window>(w: Arc<RwLock<W>>) -> Self {
+        let window = w.read().unwrap();
+        let fonts = window.fonts();
+        let text_renderer = TextRenderer::new(fonts.clone());
+        let svgs = load_svg_trees(window.svgs(), &fonts);
+
+        Self {
+            fonts,
+            text_renderer,
+            svgs,
+            layers: HashMap::new(),
+            textures: HashMap::new(),
+            runtime_images: RuntimeImages::new(),
+        }
+    }
+
+    fn render(
+        &mut self,
+        node: &Node,
+        _physical_size: PixelSize,
+        damage: &Damage,
+        ctx: &mut (dyn Any + 'static),
+    ) {
+        let context = ctx.downcast_mut::<SoftwareCanvasContext>().unwrap();
+        let canvas = &mut context.canvas;
+
+        // The canvas keeps the previous frame, so only the damaged regions are repainted
+        let frame = AABB::new(
+            Default::default(),
+            [canvas.width() as f32, canvas.height() as f32].into(),
+        );
+        let damage = damage.clip(frame);
+        if damage.is_empty() {
+            return;
+        }
+        if damage.is_full() {
+            canvas.clear();
+        } else {
+            canvas.clip(damage.rects());
+        }
+
+        self.runtime_images.next_frame();
+        let mut drawn = vec![];
+        for (item, n, _) in node.iter_layered() {
+            match item {
+                RenderItem::Renderable(renderable) => {
+                    let transform = n.render_transform.map_or(Transform::identity(), Into::into);
+                    self.draw(renderable, transform, canvas, &mut context.images)
+                }
+                RenderItem::Layer(node) => {
+                    self.draw_layer(node, None, frame, canvas, &mut context.images, &mut drawn)
+                }
+            }
+        }
+        // Drop the layers of Nodes that are gone, hidden, or inside of a layer that was reused
+        self.layers.retain(|id, _| drawn.contains(id));
+        self.runtime_images.take_evicted();
+        texture::drop_undrawn_software(&mut self.textures);
+
+        canvas.reset_clip();
+    }
+
+    fn caches(&self) -> Caches {
+        Caches {
+            font: Arc::new(RwLock::new(FontCache::new(self.fonts.clone()))),
+        }
+    }
+}
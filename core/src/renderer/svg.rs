@@ -1,57 +1,153 @@
 use crate::Scale;
-use femtovg::{Color, Paint, Path};
-use std::{borrow::Borrow, collections::HashMap};
-use usvg::{fontdb::Database, tiny_skia_path::PathSegment, Transform};
+use femtovg::{Color, FillRule, ImageId, Paint, Path};
+use image::DynamicImage;
+use std::{borrow::Borrow, collections::HashMap, sync::Arc};
+use usvg::{
+    fontdb::Database,
+    tiny_skia_path::{self, PathSegment, StrokeDash},
+    Transform,
+};
+
+/// How a fill or stroke of an SVG path is painted.
+#[derive(Debug)]
+pub struct SvgPaint {
+    pub paint: Paint,
+    /// The opacity of the paint, multiplied by that of the groups it is in. Kept when the SVG is recolored.
+    pub opacity: f32,
+}
+
+/// A part of an SVG, in drawing order.
+#[derive(Debug)]
+pub enum SvgNode {
+    Path {
+        path: Path,
+        fill: Option<SvgPaint>,
+        stroke: Option<SvgPaint>,
+        /// Whether the stroke is drawn below the fill, i.e. `paint-order: stroke`.
+        stroke_first: bool,
+        transform: Transform,
+    },
+    /// An embedded raster image. It is uploaded the first time it is drawn.
+    Image {
+        image: Arc<DynamicImage>,
+        id: Option<ImageId>,
+        view_box: usvg::ViewBox,
+        opacity: f32,
+        transform: Transform,
+    },
+}
 
 #[derive(Debug)]
 pub struct SvgData {
-    pub paths: Vec<(Path, Option<Paint>, Option<Paint>, Transform)>,
+    pub nodes: Vec<SvgNode>,
     pub scale: Scale,
 }
 
-fn render_nodes_to_paths(
-    nodes: &[usvg::Node],
-) -> Vec<(Path, Option<Paint>, Option<Paint>, Transform)> {
-    let mut paths = Vec::new();
+fn femtovg_path(data: &tiny_skia_path::Path) -> Path {
+    let mut path = Path::new();
+    for command in data.segments() {
+        match command {
+            PathSegment::MoveTo(p) => path.move_to(p.x, p.y),
+            PathSegment::LineTo(p) => path.line_to(p.x, p.y),
+            PathSegment::QuadTo(p1, p2) => path.quad_to(p1.x, p1.y, p2.x, p2.y),
+            PathSegment::CubicTo(p1, p2, p3) => path.bezier_to(p1.x, p1.y, p2.x, p2.y, p3.x, p3.y),
+            PathSegment::Close => path.close(),
+        }
+    }
+    path
+}
 
-    for node in nodes {
-        let mut path = Path::new();
+fn to_femto_color(color: usvg::Color, opacity: f32) -> Color {
+    Color::rgba(
+        color.red,
+        color.green,
+        color.blue,
+        (opacity * 255.).round() as u8,
+    )
+}
+
+/// femtovg gradients have no transform of their own, so their points are transformed instead.
+/// Radial gradients that are skewed or scaled unevenly are approximated by circles.
+fn to_femto_paint(paint: &usvg::Paint, opacity: f32) -> Option<Paint> {
+    let stops = |stops: &[usvg::Stop]| -> Vec<(f32, Color)> {
+        stops
+            .iter()
+            .map(|stop| {
+                (
+                    stop.offset().get(),
+                    to_femto_color(stop.color(), stop.opacity().get() * opacity),
+                )
+            })
+            .collect()
+    };
+
+    match paint {
+        usvg::Paint::Color(color) => Some(Paint::color(to_femto_color(*color, opacity))),
+        usvg::Paint::LinearGradient(gradient) => {
+            let mut start = tiny_skia_path::Point::from_xy(gradient.x1(), gradient.y1());
+            let mut end = tiny_skia_path::Point::from_xy(gradient.x2(), gradient.y2());
+            gradient.transform().map_point(&mut start);
+            gradient.transform().map_point(&mut end);
+            Some(Paint::linear_gradient_stops(
+                start.x,
+                start.y,
+                end.x,
+                end.y,
+                stops(gradient.stops()),
+            ))
+        }
+        usvg::Paint::RadialGradient(gradient) => {
+            let transform = gradient.transform();
+            let mut center = tiny_skia_path::Point::from_xy(gradient.cx(), gradient.cy());
+            transform.map_point(&mut center);
+            let scale = (transform.sx * transform.sy - transform.kx * transform.ky)
+                .abs()
+                .sqrt();
+            Some(Paint::radial_gradient_stops(
+                center.x,
+                center.y,
+                0.,
+                gradient.r().get() * scale,
+                stops(gradient.stops()),
+            ))
+        }
+        // Patterns are only drawn by the software renderer
+        usvg::Paint::Pattern(_) => None,
+    }
+}
 
+fn render_nodes_to_paths(nodes: &[usvg::Node], opacity: f32, svg_nodes: &mut Vec<SvgNode>) {
+    for node in nodes {
         match &*node.borrow() {
             usvg::Node::Group(child_group) => {
-                let mut child_paths = render_nodes_to_paths(child_group.children());
-                paths.append(&mut child_paths);
+                render_nodes_to_paths(
+                    child_group.children(),
+                    opacity * child_group.opacity().get(),
+                    svg_nodes,
+                );
             }
             usvg::Node::Path(svg_path) => {
-                for command in svg_path.data().segments() {
-                    match command {
-                        PathSegment::MoveTo(p) => path.move_to(p.x as f32, p.y as f32),
-                        PathSegment::LineTo(p) => path.line_to(p.x as f32, p.y as f32),
-                        PathSegment::QuadTo(p1, p2) => path.quad_to(p1.x, p1.y, p2.x, p2.y),
-                        PathSegment::CubicTo(p1, p2, p3) => {
-                            path.bezier_to(p1.x, p1.y, p2.x, p2.y, p3.x, p3.y)
-                        }
-                        PathSegment::Close => path.close(),
-                    }
+                if svg_path.visibility() != usvg::Visibility::Visible {
+                    continue;
                 }
+                let path = femtovg_path(svg_path.data());
 
-                let to_femto_color = |usvg_paint: &usvg::Paint| match usvg_paint {
-                    usvg::Paint::Color(usvg::Color { red, green, blue }) => {
-                        Some(Color::rgb(*red, *green, *blue))
-                    }
-                    _ => None,
-                };
-
-                let fill = svg_path
-                    .fill()
-                    .as_ref()
-                    .and_then(|fill| to_femto_color(&fill.paint()))
-                    .map(|col| Paint::color(col).with_anti_alias(true));
+                let fill = svg_path.fill().and_then(|fill| {
+                    let opacity = opacity * fill.opacity().get();
+                    to_femto_paint(fill.paint(), opacity).map(|mut paint| {
+                        paint.set_anti_alias(true);
+                        paint.set_fill_rule(match fill.rule() {
+                            usvg::FillRule::NonZero => FillRule::NonZero,
+                            usvg::FillRule::EvenOdd => FillRule::EvenOdd,
+                        });
+                        SvgPaint { paint, opacity }
+                    })
+                });
 
                 let stroke = svg_path.stroke().and_then(|stroke| {
-                    to_femto_color(&stroke.paint()).map(|paint| {
-                        let mut stroke_paint = Paint::color(paint);
-                        stroke_paint.set_line_width(stroke.width().get() as f32);
+                    let opacity = opacity * stroke.opacity().get();
+                    to_femto_paint(stroke.paint(), opacity).map(|mut stroke_paint| {
+                        stroke_paint.set_line_width(stroke.width().get());
                         stroke_paint.set_anti_alias(true);
                         stroke_paint.set_line_cap(match &stroke.linecap() {
                             usvg::LineCap::Butt => femtovg::LineCap::Butt,
@@ -64,21 +160,87 @@ fn render_nodes_to_paths(
                             usvg::LineJoin::Bevel => femtovg::LineJoin::Bevel,
                             usvg::LineJoin::MiterClip => femtovg::LineJoin::Miter,
                         });
-                        stroke_paint.set_miter_limit(stroke.miterlimit().get() as f32);
-                        stroke_paint
+                        stroke_paint.set_miter_limit(stroke.miterlimit().get());
+                        SvgPaint {
+                            paint: stroke_paint,
+                            opacity,
+                        }
                     })
                 });
 
+                // femtovg has no dashes of its own, so the dashed outline is built by tiny-skia
+                let stroke_path = svg_path
+                    .stroke()
+                    .and_then(|stroke| {
+                        let dash =
+                            StrokeDash::new(stroke.dasharray()?.to_vec(), stroke.dashoffset())?;
+                        svg_path.data().dash(&dash, 1.)
+                    })
+                    .map(|dashed| femtovg_path(&dashed));
+
                 let transform = svg_path.abs_transform();
+                let stroke_first = svg_path.paint_order() == usvg::PaintOrder::StrokeAndFill;
 
-                paths.push((path, fill, stroke, transform))
+                match stroke_path {
+                    // Dashed strokes need a path of their own
+                    Some(stroke_path) => {
+                        let fill = SvgNode::Path {
+                            path,
+                            fill,
+                            stroke: None,
+                            stroke_first: false,
+                            transform,
+                        };
+                        let stroke = SvgNode::Path {
+                            path: stroke_path,
+                            fill: None,
+                            stroke,
+                            stroke_first: false,
+                            transform,
+                        };
+                        if stroke_first {
+                            svg_nodes.extend([stroke, fill]);
+                        } else {
+                            svg_nodes.extend([fill, stroke]);
+                        }
+                    }
+                    None => svg_nodes.push(SvgNode::Path {
+                        path,
+                        fill,
+                        stroke,
+                        stroke_first,
+                        transform,
+                    }),
+                }
+            }
+            usvg::Node::Image(svg_image) => {
+                if svg_image.visibility() != usvg::Visibility::Visible {
+                    continue;
+                }
+                let data = match svg_image.kind() {
+                    usvg::ImageKind::JPEG(data)
+                    | usvg::ImageKind::PNG(data)
+                    | usvg::ImageKind::GIF(data) => data,
+                    // Nested SVGs are only drawn by the software renderer
+                    usvg::ImageKind::SVG(_) => continue,
+                };
+                match image::load_from_memory(data) {
+                    Ok(image) => svg_nodes.push(SvgNode::Image {
+                        image: Arc::new(image),
+                        id: None,
+                        view_box: svg_image.view_box(),
+                        opacity,
+                        transform: svg_image.abs_transform(),
+                    }),
+                    Err(e) => println!("Error while decoding svg image {:?}", e),
+                }
+            }
+            // Text is converted into paths when the SVG is parsed
+            usvg::Node::Text(text) => {
+                render_nodes_to_paths(text.flattened().children(), opacity, svg_nodes);
             }
-            usvg::Node::Image(_) => {}
-            usvg::Node::Text(_) => {}
         }
     }
-
-    paths
 }
 
 pub fn load_svg_paths(svgs: HashMap<String, String>, fonts: Database) -> HashMap<String, SvgData> {
@@ -105,10 +267,10 @@ pub fn load_svg_path(file_path: String, fonts: &Database) -> SvgData {
     let width = tree.size().width() as f32;
     let height = tree.size().height() as f32;
 
-    let paths: Vec<(Path, Option<Paint>, Option<Paint>, Transform)> =
-        render_nodes_to_paths(tree.root().children());
+    let mut nodes = Vec::new();
+    render_nodes_to_paths(tree.root().children(), 1., &mut nodes);
     SvgData {
-        paths,
+        nodes,
         scale: Scale { width, height },
     }
 }
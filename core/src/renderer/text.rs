@@ -1,9 +1,11 @@
 use std::collections::HashMap;
+use std::ops::Range;
 
 use cosmic_text::fontdb::Database;
 use cosmic_text::{
     Align as CosmicAlign, Attrs, AttrsList, Buffer, CacheKey, Color as FontColor, Family,
-    FontSystem, LayoutGlyph, Metrics, Shaping, Stretch, Style, SubpixelBin, Weight, Wrap,
+    FontSystem, LayoutGlyph, Metrics, Shaping, Stretch, Style, SubpixelBin, SwashCache, Weight,
+    Wrap,
 };
 use femtovg::renderer::OpenGl;
 use femtovg::{
@@ -19,8 +21,11 @@ use swash::zeno::{Format, Vector};
 use crate::font_cache::{
     DEFAULT_FONT_SIZE, DEFAULT_LINE_HEIGHT, GLYPH_MARGIN, GLYPH_PADDING, TEXTURE_SIZE,
 };
-use crate::renderables::text::Instance;
-use crate::{Pos, Scale};
+use crate::renderables::text::{
+    FontFeature, Instance, TextDecorationLine, TextDecorationStyle, TextSpan,
+};
+use crate::style::{FontStretch, FontStyle};
+use crate::{Color, Pos, Scale, AABB};
 
 // const DEFAULT_FONT_SIZE: f32= 12.;
 // const DEFAULT_LINE_HEIGHT: f32 = 16.;
@@ -28,6 +33,8 @@ use crate::{Pos, Scale};
 // const GLYPH_MARGIN: u32 = 0;
 // const TEXTURE_SIZE: usize = 512;
 
+const ELLIPSIS: &str = "\u{2026}";
+
 #[derive(Default, Debug, Clone, Copy)]
 pub struct TextConfig {
     pub hint: bool,
@@ -51,12 +58,35 @@ pub struct RenderedGlyph {
     color_glyph: bool,
 }
 
+/// What it takes to draw a text with femtovg.
+pub struct TextDrawCommands {
+    /// The highlighted backgrounds of spans, which are drawn beneath the glyphs.
+    pub backgrounds: Vec<(AABB, Color)>,
+    pub glyphs: Vec<(FontColor, GlyphDrawCommands)>,
+    /// The decoration lines, which are drawn above the glyphs.
+    pub decorations: Vec<(AABB, Color)>,
+}
+
+/// A line of shaped text, with letter spacing and tabular numbers applied to its glyphs.
+struct Run {
+    line_i: usize,
+    line_y: f32,
+    line_w: f32,
+    glyphs: Vec<LayoutGlyph>,
+}
+
 pub struct TextRenderer {
     pub font_system: FontSystem,
     pub buffer: Buffer,
     scale_context: ScaleContext,
+    swash_cache: SwashCache,
     rendered_glyphs: HashMap<CacheKey, Option<RenderedGlyph>>,
     glyph_textures: Vec<FontTexture>,
+    /// Where each line of the buffer starts, in the text that was last shaped.
+    line_starts: Vec<usize>,
+    letter_spacing: f32,
+    /// The advance that every digit is given, when they are tabular.
+    digit_width: Option<f32>,
 }
 
 impl TextRenderer {
@@ -70,8 +100,12 @@ impl TextRenderer {
             font_system,
             buffer,
             scale_context: ScaleContext::default(),
+            swash_cache: SwashCache::new(),
             rendered_glyphs: HashMap::new(),
             glyph_textures: vec![],
+            line_starts: vec![],
+            letter_spacing: 0.,
+            digit_width: None,
         }
     }
 
@@ -80,50 +114,82 @@ impl TextRenderer {
         self.glyph_textures.clear();
     }
 
-    pub fn draw_text(
-        &mut self,
-        canvas: &mut Canvas<OpenGl>,
-        instance: Instance,
-    ) -> Result<Vec<(FontColor, GlyphDrawCommands)>, ErrorKind> {
+    /// Set up the text buffer with the text and attributes of the given `Instance`, and shape it.
+    fn prepare_buffer(&mut self, instance: &Instance) {
         let Instance {
-            pos,
             scale,
             color,
             align,
             font,
             weight,
+            font_style,
+            stretch,
+            letter_spacing,
+            features,
             font_size,
             line_height,
             text,
+            wrap,
+            ..
         } = instance;
 
         let fs = &mut self.font_system;
         let buffer = &mut self.buffer;
 
-        buffer.set_metrics(fs, Metrics::new(font_size, line_height));
+        buffer.set_metrics(fs, Metrics::new(*font_size, *line_height));
 
         let mut attrs = Attrs::new()
-            .weight(Weight(weight as u16))
-            .stretch(Stretch::Normal)
-            .style(Style::Normal)
-            .color(FontColor::rgba(
-                color.r as u8,
-                color.g as u8,
-                color.b as u8,
-                (color.a * 255.) as u8,
-            ));
+            .weight(Weight(*weight as u16))
+            .stretch(to_stretch(*stretch))
+            .style(to_style(*font_style))
+            .color(to_font_color(*color));
 
         if font.is_some() {
             attrs = attrs.family(Family::Name(font.as_ref().unwrap()));
         }
 
-        buffer.set_wrap(fs, Wrap::None);
-        buffer.set_text(fs, &text, attrs, Shaping::Advanced);
+        self.letter_spacing = *letter_spacing;
+        let tabular = features
+            .iter()
+            .any(|f| f.tag == FontFeature::TABULAR_NUMBERS.tag && f.value != 0);
+        self.digit_width = tabular.then(|| {
+            let mut digits = Buffer::new(fs, Metrics::new(*font_size, *line_height));
+            digits.set_size(fs, f32::MAX, f32::MAX);
+            digits.set_text(fs, "0123456789", attrs, Shaping::Advanced);
+            digits
+                .layout_runs()
+                .flat_map(|run| run.glyphs.iter().map(|g| g.w))
+                .fold(0., f32::max)
+        });
+
+        buffer.set_wrap(fs, Wrap::from(*wrap));
         buffer.set_size(fs, scale.width, scale.height);
+        self.shape(text, instance, attrs);
+        self.truncate(instance, attrs);
+    }
 
-        for line in buffer.lines.iter_mut() {
-            // TODO spans
-            line.set_attrs_list(AttrsList::new(attrs));
+    /// Set the text of the buffer, with the attributes of the spans of `instance`, and shape it.
+    fn shape(&mut self, text: &str, instance: &Instance, attrs: Attrs) {
+        let Instance { align, spans, .. } = instance;
+        let fs = &mut self.font_system;
+        let buffer = &mut self.buffer;
+
+        buffer.set_text(fs, text, attrs, Shaping::Advanced);
+        self.line_starts = line_starts(text, buffer.lines.iter().map(|line| line.text()));
+        for (line, start) in buffer.lines.iter_mut().zip(&self.line_starts) {
+            let end = start + line.text().len();
+            let mut attrs_list = AttrsList::new(attrs);
+            // Spans that only have a background, like selections, leave the shaping alone
+            for span in spans.iter().filter(|s| s.changes_glyphs()) {
+                let range = span.range.start.max(*start)..span.range.end.min(end);
+                if !range.is_empty() {
+                    attrs_list.add_span(
+                        range.start - start..range.end - start,
+                        span_attrs(span, attrs),
+                    );
+                }
+            }
+            line.set_attrs_list(attrs_list);
             line.set_align(match align {
                 Align::Left => Some(CosmicAlign::Left),
                 Align::Center => Some(CosmicAlign::Center),
@@ -132,67 +198,368 @@ impl TextRenderer {
         }
 
         buffer.shape_until(fs, i32::MAX);
+    }
 
-        let config = TextConfig {
-            hint: true,
-            subpixel: true,
+    /// Cut the shaped text off after `max_lines`, or -- with an ellipsis -- where a line overflows the width of the buffer.
+    fn truncate(&mut self, instance: &Instance, attrs: Attrs) {
+        let Instance {
+            scale,
+            max_lines,
+            ellipsis,
+            ..
+        } = instance;
+        let max_lines = max_lines.unwrap_or(usize::MAX).max(1);
+
+        // The ellipsis takes up room as well, so the text is cut where it still fits next to it
+        let ellipsis_width = ellipsis.then(|| self.ellipsis_width(instance, attrs));
+        let Some(shown) = self.shown_text(scale.width, max_lines, ellipsis_width) else {
+            return;
         };
+        if ellipsis_width.is_some() {
+            self.shape(
+                &format!("{}{}", shown.trim_end(), ELLIPSIS),
+                instance,
+                attrs,
+            );
+        } else {
+            self.shape(&shown, instance, attrs);
+        }
+    }
 
-        self.fill_to_cmds(canvas, scale, pos, (0., 0.), config)
+    /// The width of the ellipsis, including the letter spacing in front of it.
+    fn ellipsis_width(&mut self, instance: &Instance, attrs: Attrs) -> f32 {
+        let fs = &mut self.font_system;
+        let mut buffer = Buffer::new(fs, Metrics::new(instance.font_size, instance.line_height));
+        buffer.set_size(fs, f32::MAX, f32::MAX);
+        buffer.set_text(fs, ELLIPSIS, attrs, Shaping::Advanced);
+        let width = buffer
+            .layout_runs()
+            .map(|run| run.line_w)
+            .fold(0., f32::max);
+        width + self.letter_spacing
     }
 
-    pub fn measure_text(
-        &mut self,
-        instance: Instance,
-    ) -> (Option<f32>, Option<f32>, Vec<LayoutGlyph>) {
+    /// The lines of the shaped text, with letter spacing and tabular numbers applied.
+    fn runs(&self) -> Vec<Run> {
+        let is_digit = |text: &str| !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit());
+        self.buffer
+            .layout_runs()
+            .map(|run| {
+                let mut glyphs = run.glyphs.to_vec();
+                let mut offset = 0.;
+                for glyph in glyphs.iter_mut() {
+                    glyph.x += offset;
+                    if let Some(width) = self
+                        .digit_width
+                        .filter(|_| is_digit(&run.text[glyph.start..glyph.end]))
+                    {
+                        // Centered in the advance of the widest digit
+                        glyph.x += (width - glyph.w) / 2.;
+                        offset += width - glyph.w;
+                    }
+                    offset += self.letter_spacing;
+                }
+                // No spacing after the last glyph
+                let extra = if glyphs.is_empty() {
+                    0.
+                } else {
+                    offset - self.letter_spacing
+                };
+                // Alignment was done without the extra width
+                let shift = match self.buffer.lines[run.line_i].align() {
+                    Some(CosmicAlign::Center) => extra / 2.,
+                    Some(CosmicAlign::Right) => extra,
+                    _ => 0.,
+                };
+                for glyph in glyphs.iter_mut() {
+                    glyph.x -= shift;
+                }
+                Run {
+                    line_i: run.line_i,
+                    line_y: run.line_y,
+                    line_w: run.line_w + extra,
+                    glyphs,
+                }
+            })
+            .collect()
+    }
+
+    /// The part of the shaped text that is shown, if some of it has to be cut off. Given an `ellipsis_width`, lines that
+    /// overflow `width` are cut off as well, and the last line that is shown leaves room for the ellipsis.
+    fn shown_text(
+        &self,
+        width: f32,
+        max_lines: usize,
+        ellipsis_width: Option<f32>,
+    ) -> Option<String> {
+        let runs = self.runs();
+        // Where the glyphs of `run` that fit next to the ellipsis end
+        let cut = |run: &Run, ellipsis_width: f32| {
+            run.glyphs.first().map_or(0, |start| {
+                run.glyphs
+                    .iter()
+                    .take_while(|g| g.x + g.w - start.x + ellipsis_width <= width)
+                    .last()
+                    .map_or(start.start, |g| g.end)
+            })
+        };
+        let overflowing = ellipsis_width.and_then(|ellipsis_width| {
+            let run = runs.iter().take(max_lines).find(|run| run.line_w > width)?;
+            Some((run.line_i, cut(run, ellipsis_width)))
+        });
+        let (line_i, end) = overflowing.or_else(|| {
+            let run = runs.get(max_lines - 1).filter(|_| runs.len() > max_lines)?;
+            let end = match ellipsis_width {
+                Some(ellipsis_width) => cut(run, ellipsis_width),
+                None => run.glyphs.last().map_or(0, |g| g.end),
+            };
+            Some((run.line_i, end))
+        })?;
+
+        let lines = &self.buffer.lines;
+        let mut text: String = lines[..line_i]
+            .iter()
+            .map(|line| format!("{}\n", line.text()))
+            .collect();
+        text.push_str(&lines[line_i].text()[..end]);
+        Some(text)
+    }
+
+    /// The boxes that the glyphs of the given byte ranges of the shaped text take up, relative to its top left corner.
+    /// Neighbouring glyphs on the same line share a box.
+    fn range_rects(&self, range: &Range<usize>) -> Vec<AABB> {
+        let line_height = self.buffer.metrics().line_height;
+        let mut rects: Vec<AABB> = vec![];
+        for (i, run) in self.runs().into_iter().enumerate() {
+            let start = self.line_starts.get(run.line_i).copied().unwrap_or(0);
+            let top = i as f32 * line_height;
+            let mut last: Option<AABB> = None;
+            for glyph in run.glyphs {
+                if !range.contains(&(start + glyph.start)) {
+                    rects.extend(last.take());
+                    continue;
+                }
+                last = Some(match last {
+                    Some(mut rect) => {
+                        rect.pos.x = rect.pos.x.min(glyph.x);
+                        rect.bottom_right.x = rect.bottom_right.x.max(glyph.x + glyph.w);
+                        rect
+                    }
+                    None => AABB::new(Pos::new(glyph.x, top, 0.), Scale::new(glyph.w, line_height)),
+                });
+            }
+            rects.extend(last);
+        }
+        rects
+    }
+
+    /// The highlighted backgrounds of the spans of the shaped `instance`.
+    fn backgrounds(&self, instance: &Instance) -> Vec<(AABB, Color)> {
+        let Instance { pos, spans, .. } = instance;
+        spans
+            .iter()
+            .filter_map(|span| Some((span, span.background?)))
+            .flat_map(|(span, color)| {
+                self.range_rects(&span.range).into_iter().map(move |rect| {
+                    (
+                        AABB::new(
+                            Pos::new(rect.pos.x + pos.x, rect.pos.y + pos.y, pos.z),
+                            rect.size(),
+                        ),
+                        color,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// The rects of the decoration lines of the shaped `instance`, which follow each line of text.
+    fn decorations(&mut self, instance: &Instance) -> Vec<(AABB, Color)> {
         let Instance {
             pos,
-            scale,
-            align,
-            font,
-            weight,
-            font_size,
-            line_height,
-            text,
+            color,
+            decoration,
             ..
         } = instance;
+        let Some(decoration) = decoration else {
+            return vec![];
+        };
+        let color = decoration.color.unwrap_or(*color);
+
+        let mut rects = vec![];
+        for run in self.runs() {
+            let Some(glyph) = run.glyphs.first() else {
+                continue;
+            };
+            let Some(font) = self.font_system.get_font(glyph.font_id) else {
+                continue;
+            };
+            let metrics = font.as_swash().metrics(&[]).scale(glyph.font_size);
+            let thickness = decoration.thickness.unwrap_or(metrics.stroke_size).max(1.);
+            // Font metrics point up from the baseline
+            let top = run.line_y
+                - match decoration.line {
+                    TextDecorationLine::Underline => metrics.underline_offset,
+                    TextDecorationLine::Overline => metrics.ascent,
+                    TextDecorationLine::LineThrough => metrics.strikeout_offset,
+                };
+            let left = run.glyphs.iter().map(|g| g.x).fold(f32::MAX, f32::min);
+            let right = run
+                .glyphs
+                .iter()
+                .map(|g| g.x + g.w)
+                .fold(f32::MIN, f32::max);
+
+            let (dash, gap) = match decoration.style {
+                TextDecorationStyle::Dotted => (thickness, thickness),
+                TextDecorationStyle::Dashed => (thickness * 3., thickness * 2.),
+                _ => (right - left, 0.),
+            };
+            let lines = if decoration.style == TextDecorationStyle::Double {
+                vec![top, top + thickness * 2.]
+            } else {
+                vec![top]
+            };
+            for y in lines {
+                let mut x = left;
+                while x < right && dash > 0. {
+                    rects.push((
+                        AABB::new(
+                            Pos::new(pos.x + x, pos.y + y, pos.z),
+                            Scale::new(dash.min(right - x), thickness),
+                        ),
+                        color,
+                    ));
+                    x += dash + gap;
+                }
+            }
+        }
+        rects
+    }
 
-        let fs = &mut self.font_system;
-        let buffer = &mut self.buffer;
+    /// The box of every glyph of the text of `instance`, as high as its line and relative to its `pos`,
+    /// with the byte range of the text that it shows.
+    pub fn glyph_boxes(&mut self, instance: Instance) -> Vec<(AABB, Range<usize>)> {
+        self.prepare_buffer(&instance);
+        let line_height = self.buffer.metrics().line_height;
+        self.runs()
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, run)| {
+                let start = self.line_starts.get(run.line_i).copied().unwrap_or(0);
+                let top = i as f32 * line_height;
+                run.glyphs.into_iter().map(move |glyph| {
+                    (
+                        AABB::new(Pos::new(glyph.x, top, 0.), Scale::new(glyph.w, line_height)),
+                        start + glyph.start..start + glyph.end,
+                    )
+                })
+            })
+            .collect()
+    }
 
-        buffer.set_metrics(fs, Metrics::new(font_size, line_height));
+    /// The boxes that the glyphs in each of the given byte ranges of the text of `instance` take up, relative to its `pos`.
+    pub fn measure_ranges(
+        &mut self,
+        instance: Instance,
+        ranges: &[Range<usize>],
+    ) -> Vec<Vec<AABB>> {
+        self.prepare_buffer(&instance);
+        ranges.iter().map(|range| self.range_rects(range)).collect()
+    }
 
-        let mut attrs = Attrs::new()
-            .weight(Weight(weight as u16))
-            .stretch(Stretch::Normal)
-            .style(Style::Normal);
+    /// How many lines the shaped text takes up. None, when it is empty.
+    fn line_count(&self) -> usize {
+        if self.buffer.layout_runs().all(|run| run.line_w == 0.0) {
+            0
+        } else {
+            self.buffer.layout_runs().count()
+        }
+    }
 
-        if font.is_some() {
-            attrs = attrs.family(Family::Name(font.as_ref().unwrap()));
+    pub fn draw_text(
+        &mut self,
+        canvas: &mut Canvas<OpenGl>,
+        instance: Instance,
+    ) -> Result<TextDrawCommands, ErrorKind> {
+        self.prepare_buffer(&instance);
+
+        let config = TextConfig {
+            hint: true,
+            subpixel: true,
+        };
+
+        Ok(TextDrawCommands {
+            backgrounds: self.backgrounds(&instance),
+            glyphs: self.fill_to_cmds(canvas, instance.scale, instance.pos, (0., 0.), config)?,
+            decorations: self.decorations(&instance),
+        })
+    }
+
+    /// Rasterize the text on the CPU. `f` is called with the position, size and color
+    /// of every block of pixels covered by a highlighted background, then by a glyph, and then by a decoration line.
+    pub fn draw_text_to_pixels<F>(&mut self, instance: Instance, mut f: F)
+    where
+        F: FnMut(i32, i32, u32, u32, FontColor),
+    {
+        self.prepare_buffer(&instance);
+
+        let fill = |f: &mut F, rect: AABB, color: Color| {
+            let (x0, y0) = (rect.pos.x.round() as i32, rect.pos.y.round() as i32);
+            let (x1, y1) = (
+                rect.bottom_right.x.round() as i32,
+                rect.bottom_right.y.round() as i32,
+            );
+            f(
+                x0,
+                y0,
+                (x1 - x0).max(0) as u32,
+                (y1 - y0).max(0) as u32,
+                to_font_color(color),
+            );
+        };
+        for (rect, color) in self.backgrounds(&instance) {
+            fill(&mut f, rect, color);
         }
 
-        buffer.set_wrap(fs, Wrap::None);
-        buffer.set_text(fs, &text, attrs, Shaping::Advanced);
-        buffer.set_size(fs, scale.width, scale.height);
+        let Instance { pos, color, .. } = instance;
+        let (x, y) = (pos.x.round() as i32, pos.y.round() as i32);
+
+        // Like `Buffer::draw`, with the glyphs moved by letter spacing and tabular numbers
+        for run in self.runs() {
+            for glyph in run.glyphs.iter() {
+                let physical_glyph = glyph.physical((0., 0.), 1.0);
+                let glyph_color = glyph.color_opt.unwrap_or(to_font_color(color));
+                let (gx, gy) = (
+                    x + physical_glyph.x,
+                    y + run.line_y as i32 + physical_glyph.y,
+                );
+                self.swash_cache.with_pixels(
+                    &mut self.font_system,
+                    physical_glyph.cache_key,
+                    glyph_color,
+                    |px, py, color| f(gx + px, gy + py, 1, 1, color),
+                );
+            }
+        }
 
-        for line in buffer.lines.iter_mut() {
-            // TODO spans
-            line.set_attrs_list(AttrsList::new(attrs));
-            line.set_align(match align {
-                Align::Left => Some(CosmicAlign::Left),
-                Align::Center => Some(CosmicAlign::Center),
-                Align::Right => Some(CosmicAlign::Right),
-            });
+        for (rect, color) in self.decorations(&instance) {
+            fill(&mut f, rect, color);
         }
+    }
 
-        buffer.shape_until(fs, i32::MAX);
+    pub fn measure_text(
+        &mut self,
+        instance: Instance,
+    ) -> (Option<f32>, Option<f32>, Vec<LayoutGlyph>) {
+        self.prepare_buffer(&instance);
 
         let config = TextConfig {
             hint: true,
             subpixel: true,
         };
 
-        let (w, h, glyphs) = self.measure_glyphs(scale, pos, (0., 0.), config);
+        let (w, h, glyphs) = self.measure_glyphs(instance.scale, instance.pos, (0., 0.), config);
         (Some(w), Some(h), glyphs)
     }
 
@@ -203,21 +570,14 @@ impl TextRenderer {
         justify: (f32, f32),
         config: TextConfig,
     ) -> (f32, f32, Vec<LayoutGlyph>) {
-        let fs = &mut self.font_system;
-        let buffer = &mut self.buffer;
-        let rendered_glyphs = &mut self.rendered_glyphs;
-
-        let lines = buffer.layout_runs().filter(|run| run.line_w != 0.0).count();
-        let total_height = lines as f32 * buffer.metrics().line_height;
+        let total_height = self.line_count() as f32 * self.buffer.metrics().line_height;
         let mut total_width: f32 = 0.;
 
         let mut glyphs: Vec<LayoutGlyph> = vec![];
 
-        for run in buffer.layout_runs() {
-            for glyph in run.glyphs {
-                total_width += glyph.w;
-                glyphs.push(glyph.clone());
-            }
+        for run in self.runs() {
+            total_width = total_width.max(run.line_w);
+            glyphs.extend(run.glyphs);
         }
 
         (total_width, total_height, glyphs)
@@ -231,17 +591,16 @@ impl TextRenderer {
         justify: (f32, f32),
         config: TextConfig,
     ) -> Result<Vec<(FontColor, GlyphDrawCommands)>, ErrorKind> {
+        let total_height = self.line_count() as f32 * self.buffer.metrics().line_height;
+        let runs = self.runs();
         let fs = &mut self.font_system;
-        let buffer = &mut self.buffer;
         let rendered_glyphs = &mut self.rendered_glyphs;
 
         let mut alpha_cmd_map = HashMap::new();
         let mut color_cmd_map = HashMap::new();
 
-        let lines = buffer.layout_runs().filter(|run| run.line_w != 0.0).count();
-        let total_height = lines as f32 * buffer.metrics().line_height;
-        for run in buffer.layout_runs() {
-            for glyph in run.glyphs {
+        for run in runs.iter() {
+            for glyph in run.glyphs.iter() {
                 let physical_glyph = glyph.physical(
                     (
                         position.x,
@@ -425,3 +784,70 @@ impl TextRenderer {
         }
     }
 }
+
+fn to_style(style: FontStyle) -> Style {
+    match style {
+        FontStyle::Normal => Style::Normal,
+        FontStyle::Italic => Style::Italic,
+        FontStyle::Oblique => Style::Oblique,
+    }
+}
+
+fn to_stretch(stretch: FontStretch) -> Stretch {
+    match stretch {
+        FontStretch::UltraCondensed => Stretch::UltraCondensed,
+        FontStretch::ExtraCondensed => Stretch::ExtraCondensed,
+        FontStretch::Condensed => Stretch::Condensed,
+        FontStretch::SemiCondensed => Stretch::SemiCondensed,
+        FontStretch::Normal => Stretch::Normal,
+        FontStretch::SemiExpanded => Stretch::SemiExpanded,
+        FontStretch::Expanded => Stretch::Expanded,
+        FontStretch::ExtraExpanded => Stretch::ExtraExpanded,
+        FontStretch::UltraExpanded => Stretch::UltraExpanded,
+    }
+}
+
+fn to_font_color(color: Color) -> FontColor {
+    FontColor::rgba(
+        color.r as u8,
+        color.g as u8,
+        color.b as u8,
+        (color.a * 255.) as u8,
+    )
+}
+
+/// The attributes of the text of `span`, on top of `attrs`.
+fn span_attrs<'a>(span: &'a TextSpan, attrs: Attrs<'a>) -> Attrs<'a> {
+    let mut attrs = attrs;
+    if let Some(color) = span.color {
+        attrs = attrs.color(to_font_color(color));
+    }
+    if let Some(weight) = span.weight {
+        attrs = attrs.weight(Weight(weight as u16));
+    }
+    if span.italic {
+        attrs = attrs.style(Style::Italic);
+    }
+    if let Some(font) = &span.font {
+        attrs = attrs.family(Family::Name(font));
+    }
+    attrs
+}
+
+/// Where each of the `lines` that cosmic-text split `text` into starts in it.
+fn line_starts<'a>(text: &str, lines: impl Iterator<Item = &'a str>) -> Vec<usize> {
+    let mut starts = vec![];
+    let mut pos = 0;
+    for line in lines {
+        starts.push(pos);
+        pos = (pos + line.len()).min(text.len());
+        // Skip the line break
+        let rest = &text[pos..];
+        pos += if rest.starts_with("\r\n") {
+            2
+        } else {
+            rest.chars().next().map_or(0, char::len_utf8)
+        };
+    }
+    starts
+}
@@ -0,0 +1,239 @@
+//! Golden-image snapshot testing.
+//!
+//! A component is rendered with the software renderer of a [`TestHarness`], and the frame is compared against
+//! a checked-in PNG. Golden images are only (re-)recorded when the `MCTK_UPDATE_SNAPSHOTS` environment variable is set,
+//! so a missing golden image fails the test rather than silently passing it. On failure, `<name>.actual.png` and
+//! `<name>.diff.png` are written next to the golden image.
+//!
+//!```ignore
+//! let image = snapshot(100, 60, 2.0, || node!(Toggle::new(true)));
+//! assert_snapshot("snapshots/toggle_active.png", &image);
+//!```
+
+use crate::headless::{HeadlessWindow, TestHarness, TestRootParams};
+use crate::node::Node;
+use crate::renderer::software::SoftwareCanvas;
+use image::{Rgba, RgbaImage};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Set to record every golden image, including missing ones, instead of comparing against it.
+pub const UPDATE_SNAPSHOTS_ENV: &str = "MCTK_UPDATE_SNAPSHOTS";
+
+/// How different a frame may be from its golden image before a snapshot fails.
+#[derive(Debug, Clone, Copy)]
+pub struct SnapshotOptions {
+    /// Largest difference, per channel, for two pixels to still be considered equal.
+    pub tolerance: u8,
+    /// Fraction of the pixels that may differ by more than `tolerance`.
+    pub max_diff_ratio: f32,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 2,
+            max_diff_ratio: 0.0,
+        }
+    }
+}
+
+/// The outcome of comparing a frame against its golden image.
+#[derive(Debug)]
+pub struct SnapshotDiff {
+    /// Number of pixels that differ by more than the tolerance.
+    pub differing_pixels: usize,
+    /// Largest per-channel difference found.
+    pub max_difference: u8,
+    /// The actual frame, faded, with differing pixels in red.
+    pub image: RgbaImage,
+}
+
+/// Render the Node returned by `view` in a window of the given logical size and `scale_factor`.
+/// The returned image has the physical size of the window.
+///
+/// The window has no fonts, so no text is drawn. Use [`snapshot_with`] and [`HeadlessWindow::fonts`] to draw text.
+pub fn snapshot<F>(width: u32, height: u32, scale_factor: f32, view: F) -> RgbaImage
+where
+    F: 'static + Fn() -> Node + Send + Sync,
+{
+    snapshot_with(
+        HeadlessWindow::new(width, height).scale_factor(scale_factor),
+        view,
+    )
+}
+
+/// Render the Node returned by `view` in the given `window`, e.g. one with the fonts or assets that `view` needs.
+/// The returned image has the physical size of the window.
+pub fn snapshot_with<F>(window: HeadlessWindow, view: F) -> RgbaImage
+where
+    F: 'static + Fn() -> Node + Send + Sync,
+{
+    let mut harness = TestHarness::with_window(
+        window,
+        TestRootParams {
+            view: Arc::new(view),
+        },
+    );
+    canvas_to_image(harness.render())
+}
+
+/// Convert the premultiplied pixels of a [`SoftwareCanvas`] to a straight-alpha image.
+pub fn canvas_to_image(canvas: &SoftwareCanvas) -> RgbaImage {
+    let mut image = RgbaImage::new(canvas.width(), canvas.height());
+    for (dst, src) in image.pixels_mut().zip(canvas.pixmap().pixels()) {
+        let c = src.demultiply();
+        *dst = Rgba([c.red(), c.green(), c.blue(), c.alpha()]);
+    }
+    image
+}
+
+/// Compare `actual` against `golden`. Colour differences are weighted by alpha, so that the colour of
+/// (nearly) transparent pixels does not matter. Returns `None` if the images have different sizes.
+pub fn compare(actual: &RgbaImage, golden: &RgbaImage, tolerance: u8) -> Option<SnapshotDiff> {
+    if actual.dimensions() != golden.dimensions() {
+        return None;
+    }
+
+    let mut differing_pixels = 0;
+    let mut max_difference = 0;
+    let mut image = RgbaImage::new(actual.width(), actual.height());
+
+    for ((a, g), d) in actual.pixels().zip(golden.pixels()).zip(image.pixels_mut()) {
+        let difference = pixel_difference(a, g);
+        max_difference = max_difference.max(difference);
+
+        *d = if difference > tolerance {
+            differing_pixels += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            // Faded greyscale of the actual pixel, over white
+            let luma = (a[0] as u32 * 299 + a[1] as u32 * 587 + a[2] as u32 * 114) / 1000;
+            let luma = 255 - (255 - luma) * a[3] as u32 / 255;
+            let faded = (191 + luma / 4) as u8;
+            Rgba([faded, faded, faded, 255])
+        };
+    }
+
+    Some(SnapshotDiff {
+        differing_pixels,
+        max_difference,
+        image,
+    })
+}
+
+fn pixel_difference(a: &Rgba<u8>, b: &Rgba<u8>) -> u8 {
+    let premultiply = |p: &Rgba<u8>, i: usize| p[i] as u32 * p[3] as u32 / 255;
+    let mut difference = (a[3] as i32 - b[3] as i32).unsigned_abs();
+    for i in 0..3 {
+        difference =
+            difference.max((premultiply(a, i) as i32 - premultiply(b, i) as i32).unsigned_abs());
+    }
+    difference as u8
+}
+
+/// Compare `actual` against the golden image at `path`, with the default [`SnapshotOptions`].
+pub fn assert_snapshot<P: AsRef<Path>>(path: P, actual: &RgbaImage) {
+    assert_snapshot_with(path, actual, SnapshotOptions::default())
+}
+
+/// Compare `actual` against the golden image at `path`.
+///
+/// # Panics
+/// If the images differ by more than `options` allow, if they have different sizes, or if there is no golden image
+/// and [`UPDATE_SNAPSHOTS_ENV`] is not set.
+pub fn assert_snapshot_with<P: AsRef<Path>>(path: P, actual: &RgbaImage, options: SnapshotOptions) {
+    let path = path.as_ref();
+
+    if std::env::var_os(UPDATE_SNAPSHOTS_ENV).is_some() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        actual.save(path).unwrap();
+        println!("Recorded snapshot {:?}", path);
+        return;
+    }
+
+    let actual_path = sibling_path(path, "actual");
+    if !path.exists() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        actual.save(&actual_path).unwrap();
+        panic!(
+            "Snapshot {:?} does not exist. Wrote {:?}; set {} to record it",
+            path, actual_path, UPDATE_SNAPSHOTS_ENV
+        );
+    }
+
+    let golden = image::open(path)
+        .unwrap_or_else(|e| panic!("Failed to open snapshot {:?}: {:?}", path, e))
+        .to_rgba8();
+
+    let Some(diff) = compare(actual, &golden, options.tolerance) else {
+        actual.save(&actual_path).unwrap();
+        panic!(
+            "Snapshot {:?} is {:?}, but the frame is {:?}. Wrote {:?}",
+            path,
+            golden.dimensions(),
+            actual.dimensions(),
+            actual_path
+        );
+    };
+
+    let allowed = (options.max_diff_ratio * (actual.width() * actual.height()) as f32) as usize;
+    if diff.differing_pixels > allowed {
+        let diff_path = sibling_path(path, "diff");
+        actual.save(&actual_path).unwrap();
+        diff.image.save(&diff_path).unwrap();
+        panic!(
+            "Snapshot {:?} does not match: {} pixels differ (max difference {}). Wrote {:?} and {:?}",
+            path, diff.differing_pixels, diff.max_difference, actual_path, diff_path
+        );
+    }
+}
+
+/// `dir/name.png` -> `dir/name.<suffix>.png`
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}.{}.png", stem, suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::test_fonts;
+    use crate::style::Styled;
+    use crate::widgets::Text;
+    use crate::{node, txt};
+
+    #[test]
+    fn test_compare() {
+        let a = RgbaImage::from_pixel(4, 4, Rgba([10, 20, 30, 255]));
+        let mut b = a.clone();
+        b.put_pixel(1, 1, Rgba([13, 20, 30, 255]));
+        b.put_pixel(2, 2, Rgba([200, 20, 30, 255]));
+
+        let diff = compare(&a, &b, 3).unwrap();
+        assert_eq!(diff.differing_pixels, 1);
+        assert_eq!(diff.max_difference, 190);
+        assert_eq!(diff.image.get_pixel(2, 2), &Rgba([255, 0, 0, 255]));
+
+        // The colour of transparent pixels is ignored
+        let c = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 0]));
+        let d = RgbaImage::from_pixel(4, 4, Rgba([255, 255, 255, 0]));
+        assert_eq!(compare(&c, &d, 0).unwrap().differing_pixels, 0);
+
+        assert!(compare(&a, &RgbaImage::new(2, 2), 0).is_none());
+    }
+
+    #[test]
+    fn test_snapshot_text() {
+        let view = || node!(Text::new(txt!("Hello")).style("font", "Space Grotesk"));
+        let drawn = |image: RgbaImage| image.pixels().filter(|p| p[3] > 0).count();
+
+        assert_eq!(drawn(snapshot(100, 20, 1.0, view)), 0);
+        let window = HeadlessWindow::new(100, 20).fonts(test_fonts());
+        assert!(drawn(snapshot_with(window, view)) > 20);
+    }
+}
@@ -6,6 +6,9 @@ use std::sync::{Mutex, OnceLock};
 
 use cosmic_text::Weight;
 
+use crate::renderables::image::ImageFit;
+use crate::renderables::rect::{BoxShadow, Gradient};
+use crate::renderables::text::{TextDecoration, TextDecorationLine, TextShadow, TextWrap};
 use crate::types::*;
 use crate::{layout::*, size};
 
@@ -62,6 +65,40 @@ impl Default for FontWeight {
     }
 }
 
+/// Whether the upright, italic or oblique face of a font is used. Fonts without such a face fall back to another.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FontStyle {
+    Normal,
+    Italic,
+    Oblique,
+}
+
+impl Default for FontStyle {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+/// The width of the face of a font that is used, for fonts that come in several.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FontStretch {
+    UltraCondensed = 1,
+    ExtraCondensed = 2,
+    Condensed = 3,
+    SemiCondensed = 4,
+    Normal = 5,
+    SemiExpanded = 6,
+    Expanded = 7,
+    ExtraExpanded = 8,
+    UltraExpanded = 9,
+}
+
+impl Default for FontStretch {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum StyleVal {
     Dimension(Dimension),
@@ -75,6 +112,14 @@ pub enum StyleVal {
     VerticalPosition(VerticalPosition),
     BorderWidth(BorderWidth),
     FontWeight(FontWeight),
+    FontStyle(FontStyle),
+    FontStretch(FontStretch),
+    BoxShadow(BoxShadow),
+    Gradient(Gradient),
+    ImageFit(ImageFit),
+    TextWrap(TextWrap),
+    TextDecoration(TextDecoration),
+    TextShadow(TextShadow),
     Float(f64),
     Int(u32),
     Bool(bool),
@@ -1007,6 +1052,74 @@ impl Default for Style {
                 StyleKey::new("Text", "h_alignment", None),
                 HorizontalPosition::Left.into(),
             ),
+            (
+                StyleKey::new("Text", "font_style", None),
+                FontStyle::Normal.into(),
+            ),
+            (
+                StyleKey::new("Text", "font_style", Some("italic")),
+                FontStyle::Italic.into(),
+            ),
+            (
+                StyleKey::new("Text", "font_style", Some("not-italic")),
+                FontStyle::Normal.into(),
+            ),
+            (
+                StyleKey::new("Text", "font_stretch", None),
+                FontStretch::Normal.into(),
+            ),
+            (
+                StyleKey::new("Text", "font_stretch", Some("font-stretch-condensed")),
+                FontStretch::Condensed.into(),
+            ),
+            (
+                StyleKey::new("Text", "font_stretch", Some("font-stretch-expanded")),
+                FontStretch::Expanded.into(),
+            ),
+            (StyleKey::new("Text", "letter_spacing", None), 0.0.into()),
+            (StyleKey::new("Text", "tabular_nums", None), false.into()),
+            (
+                StyleKey::new("Text", "tabular_nums", Some("tabular-nums")),
+                true.into(),
+            ),
+            (StyleKey::new("Text", "wrap", None), TextWrap::None.into()),
+            (
+                StyleKey::new("Text", "wrap", Some("text-wrap")),
+                TextWrap::Word.into(),
+            ),
+            (
+                StyleKey::new("Text", "decoration", Some("underline")),
+                TextDecoration::new(TextDecorationLine::Underline).into(),
+            ),
+            (
+                StyleKey::new("Text", "decoration", Some("overline")),
+                TextDecoration::new(TextDecorationLine::Overline).into(),
+            ),
+            (
+                StyleKey::new("Text", "decoration", Some("line-through")),
+                TextDecoration::new(TextDecorationLine::LineThrough).into(),
+            ),
+            (
+                StyleKey::new("Text", "shadow", Some("text-shadow-sm")),
+                TextShadow::new(Color::rgba(0., 0., 0., 0.2), (0., 1.), 2.).into(),
+            ),
+            (
+                StyleKey::new("Text", "shadow", Some("text-shadow")),
+                TextShadow::new(Color::rgba(0., 0., 0., 0.4), (0., 1.), 4.).into(),
+            ),
+            (
+                StyleKey::new("Text", "shadow", Some("text-shadow-lg")),
+                TextShadow::new(Color::rgba(0., 0., 0., 0.5), (0., 2.), 8.).into(),
+            ),
+            (StyleKey::new("Text", "ellipsis", None), false.into()),
+            (
+                StyleKey::new("Text", "selection_color", None),
+                Color::MID_GREY.into(),
+            ),
+            (
+                StyleKey::new("Text", "ellipsis", Some("truncate")),
+                true.into(),
+            ),
             (
                 StyleKey::new("Text", "line_height", Some("leading-3")),
                 12.0.into(),
@@ -1091,8 +1204,45 @@ impl Default for Style {
                 StyleKey::new("Scroll", "bar_active_color", None),
                 Color::DARK_GREY.into(),
             ),
+            (
+                StyleKey::new("Scroll", "shadow", Some("shadow-sm")),
+                BoxShadow::new(Color::rgba(0., 0., 0., 0.05), (0., 1.), 2., 0.).into(),
+            ),
+            (
+                StyleKey::new("Scroll", "shadow", Some("shadow")),
+                BoxShadow::new(Color::rgba(0., 0., 0., 0.1), (0., 1.), 3., 0.).into(),
+            ),
+            (
+                StyleKey::new("Scroll", "shadow", Some("shadow-md")),
+                BoxShadow::new(Color::rgba(0., 0., 0., 0.1), (0., 4.), 6., -1.).into(),
+            ),
+            (
+                StyleKey::new("Scroll", "shadow", Some("shadow-lg")),
+                BoxShadow::new(Color::rgba(0., 0., 0., 0.1), (0., 10.), 15., -3.).into(),
+            ),
+            (
+                StyleKey::new("Scroll", "shadow", Some("shadow-xl")),
+                BoxShadow::new(Color::rgba(0., 0., 0., 0.1), (0., 20.), 25., -5.).into(),
+            ),
+            (
+                StyleKey::new("Scroll", "shadow", Some("shadow-inner")),
+                BoxShadow::new(Color::rgba(0., 0., 0., 0.05), (0., 2.), 4., 0.)
+                    .inset()
+                    .into(),
+            ),
             //Image
             (StyleKey::new("Image", "radius", None), 0.0.into()),
+            (StyleKey::new("Image", "fit", None), ImageFit::Fill.into()),
+            (
+                StyleKey::new("Image", "h_align", None),
+                HorizontalPosition::Center.into(),
+            ),
+            (
+                StyleKey::new("Image", "v_align", None),
+                VerticalPosition::Center.into(),
+            ),
+            (StyleKey::new("Image", "flip_x", None), false.into()),
+            (StyleKey::new("Image", "flip_y", None), false.into()),
         ]);
         Self(map)
     }
@@ -1447,6 +1597,158 @@ impl From<Option<StyleVal>> for FontWeight {
         }
     }
 }
+impl From<BoxShadow> for StyleVal {
+    fn from(c: BoxShadow) -> Self {
+        Self::BoxShadow(c)
+    }
+}
+impl From<StyleVal> for BoxShadow {
+    fn from(v: StyleVal) -> Self {
+        match v {
+            StyleVal::BoxShadow(c) => c,
+            x => panic!("Tried to coerce {x:?} into a BoxShadow"),
+        }
+    }
+}
+impl From<Gradient> for StyleVal {
+    fn from(c: Gradient) -> Self {
+        Self::Gradient(c)
+    }
+}
+impl From<StyleVal> for Gradient {
+    fn from(v: StyleVal) -> Self {
+        match v {
+            StyleVal::Gradient(c) => c,
+            x => panic!("Tried to coerce {x:?} into a Gradient"),
+        }
+    }
+}
+impl From<ImageFit> for StyleVal {
+    fn from(c: ImageFit) -> Self {
+        Self::ImageFit(c)
+    }
+}
+impl From<StyleVal> for ImageFit {
+    fn from(v: StyleVal) -> Self {
+        match v {
+            StyleVal::ImageFit(c) => c,
+            x => panic!("Tried to coerce {x:?} into an ImageFit"),
+        }
+    }
+}
+impl From<Option<StyleVal>> for ImageFit {
+    fn from(v: Option<StyleVal>) -> Self {
+        match v {
+            Some(StyleVal::ImageFit(c)) => c,
+            x => panic!("Tried to coerce {x:?} into an ImageFit"),
+        }
+    }
+}
+impl From<FontStyle> for StyleVal {
+    fn from(c: FontStyle) -> Self {
+        Self::FontStyle(c)
+    }
+}
+impl From<StyleVal> for FontStyle {
+    fn from(v: StyleVal) -> Self {
+        match v {
+            StyleVal::FontStyle(c) => c,
+            x => panic!("Tried to coerce {x:?} into a FontStyle"),
+        }
+    }
+}
+impl From<Option<StyleVal>> for FontStyle {
+    fn from(v: Option<StyleVal>) -> Self {
+        match v {
+            Some(StyleVal::FontStyle(c)) => c,
+            x => panic!("Tried to coerce {x:?} into a FontStyle"),
+        }
+    }
+}
+impl From<FontStretch> for StyleVal {
+    fn from(c: FontStretch) -> Self {
+        Self::FontStretch(c)
+    }
+}
+impl From<StyleVal> for FontStretch {
+    fn from(v: StyleVal) -> Self {
+        match v {
+            StyleVal::FontStretch(c) => c,
+            x => panic!("Tried to coerce {x:?} into a FontStretch"),
+        }
+    }
+}
+impl From<Option<StyleVal>> for FontStretch {
+    fn from(v: Option<StyleVal>) -> Self {
+        match v {
+            Some(StyleVal::FontStretch(c)) => c,
+            x => panic!("Tried to coerce {x:?} into a FontStretch"),
+        }
+    }
+}
+impl From<TextWrap> for StyleVal {
+    fn from(c: TextWrap) -> Self {
+        Self::TextWrap(c)
+    }
+}
+impl From<StyleVal> for TextWrap {
+    fn from(v: StyleVal) -> Self {
+        match v {
+            StyleVal::TextWrap(c) => c,
+            x => panic!("Tried to coerce {x:?} into a TextWrap"),
+        }
+    }
+}
+impl From<Option<StyleVal>> for TextWrap {
+    fn from(v: Option<StyleVal>) -> Self {
+        match v {
+            Some(StyleVal::TextWrap(c)) => c,
+            x => panic!("Tried to coerce {x:?} into a TextWrap"),
+        }
+    }
+}
+impl From<TextDecoration> for StyleVal {
+    fn from(c: TextDecoration) -> Self {
+        Self::TextDecoration(c)
+    }
+}
+impl From<StyleVal> for TextDecoration {
+    fn from(v: StyleVal) -> Self {
+        match v {
+            StyleVal::TextDecoration(c) => c,
+            x => panic!("Tried to coerce {x:?} into a TextDecoration"),
+        }
+    }
+}
+impl From<Option<StyleVal>> for TextDecoration {
+    fn from(v: Option<StyleVal>) -> Self {
+        match v {
+            Some(StyleVal::TextDecoration(c)) => c,
+            x => panic!("Tried to coerce {x:?} into a TextDecoration"),
+        }
+    }
+}
+impl From<TextShadow> for StyleVal {
+    fn from(c: TextShadow) -> Self {
+        Self::TextShadow(c)
+    }
+}
+impl From<StyleVal> for TextShadow {
+    fn from(v: StyleVal) -> Self {
+        match v {
+            StyleVal::TextShadow(c) => c,
+            x => panic!("Tried to coerce {x:?} into a TextShadow"),
+        }
+    }
+}
+impl From<Option<StyleVal>> for TextShadow {
+    fn from(v: Option<StyleVal>) -> Self {
+        match v {
+            Some(StyleVal::TextShadow(c)) => c,
+            x => panic!("Tried to coerce {x:?} into a TextShadow"),
+        }
+    }
+}
 impl From<f64> for StyleVal {
     fn from(c: f64) -> Self {
         Self::Float(c)
@@ -1537,10 +1839,42 @@ impl StyleVal {
         self.into()
     }
 
+    pub fn font_style(self) -> FontStyle {
+        self.into()
+    }
+
+    pub fn font_stretch(self) -> FontStretch {
+        self.into()
+    }
+
     pub fn color(self) -> Color {
         self.into()
     }
 
+    pub fn box_shadow(self) -> BoxShadow {
+        self.into()
+    }
+
+    pub fn gradient(self) -> Gradient {
+        self.into()
+    }
+
+    pub fn image_fit(self) -> ImageFit {
+        self.into()
+    }
+
+    pub fn text_wrap(self) -> TextWrap {
+        self.into()
+    }
+
+    pub fn text_decoration(self) -> TextDecoration {
+        self.into()
+    }
+
+    pub fn text_shadow(self) -> TextShadow {
+        self.into()
+    }
+
     pub fn str(self) -> &'static str {
         self.into()
     }
@@ -0,0 +1,304 @@
+//! Textures whose pixels are streamed in at runtime, e.g. from a camera or a video decoder.
+//!
+//! Frames can be pushed into a [`Texture`] from any thread. They are drawn by the [`TextureView`][crate::widgets::TextureView] widget, or by a [`renderables::Texture`][crate::renderables::Texture], which update the renderer's copy of the texture in place whenever a new frame arrives.
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// The layout of the pixels of a frame that is pushed into a [`Texture`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TextureFormat {
+    /// 4 bytes per pixel, red, green, blue and alpha, that are not premultiplied.
+    Rgba8,
+    /// A plane of luma bytes, followed by a plane of interleaved U and V bytes at half the resolution in both directions.
+    Nv12,
+    /// 2 bytes per pixel: every pair of pixels is stored as Y0, U, Y1, V.
+    Yuyv,
+}
+
+impl TextureFormat {
+    /// How many bytes a frame of the given size takes up.
+    pub fn frame_len(&self, width: u32, height: u32) -> usize {
+        let (width, height) = (width as usize, height as usize);
+        match self {
+            TextureFormat::Rgba8 => width * height * 4,
+            TextureFormat::Nv12 => width * height + width.div_ceil(2) * 2 * height.div_ceil(2),
+            TextureFormat::Yuyv => width.div_ceil(2) * 4 * height,
+        }
+    }
+}
+
+/// The latest frame of a [`Texture`], converted to RGBA.
+#[derive(Debug, Default)]
+pub struct TextureFrame {
+    pub width: u32,
+    pub height: u32,
+    /// `width * height` RGBA8 pixels, row by row. They are not premultiplied.
+    pub data: Vec<u8>,
+    /// Counts the frames that were pushed into the texture.
+    pub generation: u64,
+}
+
+/// A named texture that frames can be pushed into from any thread. Clones share the same frame.
+#[derive(Clone)]
+pub struct Texture {
+    name: String,
+    frame: Arc<Mutex<TextureFrame>>,
+}
+
+impl std::fmt::Debug for Texture {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Texture").field("name", &self.name).finish()
+    }
+}
+
+impl PartialEq for Texture {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.frame, &other.frame)
+    }
+}
+
+fn textures() -> MutexGuard<'static, HashMap<String, Texture>> {
+    static TEXTURES: OnceLock<Mutex<HashMap<String, Texture>>> = OnceLock::new();
+    TEXTURES
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap()
+}
+
+impl Texture {
+    /// The texture called `name`, which is created, without any frame, if it doesn't exist yet.
+    pub fn named<S: Into<String>>(name: S) -> Self {
+        let name = name.into();
+        textures()
+            .entry(name.clone())
+            .or_insert_with(|| Self {
+                name,
+                frame: Default::default(),
+            })
+            .clone()
+    }
+
+    /// Forget the texture called `name`. Existing handles keep working, but [`Texture::named`] will create a new texture.
+    pub fn remove(name: &str) {
+        textures().remove(name);
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Tells textures apart, including one that replaced a removed texture of the same name.
+    pub(crate) fn id(&self) -> usize {
+        Arc::as_ptr(&self.frame) as usize
+    }
+
+    /// How many frames were pushed into the texture. Changes whenever a new frame arrives.
+    pub fn generation(&self) -> u64 {
+        self.frame.lock().unwrap().generation
+    }
+
+    /// Lock the latest frame, e.g. to upload it.
+    pub fn frame(&self) -> MutexGuard<'_, TextureFrame> {
+        self.frame.lock().unwrap()
+    }
+
+    /// Replace the frame of the texture with `data`, which holds `width * height` pixels laid out in `format`.
+    /// The frame is converted to RGBA on the calling thread. Its buffer is reused when the size doesn't change.
+    pub fn push(
+        &self,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        data: &[u8],
+    ) -> anyhow::Result<()> {
+        let len = format.frame_len(width, height);
+        if data.len() < len {
+            anyhow::bail!(
+                "A {}x{} {:?} frame needs {} bytes, but {} were pushed",
+                width,
+                height,
+                format,
+                len,
+                data.len()
+            );
+        }
+
+        let mut frame = self.frame.lock().unwrap();
+        frame.data.resize(width as usize * height as usize * 4, 0);
+        match format {
+            TextureFormat::Rgba8 => frame.data.copy_from_slice(&data[..len]),
+            TextureFormat::Nv12 => nv12_to_rgba(width, height, data, &mut frame.data),
+            TextureFormat::Yuyv => yuyv_to_rgba(width, height, data, &mut frame.data),
+        }
+        frame.width = width;
+        frame.height = height;
+        frame.generation += 1;
+        Ok(())
+    }
+}
+
+/// Convert a BT.601 limited range YUV pixel to RGB.
+fn yuv_to_rgb(y: u8, u: u8, v: u8) -> [u8; 3] {
+    let c = 298 * (y as i32 - 16);
+    let d = u as i32 - 128;
+    let e = v as i32 - 128;
+    let clamp = |x: i32| ((x + 128) >> 8).clamp(0, 255) as u8;
+    [
+        clamp(c + 409 * e),
+        clamp(c - 100 * d - 208 * e),
+        clamp(c + 516 * d),
+    ]
+}
+
+/// Convert an RGB pixel to BT.601 limited range YUV.
+fn rgb_to_yuv([r, g, b]: [u8; 3]) -> [u8; 3] {
+    let (r, g, b) = (r as i32, g as i32, b as i32);
+    [
+        (((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8,
+        (((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8,
+        (((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8,
+    ]
+}
+
+fn nv12_to_rgba(width: u32, height: u32, data: &[u8], rgba: &mut [u8]) {
+    let (width, height) = (width as usize, height as usize);
+    let stride = width.div_ceil(2) * 2;
+    let (luma, chroma) = data.split_at(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let uv = (y / 2) * stride + (x / 2) * 2;
+            let [r, g, b] = yuv_to_rgb(luma[y * width + x], chroma[uv], chroma[uv + 1]);
+            let i = (y * width + x) * 4;
+            rgba[i..i + 4].copy_from_slice(&[r, g, b, 255]);
+        }
+    }
+}
+
+fn yuyv_to_rgba(width: u32, height: u32, data: &[u8], rgba: &mut [u8]) {
+    let (width, height) = (width as usize, height as usize);
+    let stride = width.div_ceil(2) * 4;
+    for y in 0..height {
+        for x in 0..width {
+            let pair = y * stride + (x / 2) * 4;
+            let luma = data[pair + (x % 2) * 2];
+            let [r, g, b] = yuv_to_rgb(luma, data[pair + 1], data[pair + 3]);
+            let i = (y * width + x) * 4;
+            rgba[i..i + 4].copy_from_slice(&[r, g, b, 255]);
+        }
+    }
+}
+
+/// Generates frames of colour bars that scroll sideways, for exercising a [`Texture`] without a camera.
+#[derive(Debug)]
+pub struct TestFrameSource {
+    pub width: u32,
+    pub height: u32,
+    pub format: TextureFormat,
+    frame: u32,
+}
+
+impl TestFrameSource {
+    /// The colours of the bars, from left to right.
+    pub const BARS: [[u8; 3]; 8] = [
+        [255, 255, 255],
+        [255, 255, 0],
+        [0, 255, 255],
+        [0, 255, 0],
+        [255, 0, 255],
+        [255, 0, 0],
+        [0, 0, 255],
+        [0, 0, 0],
+    ];
+
+    pub fn new(width: u32, height: u32, format: TextureFormat) -> Self {
+        Self {
+            width,
+            height,
+            format,
+            frame: 0,
+        }
+    }
+
+    /// The colour at `x` in the frame with the given index.
+    pub fn color_at(&self, frame: u32, x: u32) -> [u8; 3] {
+        let bar = self.width.div_ceil(Self::BARS.len() as u32).max(1);
+        let x = (x + frame) % self.width.max(1);
+        Self::BARS[(x / bar) as usize % Self::BARS.len()]
+    }
+
+    /// Generate the next frame, in this source's `format`.
+    pub fn next_frame(&mut self) -> Vec<u8> {
+        let frame = self.frame;
+        self.frame = self.frame.wrapping_add(1);
+        let (width, height) = (self.width as usize, self.height as usize);
+        let mut data = vec![0; self.format.frame_len(self.width, self.height)];
+
+        match self.format {
+            TextureFormat::Rgba8 => {
+                for (i, pixel) in data.chunks_exact_mut(4).enumerate() {
+                    let [r, g, b] = self.color_at(frame, (i % width) as u32);
+                    pixel.copy_from_slice(&[r, g, b, 255]);
+                }
+            }
+            TextureFormat::Nv12 => {
+                let stride = width.div_ceil(2) * 2;
+                let (luma, chroma) = data.split_at_mut(width * height);
+                for y in 0..height {
+                    for x in 0..width {
+                        let [l, u, v] = rgb_to_yuv(self.color_at(frame, x as u32));
+                        luma[y * width + x] = l;
+                        if y % 2 == 0 && x % 2 == 0 {
+                            let uv = (y / 2) * stride + x;
+                            chroma[uv] = u;
+                            chroma[uv + 1] = v;
+                        }
+                    }
+                }
+            }
+            TextureFormat::Yuyv => {
+                let stride = width.div_ceil(2) * 4;
+                for y in 0..height {
+                    for x in 0..width {
+                        let [l, u, v] = rgb_to_yuv(self.color_at(frame, x as u32));
+                        let pair = y * stride + (x / 2) * 4;
+                        data[pair + (x % 2) * 2] = l;
+                        if x % 2 == 0 {
+                            data[pair + 1] = u;
+                            data[pair + 3] = v;
+                        }
+                    }
+                }
+            }
+        }
+        data
+    }
+
+    /// Push the next frame into `texture`.
+    pub fn push_to(&mut self, texture: &Texture) -> anyhow::Result<()> {
+        let data = self.next_frame();
+        texture.push(self.width, self.height, self.format, &data)
+    }
+
+    /// Push a frame into `texture` every `interval` from a new thread, until the returned flag is set.
+    pub fn spawn(
+        mut self,
+        texture: Texture,
+        interval: Duration,
+    ) -> (Arc<AtomicBool>, JoinHandle<()>) {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let handle = std::thread::spawn(move || {
+            while !stopped.load(Ordering::Relaxed) {
+                if let Err(e) = self.push_to(&texture) {
+                    println!("Error while pushing test frame {:?}", e);
+                    break;
+                }
+                std::thread::sleep(interval);
+            }
+        });
+        (stop, handle)
+    }
+}
@@ -585,6 +585,60 @@ impl AABB {
             },
         }
     }
+
+    /// Does the AABB cover no area?
+    pub fn is_empty(&self) -> bool {
+        self.width() <= 0.0 || self.height() <= 0.0
+    }
+
+    /// Do the two AABBs overlap?
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.pos.x < other.bottom_right.x
+            && other.pos.x < self.bottom_right.x
+            && self.pos.y < other.bottom_right.y
+            && other.pos.y < self.bottom_right.y
+    }
+
+    /// The area covered by both AABBs, if any. Keeps the z of `self`.
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        if !self.intersects(other) {
+            return None;
+        }
+        Some(Self {
+            pos: Pos::new(
+                self.pos.x.max(other.pos.x),
+                self.pos.y.max(other.pos.y),
+                self.pos.z,
+            ),
+            bottom_right: Point::new(
+                self.bottom_right.x.min(other.bottom_right.x),
+                self.bottom_right.y.min(other.bottom_right.y),
+            ),
+        })
+    }
+
+    /// The smallest AABB that covers both AABBs. Keeps the z of `self`.
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            pos: Pos::new(
+                self.pos.x.min(other.pos.x),
+                self.pos.y.min(other.pos.y),
+                self.pos.z,
+            ),
+            bottom_right: Point::new(
+                self.bottom_right.x.max(other.bottom_right.x),
+                self.bottom_right.y.max(other.bottom_right.y),
+            ),
+        }
+    }
+
+    /// Grow the AABB by `d` on every side.
+    pub fn expand(self, d: f32) -> Self {
+        Self {
+            pos: Pos::new(self.pos.x - d, self.pos.y - d, self.pos.z),
+            bottom_right: Point::new(self.bottom_right.x + d, self.bottom_right.y + d),
+        }
+    }
 }
 
 impl Scalable for AABB {
@@ -639,6 +693,368 @@ impl Div<f32> for AABB {
     }
 }
 
+/// A 2D affine transform, that maps `(x, y)` to `(a * x + c * y + e, b * x + d * y + f)`.
+///
+/// Construct with [`translate`][Transform::translate], [`scale`][Transform::scale], and [`rotate`][Transform::rotate],
+/// and combine with [`then`][Transform::then].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        a: 1.0,
+        b: 0.0,
+        c: 0.0,
+        d: 1.0,
+        e: 0.0,
+        f: 0.0,
+    };
+
+    /// Move by `(x, y)`.
+    pub fn translate(x: f32, y: f32) -> Self {
+        Self {
+            e: x,
+            f: y,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Scale by `x` horizontally and `y` vertically. Negative values flip.
+    pub fn scale(x: f32, y: f32) -> Self {
+        Self {
+            a: x,
+            d: y,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Rotate clockwise by `radians`.
+    pub fn rotate(radians: f32) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        Self {
+            a: cos,
+            b: sin,
+            c: -sin,
+            d: cos,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// The transform that applies `self`, followed by `other`.
+    pub fn then(self, other: Self) -> Self {
+        Self {
+            a: other.a * self.a + other.c * self.b,
+            b: other.b * self.a + other.d * self.b,
+            c: other.a * self.c + other.c * self.d,
+            d: other.b * self.c + other.d * self.d,
+            e: other.a * self.e + other.c * self.f + other.e,
+            f: other.b * self.e + other.d * self.f + other.f,
+        }
+    }
+
+    /// Apply `self` about `origin`, instead of about `(0, 0)`.
+    pub fn about(self, origin: Point) -> Self {
+        Self::translate(-origin.x, -origin.y)
+            .then(self)
+            .then(Self::translate(origin.x, origin.y))
+    }
+
+    /// The transform that undoes `self`, if it can be undone.
+    pub fn invert(self) -> Option<Self> {
+        let det = self.a * self.d - self.b * self.c;
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        let (a, b, c, d) = (self.d / det, -self.b / det, -self.c / det, self.a / det);
+        Some(Self {
+            a,
+            b,
+            c,
+            d,
+            e: -(a * self.e + c * self.f),
+            f: -(b * self.e + d * self.f),
+        })
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::IDENTITY
+    }
+
+    /// Map a point.
+    pub fn apply(&self, p: Point) -> Point {
+        Point {
+            x: self.a * p.x + self.c * p.y + self.e,
+            y: self.b * p.x + self.d * p.y + self.f,
+        }
+    }
+
+    /// The smallest [`AABB`] that covers `aabb` once it is transformed. Keeps the z of `aabb`.
+    pub fn apply_aabb(&self, aabb: AABB) -> AABB {
+        let corners = [
+            self.apply(aabb.pos.into()),
+            self.apply(Point::new(aabb.bottom_right.x, aabb.pos.y)),
+            self.apply(Point::new(aabb.pos.x, aabb.bottom_right.y)),
+            self.apply(aabb.bottom_right),
+        ];
+        let (mut min, mut max) = (corners[0], corners[0]);
+        for p in corners.iter().skip(1) {
+            min = Point::new(min.x.min(p.x), min.y.min(p.y));
+            max = Point::new(max.x.max(p.x), max.y.max(p.y));
+        }
+        AABB {
+            pos: Pos::new(min.x, min.y, aabb.pos.z),
+            bottom_right: max,
+        }
+    }
+}
+
+impl Hash for Transform {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for v in [self.a, self.b, self.c, self.d, self.e, self.f] {
+            v.to_bits().hash(state);
+        }
+    }
+}
+
+impl From<Transform> for femtovg::Transform2D {
+    fn from(t: Transform) -> Self {
+        femtovg::Transform2D([t.a, t.b, t.c, t.d, t.e, t.f])
+    }
+}
+
+impl From<Transform> for resvg::tiny_skia::Transform {
+    fn from(t: Transform) -> Self {
+        resvg::tiny_skia::Transform::from_row(t.a, t.b, t.c, t.d, t.e, t.f)
+    }
+}
+
+/// A segment of a vector path.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PathCommand {
+    MoveTo(Point),
+    LineTo(Point),
+    /// A quadratic bezier curve, through the control point to the end point.
+    QuadTo(Point, Point),
+    /// A cubic bezier curve, through the two control points to the end point.
+    CubicTo(Point, Point, Point),
+    /// An arc of the circle around `center`, from the angle `start` to `end`, in radians clockwise from the positive x axis.
+    /// Runs counter-clockwise when `end` is less than `start`. Begins with a line to the start of the arc, unless it is the first command.
+    Arc {
+        center: Point,
+        radius: f32,
+        start: f32,
+        end: f32,
+    },
+    Close,
+}
+
+impl PathCommand {
+    /// Apply `f` to every point of the command.
+    pub fn map<F: FnMut(Point) -> Point>(self, mut f: F) -> Self {
+        match self {
+            Self::MoveTo(p) => Self::MoveTo(f(p)),
+            Self::LineTo(p) => Self::LineTo(f(p)),
+            Self::QuadTo(c, p) => Self::QuadTo(f(c), f(p)),
+            Self::CubicTo(c1, c2, p) => Self::CubicTo(f(c1), f(c2), f(p)),
+            Self::Arc {
+                center,
+                radius,
+                start,
+                end,
+            } => Self::Arc {
+                center: f(center),
+                radius,
+                start,
+                end,
+            },
+            Self::Close => Self::Close,
+        }
+    }
+
+    /// Replace every [`Arc`][PathCommand::Arc] with the cubic bezier curves that approximate it.
+    pub fn expand_arcs(commands: &[Self]) -> Vec<Self> {
+        let mut expanded = Vec::with_capacity(commands.len());
+        for command in commands.iter() {
+            let Self::Arc {
+                center,
+                radius,
+                start,
+                end,
+            } = *command
+            else {
+                expanded.push(*command);
+                continue;
+            };
+            let sweep = (end - start).clamp(-std::f32::consts::TAU, std::f32::consts::TAU);
+            let at = |angle: f32| {
+                Point::new(
+                    center.x + radius * angle.cos(),
+                    center.y + radius * angle.sin(),
+                )
+            };
+            expanded.push(if expanded.is_empty() {
+                Self::MoveTo(at(start))
+            } else {
+                Self::LineTo(at(start))
+            });
+
+            // Each curve covers at most a quarter of the circle
+            let segments = (sweep.abs() / std::f32::consts::FRAC_PI_2).ceil().max(1.);
+            let delta = sweep / segments;
+            let k = 4. / 3. * (delta / 4.).tan() * radius;
+            for i in 0..segments as usize {
+                let (a1, a2) = (start + delta * i as f32, start + delta * (i + 1) as f32);
+                let (p1, p2) = (at(a1), at(a2));
+                expanded.push(Self::CubicTo(
+                    Point::new(p1.x - k * a1.sin(), p1.y + k * a1.cos()),
+                    Point::new(p2.x + k * a2.sin(), p2.y - k * a2.cos()),
+                    p2,
+                ));
+            }
+        }
+        expanded
+    }
+
+    /// Commands that trace a rect with a separate radius for each corner, in the order
+    /// top left, top right, bottom right, bottom left.
+    pub fn rounded_rect(aabb: AABB, radius: (f32, f32, f32, f32)) -> Vec<Self> {
+        // Magic number for approximating a quarter circle with a cubic bezier
+        const KAPPA: f32 = 0.552_284_8;
+        let max = aabb.width().min(aabb.height()) / 2.;
+        let (tl, tr, br, bl) = (
+            radius.0.clamp(0., max),
+            radius.1.clamp(0., max),
+            radius.2.clamp(0., max),
+            radius.3.clamp(0., max),
+        );
+        let (x, y, right, bottom) = (
+            aabb.pos.x,
+            aabb.pos.y,
+            aabb.bottom_right.x,
+            aabb.bottom_right.y,
+        );
+        let k = 1. - KAPPA;
+        vec![
+            Self::MoveTo(Point::new(x + tl, y)),
+            Self::LineTo(Point::new(right - tr, y)),
+            Self::CubicTo(
+                Point::new(right - tr * k, y),
+                Point::new(right, y + tr * k),
+                Point::new(right, y + tr),
+            ),
+            Self::LineTo(Point::new(right, bottom - br)),
+            Self::CubicTo(
+                Point::new(right, bottom - br * k),
+                Point::new(right - br * k, bottom),
+                Point::new(right - br, bottom),
+            ),
+            Self::LineTo(Point::new(x + bl, bottom)),
+            Self::CubicTo(
+                Point::new(x + bl * k, bottom),
+                Point::new(x, bottom - bl * k),
+                Point::new(x, bottom - bl),
+            ),
+            Self::LineTo(Point::new(x, y + tl)),
+            Self::CubicTo(
+                Point::new(x, y + tl * k),
+                Point::new(x + tl * k, y),
+                Point::new(x + tl, y),
+            ),
+            Self::Close,
+        ]
+    }
+}
+
+impl Hash for PathCommand {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        if let Self::Arc {
+            radius, start, end, ..
+        } = self
+        {
+            for v in [radius, start, end] {
+                v.to_bits().hash(state);
+            }
+        }
+        let mut hash_point = |p: Point| {
+            p.x.to_bits().hash(state);
+            p.y.to_bits().hash(state);
+            p
+        };
+        self.map(&mut hash_point);
+    }
+}
+
+/// A shape that the children of a Node are clipped to, see [`Component::clip`][crate::component::Component#method.clip].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Clip {
+    /// The [`AABB`] of the Node, with a radius in logical pixels for each corner, in the order
+    /// top left, top right, bottom right, bottom left.
+    RoundedRect(f32, f32, f32, f32),
+    /// A closed path, in logical pixels relative to the top left corner of the Node.
+    Path(Vec<PathCommand>),
+}
+
+impl Clip {
+    /// The AABB of the Node, with the same `radius` on every corner.
+    pub fn rounded(radius: f32) -> Self {
+        Self::RoundedRect(radius, radius, radius, radius)
+    }
+
+    /// The clip of a Node at `aabb`, in physical pixels.
+    pub(crate) fn to_path(&self, aabb: AABB, scale_factor: f32) -> Vec<PathCommand> {
+        match self {
+            Self::RoundedRect(tl, tr, br, bl) => PathCommand::rounded_rect(
+                aabb,
+                (
+                    tl * scale_factor,
+                    tr * scale_factor,
+                    br * scale_factor,
+                    bl * scale_factor,
+                ),
+            ),
+            Self::Path(commands) => PathCommand::expand_arcs(commands)
+                .iter()
+                .map(|c| {
+                    c.map(|p| {
+                        Point::new(
+                            aabb.pos.x + p.x * scale_factor,
+                            aabb.pos.y + p.y * scale_factor,
+                        )
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Hash for Clip {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::RoundedRect(tl, tr, br, bl) => {
+                for r in [tl, tr, br, bl] {
+                    r.to_bits().hash(state);
+                }
+            }
+            Self::Path(commands) => commands.hash(state),
+        }
+    }
+}
+
 /// RGBA color struct, used for styling and rendering. Values are normalized (0.0--1.0) floating point.
 #[derive(Debug, Copy, Clone, PartialEq, Pod, Zeroable, Serialize, Deserialize)]
 #[repr(C)]
@@ -862,6 +1278,13 @@ impl From<Color> for femtovg::Color {
     }
 }
 
+impl From<Color> for resvg::tiny_skia::Color {
+    fn from(value: Color) -> Self {
+        let Color { r, g, b, a } = value;
+        resvg::tiny_skia::Color::from_rgba8(r as u8, g as u8, b as u8, (a * 255.0) as u8)
+    }
+}
+
 #[inline]
 fn u8_to_norm(x: u8) -> f32 {
     x as f32 / 255.0
@@ -917,6 +1340,66 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_aabb_intersection() {
+        let a = AABB::new(Pos::new(0.0, 0.0, 0.0), Scale::new(10.0, 10.0));
+        let b = AABB::new(Pos::new(5.0, 5.0, 0.0), Scale::new(10.0, 10.0));
+        let c = AABB::new(Pos::new(10.0, 0.0, 0.0), Scale::new(10.0, 10.0));
+
+        assert_eq!(
+            a.intersection(&b),
+            Some(AABB::new(Pos::new(5.0, 5.0, 0.0), Scale::new(5.0, 5.0)))
+        );
+        // Touching edges do not overlap
+        assert!(!a.intersects(&c));
+        assert_eq!(a.intersection(&c), None);
+        assert_eq!(
+            a.union(&b),
+            AABB::new(Pos::new(0.0, 0.0, 0.0), Scale::new(15.0, 15.0))
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        let t = Transform::rotate(std::f32::consts::FRAC_PI_2).about(Point::new(10.0, 10.0));
+        let p = t.apply(Point::new(20.0, 10.0));
+        assert!((p.x - 10.0).abs() < 1e-4 && (p.y - 20.0).abs() < 1e-4);
+
+        let p = t.invert().unwrap().apply(p);
+        assert!((p.x - 20.0).abs() < 1e-4 && (p.y - 10.0).abs() < 1e-4);
+
+        let t = Transform::scale(2.0, 2.0).then(Transform::translate(5.0, 0.0));
+        assert_eq!(t.apply(Point::new(1.0, 1.0)), Point::new(7.0, 2.0));
+        assert_eq!(
+            t.apply_aabb(AABB::new(Pos::new(0.0, 0.0, 0.0), Scale::new(10.0, 10.0))),
+            AABB::new(Pos::new(5.0, 0.0, 0.0), Scale::new(20.0, 20.0))
+        );
+        assert!(Transform::scale(0.0, 1.0).invert().is_none());
+    }
+
+    #[test]
+    fn test_expand_arcs() {
+        let arc = PathCommand::Arc {
+            center: Point::new(0.0, 0.0),
+            radius: 10.0,
+            start: 0.0,
+            end: std::f32::consts::PI,
+        };
+        let commands = PathCommand::expand_arcs(&[arc]);
+        assert_eq!(commands.len(), 3);
+        assert_eq!(commands[0], PathCommand::MoveTo(Point::new(10.0, 0.0)));
+        // A quarter of the way round, clockwise, is straight down
+        let PathCommand::CubicTo(c1, _, p) = commands[1] else {
+            panic!("Expected a curve, got {:?}", commands[1]);
+        };
+        assert!(p.x.abs() < 1e-4 && (p.y - 10.0).abs() < 1e-4);
+        assert!((c1.x - 10.0).abs() < 1e-4 && (c1.y - 5.5228).abs() < 1e-3);
+
+        // Later arcs are joined with a line
+        let commands = PathCommand::expand_arcs(&[PathCommand::MoveTo(Point::new(0.0, 0.0)), arc]);
+        assert_eq!(commands[1], PathCommand::LineTo(Point::new(10.0, 0.0)));
+    }
+
     #[test]
     fn test_color_from() {
         // A float that is representable in 8 bits:
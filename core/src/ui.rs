@@ -1,11 +1,12 @@
 use crate::component::{Message, RootComponent};
+use crate::damage::Damage;
 use crate::event::{self, Event, EventCache, EventInput};
 use crate::input::*;
 use crate::layout::*;
 use crate::raw_handle::RawWaylandHandle;
-use crate::renderer::canvas::{self, GlCanvasContext};
-use crate::renderer::gl::{self};
-use crate::renderer::Renderer;
+use crate::renderer::canvas::GlCanvasContext;
+use crate::renderer::software::{SoftwareCanvas, SoftwareCanvasContext};
+use crate::renderer::{ActiveRenderer, Renderer, RendererType};
 use crate::{component::Component, node::Node, types::PixelSize};
 use crate::{lay, node::Registration, size, types::*, window::Window};
 use crossbeam_channel::{unbounded, Receiver, Sender};
@@ -20,9 +21,6 @@ use std::{
     time::Instant,
 };
 
-// This can become feature-dependant
-type ActiveRenderer = crate::renderer::canvas::CanvasRenderer;
-
 pub struct UI<W: Window, A: Component + Default + Send + Sync, B> {
     renderer: Arc<RwLock<Option<ActiveRenderer>>>,
     pub window: Arc<RwLock<W>>,
@@ -39,6 +37,8 @@ pub struct UI<W: Window, A: Component + Default + Send + Sync, B> {
     event_cache: EventCache,
     node_dirty: Arc<RwLock<bool>>,
     frame_dirty: Arc<RwLock<bool>>,
+    // Damage accumulated by draws since the last render
+    damage: Arc<RwLock<Damage>>,
     app_params: B,
 }
 
@@ -127,6 +127,7 @@ impl<
         )));
         let frame_dirty = Arc::new(RwLock::new(false));
         let node_dirty = Arc::new(RwLock::new(true));
+        let damage = Arc::new(RwLock::new(Damage::full()));
         let registrations: Arc<RwLock<Vec<Registration>>> = Default::default();
 
         let n = Self {
@@ -135,6 +136,7 @@ impl<
             render_channel: None,
             render_thread: None,
             frame_dirty: frame_dirty.clone(),
+            damage,
             draw_channel: None,
             _draw_thread: None,
             window,
@@ -150,11 +152,11 @@ impl<
         n
     }
 
-    fn node_ref(&self) -> RwLockReadGuard<'_, Node> {
+    pub(crate) fn node_ref(&self) -> RwLockReadGuard<'_, Node> {
         self.node.read().unwrap()
     }
 
-    fn node_mut(&mut self) -> RwLockWriteGuard<'_, Node> {
+    pub(crate) fn node_mut(&mut self) -> RwLockWriteGuard<'_, Node> {
         self.node.write().unwrap()
     }
 
@@ -165,6 +167,7 @@ impl<
         scale_factor: Arc<RwLock<f32>>,
         frame_dirty: Arc<RwLock<bool>>,
         node_dirty: Arc<RwLock<bool>>,
+        damage: Arc<RwLock<Damage>>,
         registrations: Arc<RwLock<Vec<Registration>>>,
         window: Arc<RwLock<W>>,
     ) -> JoinHandle<()> {
@@ -175,46 +178,19 @@ impl<
                     *node_dirty.write().unwrap() = false;
                     let logical_size = window.read().unwrap().logical_size();
                     let scale_factor = *scale_factor.read().unwrap();
-                    let mut new = Node::new(
-                        Box::<A>::default(),
-                        0,
-                        lay!(size: size!(logical_size.width as f32, logical_size.height as f32)),
-                    );
-
-                    {
-                        // We need to lock the renderer while we modify the node, so that we don't try to render it while doing so
-                        // Since this will cause a deadlock
-                        let renderer = renderer.write().unwrap();
-
-                        if renderer.is_none() {
-                            *node_dirty.write().unwrap() = true;
-                            return;
-                        }
-                    }
-
-                    let mut do_render = false;
-                    {
-                        // We need to acquire a lock on the node once we `view` it, because we remove its state at this point
-                        let mut old = node.write().unwrap();
-                        let mut new_registrations: Vec<Registration> = vec![];
-                        new.view(Some(&mut old), &mut new_registrations);
-                        *registrations.write().unwrap() = new_registrations;
-
-                        let renderer = renderer.read().unwrap();
 
-                        if renderer.is_none() {
-                            *node_dirty.write().unwrap() = true;
-                            return;
-                        }
-
-                        let caches: crate::renderer::Caches = renderer.as_ref().unwrap().caches();
-
-                        new.layout(&old, &mut caches.font.write().unwrap(), scale_factor);
-
-                        do_render = new.render(caches, Some(&mut old), scale_factor);
+                    let Some(do_render) = Self::draw_node(
+                        &renderer,
+                        &node,
+                        &damage,
+                        &registrations,
+                        logical_size,
+                        scale_factor,
+                    ) else {
+                        *node_dirty.write().unwrap() = true;
+                        return;
+                    };
 
-                        *old = new;
-                    }
                     {
                         if do_render {
                             let window = window.read();
@@ -229,6 +205,53 @@ impl<
         })
     }
 
+    /// View, layout and render a new Node graph, replacing the current one, and add the regions that changed to `damage`.
+    /// Returns `None` if there is no renderer yet, otherwise whether any Node was re-rendered.
+    fn draw_node(
+        renderer: &RwLock<Option<ActiveRenderer>>,
+        node: &RwLock<Node>,
+        damage: &RwLock<Damage>,
+        registrations: &RwLock<Vec<Registration>>,
+        logical_size: PixelSize,
+        scale_factor: f32,
+    ) -> Option<bool> {
+        {
+            // We need to lock the renderer while we modify the node, so that we don't try to render it while doing so
+            // Since this will cause a deadlock
+            let renderer = renderer.write().unwrap();
+
+            renderer.as_ref()?;
+        }
+
+        // We need to acquire a lock on the node once we `view` it, because we remove its state at this point
+        let mut old = node.write().unwrap();
+        let mut new_registrations: Vec<Registration> = vec![];
+        // Only the dirty parts of the graph are viewed again, clean subtrees are moved over
+        old.propagate_dirty();
+        let mut new = Node::recycle(&mut old, &mut new_registrations);
+        new.layout = lay!(size: size!(logical_size.width as f32, logical_size.height as f32));
+        *registrations.write().unwrap() = new_registrations;
+
+        let renderer = renderer.read().unwrap();
+        let caches: crate::renderer::Caches = renderer.as_ref()?.caches();
+
+        new.layout(&old, &mut caches.font.write().unwrap(), scale_factor);
+
+        let do_render = new.render(caches, Some(&mut old), scale_factor);
+
+        let mut new_damage = Damage::default();
+        new.damage(&old, &mut new_damage);
+        {
+            let mut damage = damage.write().unwrap();
+            damage.merge(&new_damage);
+            new.damage_backdrops(&mut damage);
+        }
+
+        *old = new;
+
+        Some(do_render)
+    }
+
     pub fn configure(&mut self, width: u32, height: u32, wayland_handle: RawWaylandHandle) {
         {
             let mut window = self.window.write().unwrap();
@@ -256,6 +279,7 @@ impl<
         let scale_factor = Arc::new(RwLock::new(window.clone().read().unwrap().scale_factor()));
         let frame_dirty = self.frame_dirty.clone();
         let node_dirty = self.node_dirty.clone();
+        let damage = self.damage.clone();
         let registrations = self.registrations.clone();
 
         let draw_thread = Self::draw_thread(
@@ -266,6 +290,7 @@ impl<
             scale_factor.clone(),
             frame_dirty.clone(),
             node_dirty,
+            damage.clone(),
             registrations,
             window.clone(),
         );
@@ -279,6 +304,7 @@ impl<
             node.clone(),
             self.logical_size.clone(),
             frame_dirty.clone(),
+            damage,
             window.clone(),
         );
 
@@ -341,6 +367,7 @@ impl<
             self.node.clone(),
             self.logical_size.clone(),
             self.frame_dirty.clone(),
+            self.damage.clone(),
             self.window.clone(),
         );
 
@@ -354,6 +381,7 @@ impl<
     /// Signal to the draw thread that it may be time to draw a redraw the app.
     /// This performs three actions:
     /// - View, which calls [`view`][Component#method.view] on the root Component and then recursively across the children of the returned Node, thus recreating the Node graph. This does a number of sub tasks:
+    ///   - Only Components whose state changed since the last draw are viewed again, along with the Nodes they create. Other subtrees are moved over from the old graph as they are, and restore their previous layout unless their bounds changed.
     ///   - State is transferred from the old graph to the new one, where possible. Some new Nodes will not have existed in the old graph.
     ///   - For net new Nodes (not present in the old graph), [`init`][Component#method.init] is called, and then a hash of input values is computed with [`props_hash`][Component#method.props_hash].
     ///   - For Nodes that existed in the old graph, [`props_hash`][Component#method.props_hash] is called on the new Component. If the new hash is not equal to the old one, then [`new_props`][Component#method.new_props] is called.
@@ -377,6 +405,7 @@ impl<
         node: Arc<RwLock<Node>>,
         logical_size: Arc<RwLock<PixelSize>>,
         frame_dirty: Arc<RwLock<bool>>,
+        damage: Arc<RwLock<Damage>>,
         window: Arc<RwLock<W>>,
     ) -> JoinHandle<()> {
         let size = logical_size.read().unwrap();
@@ -386,24 +415,51 @@ impl<
         thread::spawn(move || {
             // let scale_factor = window.scale_factor();
             // let size = window.logical_size();
-            let raw_window_handle = raw_wayland_handle.raw_window_handle();
-            let raw_display_handle = raw_wayland_handle.raw_display_handle();
-
-            let (gl_display, gl_surface, gl_context) =
-                gl::init_gl(raw_display_handle, raw_window_handle, (width, height));
-            let mut gl_canvas =
-                gl::init_gl_canvas(&gl_display, (width, height), *scale_factor.read().unwrap());
-
-            // load assets
-            let images = canvas::load_assets_to_canvas(&mut gl_canvas, assets);
-
-            let mut gl_context = GlCanvasContext {
-                gl_canvas,
-                gl_context,
-                gl_surface,
-                images,
+            let scale_factor = *scale_factor.read().unwrap();
+            let renderer_type = window.read().unwrap().renderer_type();
+
+            let physical_size = (
+                (width as f32 * scale_factor).round() as u32,
+                (height as f32 * scale_factor).round() as u32,
+            );
+
+            let mut context: Box<dyn Any> = match renderer_type {
+                RendererType::Canvas => match GlCanvasContext::new(
+                    raw_wayland_handle.raw_display_handle(),
+                    raw_wayland_handle.raw_window_handle(),
+                    (width, height),
+                    scale_factor,
+                    assets.clone(),
+                ) {
+                    Ok(context) => Box::new(context),
+                    Err(e) => {
+                        println!(
+                            "Failed to initialize EGL, falling back to software rendering: {:?}",
+                            e
+                        );
+                        window
+                            .write()
+                            .unwrap()
+                            .set_renderer_type(RendererType::Software);
+                        *renderer.write().unwrap() = Some(ActiveRenderer::new(window.clone()));
+
+                        Box::new(SoftwareCanvasContext::new(
+                            physical_size.0,
+                            physical_size.1,
+                            assets,
+                        ))
+                    }
+                },
+                RendererType::Software => Box::new(SoftwareCanvasContext::new(
+                    physical_size.0,
+                    physical_size.1,
+                    assets,
+                )),
             };
 
+            // Nothing has been drawn into a new context yet
+            *damage.write().unwrap() = Damage::full();
+
             for msg in receiver.iter() {
                 // exit thread
                 if msg == RenderMessage::Exit {
@@ -419,12 +475,19 @@ impl<
                         return;
                     }
 
+                    let frame_damage = std::mem::take(&mut *damage.write().unwrap());
                     renderer.as_mut().unwrap().render(
                         &node,
                         PixelSize { width, height },
-                        &mut gl_context,
+                        &frame_damage,
+                        context.as_mut(),
                     );
 
+                    // hand software frames over to the window to be presented
+                    if let Some(context) = context.downcast_ref::<SoftwareCanvasContext>() {
+                        Self::present(&*window.read().unwrap(), &context.canvas, &frame_damage);
+                    }
+
                     *frame_dirty.write().unwrap() = false;
 
                     // request next frame
@@ -451,6 +514,108 @@ impl<
             .unwrap();
     }
 
+    /// Set up the renderer without spawning the draw and render threads, for windows that are never shown,
+    /// like a [`HeadlessWindow`][crate::headless::HeadlessWindow]. Drawing and rendering then only happen
+    /// when [`draw_sync`][UI#method.draw_sync] and [`render_sync`][UI#method.render_sync] are called.
+    pub fn configure_sync(&mut self, width: u32, height: u32) {
+        {
+            let mut window = self.window.write().unwrap();
+            window.set_size(width, height);
+
+            let scale_factor = window.scale_factor();
+            *self.physical_size.write().unwrap() = window.physical_size();
+            *self.logical_size.write().unwrap() = window.logical_size();
+            *self.scale_factor.write().unwrap() = scale_factor;
+            self.event_cache.scale_factor = scale_factor;
+        }
+
+        *self.renderer.write().unwrap() = Some(ActiveRenderer::new(self.window.clone()));
+        *self.damage.write().unwrap() = Damage::full();
+
+        // mark node dirty, so that we can redraw
+        *self.node_dirty.write().unwrap() = true;
+    }
+
+    /// Perform a [`draw`][UI#method.draw] on the calling thread, if the node is dirty.
+    /// Returns whether any Node was re-rendered.
+    pub fn draw_sync(&mut self) -> bool {
+        if !*self.node_dirty.read().unwrap() {
+            return false;
+        }
+        *self.node_dirty.write().unwrap() = false;
+
+        let logical_size = self.window.read().unwrap().logical_size();
+        let scale_factor = *self.scale_factor.read().unwrap();
+
+        match Self::draw_node(
+            &self.renderer,
+            &self.node,
+            &self.damage,
+            &self.registrations,
+            logical_size,
+            scale_factor,
+        ) {
+            Some(do_render) => {
+                *self.frame_dirty.write().unwrap() = true;
+                do_render
+            }
+            None => {
+                *self.node_dirty.write().unwrap() = true;
+                false
+            }
+        }
+    }
+
+    /// Render the current Node graph on the calling thread into `context`, which must be the context
+    /// of the window's renderer, e.g. a [`SoftwareCanvasContext`] for [`RendererType::Software`].
+    /// Only the regions damaged since the last render are repainted. Software frames are then
+    /// [`present`][Window#method.present]ed to the window.
+    pub fn render_sync(&mut self, context: &mut dyn Any) {
+        let logical_size = *self.logical_size.read().unwrap();
+        let frame_damage = std::mem::take(&mut *self.damage.write().unwrap());
+        if let Some(renderer) = self.renderer.write().unwrap().as_mut() {
+            renderer.render(&self.node_ref(), logical_size, &frame_damage, context);
+        }
+
+        if let Some(context) = context.downcast_ref::<SoftwareCanvasContext>() {
+            Self::present(
+                &*self.window.read().unwrap(),
+                &context.canvas,
+                &frame_damage,
+            );
+        }
+
+        *self.frame_dirty.write().unwrap() = false;
+    }
+
+    /// Repaint the whole frame on the next render, e.g. after the render context was recreated.
+    pub fn damage_all(&self) {
+        *self.damage.write().unwrap() = Damage::full();
+    }
+
+    /// Hand a software frame over to `window`, along with the parts of it that changed.
+    fn present(window: &W, canvas: &SoftwareCanvas, damage: &Damage) {
+        let frame = AABB::new(
+            Pos::default(),
+            Scale::new(canvas.width() as f32, canvas.height() as f32),
+        );
+        let damage = damage.clip(frame);
+        if damage.is_empty() {
+            return;
+        }
+        window.present(
+            canvas.width(),
+            canvas.height(),
+            canvas.data(),
+            damage.rects(),
+        );
+    }
+
+    /// The id of the Node that currently has focus. The root Node has focus when no other Node does.
+    pub fn focus(&self) -> u64 {
+        self.event_cache.focus
+    }
+
     fn blur(&mut self) {
         let mut blur_event = Event::new(event::Blur, &self.event_cache);
         blur_event.target = Some(self.event_cache.focus);
@@ -490,6 +655,12 @@ impl<
         }
     }
 
+    fn handle_clipboard<T: EventInput>(&mut self, event: &mut Event<T>) {
+        if let Some(data) = event.clipboard.take() {
+            self.window.read().unwrap().put_on_clipboard(&data);
+        }
+    }
+
     fn handle_event<T: EventInput, F>(
         &mut self,
         handler: F,
@@ -501,6 +672,7 @@ impl<
         event.target = target;
         event.registrations = self.registrations.read().unwrap().clone();
         handler(&mut self.node_mut(), event);
+        self.handle_clipboard(event);
         self.handle_focus_or_blur(event);
         self.handle_dirty_event(event);
     }
@@ -515,6 +687,7 @@ impl<
     {
         event.target = target;
         handler(&mut self.node_mut(), event);
+        self.handle_clipboard(event);
         self.handle_dirty_event(event);
     }
 
@@ -1001,7 +1174,10 @@ impl<
 
     /// Calls [`Component#update`][Component#method.update] with `msg` on the root Node of the application. This will always trigger a redraw.
     pub fn update(&mut self, msg: Message) {
-        self.node_mut().component.update(msg);
+        let mut node = self.node_mut();
+        node.component.update(msg);
+        node.dirty = true;
+        drop(node);
         *self.node_dirty.write().unwrap() = true;
     }
 
@@ -1019,6 +1195,7 @@ impl<
                     f(s);
                 }
                 node.component.replace_state(state);
+                node.dirty = true;
                 dirty = true;
             }
         }
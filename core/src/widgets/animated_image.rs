@@ -0,0 +1,444 @@
+use std::hash::Hash;
+use std::io::Cursor;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{AnimationDecoder, DynamicImage, ImageFormat, ImageResult};
+use mctk_macros::{component, state_component_impl};
+
+use crate::assets;
+use crate::component::{Component, ComponentHasher, RenderContext};
+use crate::event::{self, Event};
+use crate::renderables::image::InstanceBuilder as ImageInstanceBuilder;
+use crate::renderables::{self, Renderable};
+use crate::style::Styled;
+use crate::types::*;
+
+/// Frames that ask to be shown for this long or less are shown for [`DEFAULT_DELAY`] instead, as browsers do.
+const MIN_DELAY: Duration = Duration::from_millis(10);
+const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+
+/// One frame of an [`Animation`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationFrame {
+    pub image: Arc<DynamicImage>,
+    /// How long the frame is shown for.
+    pub delay: Duration,
+}
+
+/// The decoded frames of an animated GIF, PNG (APNG) or WebP image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    pub frames: Vec<AnimationFrame>,
+    /// How many times the animation plays, or `None` if it loops forever.
+    pub plays: Option<u32>,
+}
+
+impl Animation {
+    /// Decode every frame of the image file at `path`.
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> ImageResult<Self> {
+        Self::decode(&std::fs::read(path)?)
+    }
+
+    /// Decode every frame of an encoded image. Still images are decoded into a single frame.
+    pub fn decode(bytes: &[u8]) -> ImageResult<Self> {
+        let format = image::guess_format(bytes)?;
+        let frames = match format {
+            ImageFormat::Gif => GifDecoder::new(Cursor::new(bytes))?.into_frames(),
+            ImageFormat::Png => {
+                let decoder = PngDecoder::new(Cursor::new(bytes))?;
+                if !decoder.is_apng() {
+                    return Ok(Self::still(image::load_from_memory(bytes)?));
+                }
+                decoder.apng().into_frames()
+            }
+            ImageFormat::WebP => WebPDecoder::new(Cursor::new(bytes))?.into_frames(),
+            _ => return Ok(Self::still(image::load_from_memory(bytes)?)),
+        };
+
+        let frames = frames
+            .collect_frames()?
+            .into_iter()
+            .map(|frame| {
+                let (numer, denom) = frame.delay().numer_denom_ms();
+                let delay = Duration::from_secs_f64(numer as f64 / denom.max(1) as f64 / 1000.);
+                AnimationFrame {
+                    image: Arc::new(DynamicImage::ImageRgba8(frame.into_buffer())),
+                    delay: if delay <= MIN_DELAY {
+                        DEFAULT_DELAY
+                    } else {
+                        delay
+                    },
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            frames,
+            plays: plays(format, bytes),
+        })
+    }
+
+    fn still(image: DynamicImage) -> Self {
+        Self {
+            frames: vec![AnimationFrame {
+                image: Arc::new(image),
+                delay: Duration::ZERO,
+            }],
+            plays: Some(1),
+        }
+    }
+
+    /// How long it takes to play every frame once.
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(|f| f.delay).sum()
+    }
+
+    /// Whether every play of the animation is over after playing for `position`.
+    pub fn is_finished(&self, position: Duration) -> bool {
+        self.plays
+            .map_or(false, |plays| position >= self.duration() * plays)
+    }
+
+    /// The index of the frame that is shown after playing for `position`. Finished animations stay on their last frame.
+    pub fn frame_at(&self, position: Duration) -> Option<usize> {
+        let last = self.frames.len().checked_sub(1)?;
+        let duration = self.duration();
+        if duration.is_zero() || self.is_finished(position) {
+            return Some(last);
+        }
+
+        let position = Duration::from_nanos((position.as_nanos() % duration.as_nanos()) as u64);
+        let mut end = Duration::ZERO;
+        for (i, frame) in self.frames.iter().enumerate() {
+            end += frame.delay;
+            if position < end {
+                return Some(i);
+            }
+        }
+        Some(last)
+    }
+}
+
+/// Read how many times an animation plays from its GIF Netscape extension, APNG `acTL` chunk or WebP `ANIM` chunk.
+fn plays(format: ImageFormat, bytes: &[u8]) -> Option<u32> {
+    let find = |tag: &[u8]| bytes.windows(tag.len()).position(|w| w == tag);
+    let count = match format {
+        // Counts the loops after the first play
+        ImageFormat::Gif => match find(b"NETSCAPE2.0") {
+            Some(i) => bytes
+                .get(i + 13..i + 15)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]) as u32)
+                .map(|loops| if loops == 0 { 0 } else { loops + 1 })?,
+            None => 1,
+        },
+        ImageFormat::Png => find(b"acTL")
+            .and_then(|i| bytes.get(i + 8..i + 12))
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))?,
+        ImageFormat::WebP => match find(b"ANIM") {
+            Some(i) => bytes
+                .get(i + 12..i + 14)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]) as u32)?,
+            None => 1,
+        },
+        _ => 1,
+    };
+    (count > 0).then_some(count)
+}
+
+#[derive(Debug, Default)]
+struct AnimatedImageState {
+    /// The `name` of the animation that was loaded.
+    name: String,
+    animation: Option<Arc<Animation>>,
+    /// Set by the decoder thread once the file at `path` has been decoded, or has failed to.
+    decoded: Option<Arc<OnceLock<Option<Arc<Animation>>>>>,
+    frame: usize,
+    /// How long the animation had played for when it was last paused or seeked.
+    position: Duration,
+    /// When the animation started playing from `position`, if it is playing.
+    since: Option<Instant>,
+    /// The last `seek` that was applied.
+    seek: Option<Duration>,
+}
+
+/// Plays an animated GIF, PNG (APNG) or WebP image, advancing its frames on every [`Tick`][event::Tick].
+///
+/// `name` identifies the animation: it is only decoded again once `name` changes. Its frames are loaded into the renderer as `"{name}#{index}"`.
+/// Files are decoded on a background thread, and nothing is drawn until they are.
+#[component(State = "AnimatedImageState", Styled = "Image", Internal)]
+pub struct AnimatedImage {
+    pub name: String,
+    pub path: Option<String>,
+    pub animation: Option<Arc<Animation>>,
+    pub playing: bool,
+    pub seek: Option<Duration>,
+}
+
+impl std::fmt::Debug for AnimatedImage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("AnimatedImage")
+            .field("name", &self.name)
+            .field("path", &self.path)
+            .field("playing", &self.playing)
+            .field("seek", &self.seek)
+            .finish()
+    }
+}
+
+impl AnimatedImage {
+    /// Play the image file at `path`.
+    pub fn new<S: Into<String>>(name: S, path: S) -> Self {
+        Self {
+            name: name.into(),
+            path: Some(path.into()),
+            animation: None,
+            playing: true,
+            seek: None,
+            state: Some(AnimatedImageState::default()),
+            dirty: false,
+            class: Default::default(),
+            style_overrides: Default::default(),
+        }
+    }
+
+    /// Play an [`Animation`] that has already been decoded.
+    pub fn from_animation<S: Into<String>>(name: S, animation: Arc<Animation>) -> Self {
+        Self {
+            path: None,
+            animation: Some(animation),
+            ..Self::new(name.into(), String::new())
+        }
+    }
+
+    /// Pause the animation when `false`. It resumes from the same frame.
+    pub fn playing(mut self, playing: bool) -> Self {
+        self.playing = playing;
+        self
+    }
+
+    /// Jump to the frame that is shown after playing for `position`, counting every loop. This happens whenever the value changes.
+    pub fn seek(mut self, position: Duration) -> Self {
+        self.seek = Some(position);
+        self
+    }
+
+    /// Start loading the animation if `name` changed, and apply `playing` and `seek`.
+    fn sync(&mut self) {
+        let loading = self.state_ref().decoded.is_some();
+        if (self.state_ref().animation.is_none() && !loading) || self.state_ref().name != self.name
+        {
+            let decoded = match (&self.animation, &self.path) {
+                (None, Some(path)) => {
+                    let decoded = Arc::new(OnceLock::new());
+                    let (path, result) = (path.clone(), decoded.clone());
+                    assets::decode_in_background(move || {
+                        let animation = match Animation::open(&path) {
+                            Ok(animation) => Some(Arc::new(animation)),
+                            Err(e) => {
+                                println!("Error while opening image {:?} error: {:?}", path, e);
+                                None
+                            }
+                        };
+                        let _ = result.set(animation);
+                    });
+                    Some(decoded)
+                }
+                _ => None,
+            };
+            *self.state_mut() = AnimatedImageState {
+                name: self.name.clone(),
+                animation: self.animation.clone(),
+                decoded,
+                ..Default::default()
+            };
+        }
+
+        let seek = self.seek;
+        let playing = self.playing;
+        let state = self.state_mut();
+        if seek != state.seek {
+            state.seek = seek;
+            state.position = seek.unwrap_or_default();
+            state.since = state.since.map(|_| Instant::now());
+        }
+        if !playing {
+            if let Some(since) = state.since.take() {
+                state.position += since.elapsed();
+            }
+        }
+        if let Some(animation) = &state.animation {
+            state.frame = animation.frame_at(state.position).unwrap_or(0);
+        }
+    }
+}
+
+#[state_component_impl(AnimatedImageState)]
+impl Component for AnimatedImage {
+    fn init(&mut self) {
+        self.sync();
+    }
+
+    fn new_props(&mut self) {
+        self.sync();
+    }
+
+    fn props_hash(&self, hasher: &mut ComponentHasher) {
+        self.name.hash(hasher);
+        self.path.hash(hasher);
+        self.playing.hash(hasher);
+        self.seek.hash(hasher);
+    }
+
+    fn render_hash(&self, hasher: &mut ComponentHasher) {
+        self.name.hash(hasher);
+        self.state_ref().frame.hash(hasher);
+    }
+
+    fn on_tick(&mut self, _event: &mut Event<event::Tick>) {
+        if let Some(decoded) = self.state_ref().decoded.clone() {
+            let Some(animation) = decoded.get() else {
+                return;
+            };
+            let state = self.state_mut();
+            state.decoded = None;
+            state.animation = animation.clone();
+            if let Some(animation) = &state.animation {
+                state.frame = animation.frame_at(state.position).unwrap_or(0);
+            }
+        }
+
+        let state = self.state_ref();
+        let Some(animation) = state.animation.clone() else {
+            return;
+        };
+        if !self.playing || animation.frames.len() < 2 {
+            return;
+        }
+
+        match state.since {
+            None => self.state_mut().since = Some(Instant::now()),
+            Some(since) => {
+                // Only touch the state when the frame changes, so that the Node isn't redrawn on every tick
+                let frame = animation
+                    .frame_at(state.position + since.elapsed())
+                    .unwrap_or(0);
+                if frame != state.frame {
+                    self.state_mut().frame = frame;
+                }
+            }
+        }
+    }
+
+    fn render(&mut self, context: RenderContext) -> Option<Vec<Renderable>> {
+        let index = self.state_ref().frame;
+        let frame = self
+            .state_ref()
+            .animation
+            .as_ref()?
+            .frames
+            .get(index)?
+            .clone();
+        let AABB { pos, .. } = context.aabb;
+        let radius = self.style_val("radius").unwrap().f32();
+
+        let instance = ImageInstanceBuilder::default()
+            .pos(pos)
+            .scale(Scale {
+                width: context.aabb.width(),
+                height: context.aabb.height(),
+            })
+            .name(format!("{}#{}", self.name, index))
+            .radius(radius)
+            .data(Some(frame.image))
+            .build()
+            .unwrap();
+
+        Some(vec![Renderable::Image(
+            renderables::Image::from_instance_data(instance),
+        )])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::TestHarness;
+    use crate::{lay, node, size};
+
+    #[test]
+    fn test_animated_image() {
+        use image::codecs::gif::{GifEncoder, Repeat};
+        use image::{Delay, Frame, Rgba, RgbaImage};
+
+        let mut gif = vec![];
+        {
+            let mut encoder = GifEncoder::new(&mut gif);
+            encoder.set_repeat(Repeat::Finite(1)).unwrap();
+            let frames = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]].map(|c| {
+                Frame::from_parts(
+                    RgbaImage::from_pixel(4, 4, Rgba(c)),
+                    0,
+                    0,
+                    Delay::from_numer_denom_ms(100, 1),
+                )
+            });
+            encoder.encode_frames(frames).unwrap();
+        }
+
+        let animation = Animation::decode(&gif).unwrap();
+        assert_eq!(animation.frames.len(), 3);
+        assert_eq!(animation.frames[1].delay, Duration::from_millis(100));
+        // Played once, then looped once more
+        assert_eq!(animation.plays, Some(2));
+        let ms = Duration::from_millis;
+        assert_eq!(animation.frame_at(ms(150)), Some(1));
+        assert_eq!(animation.frame_at(ms(350)), Some(0));
+        assert!(animation.is_finished(ms(600)));
+        assert_eq!(animation.frame_at(ms(650)), Some(2));
+
+        let animation = Arc::new(animation);
+        let mut harness = TestHarness::single(move || {
+            node!(
+                AnimatedImage::from_animation("spinner", animation.clone())
+                    .playing(false)
+                    .seek(ms(250)),
+                lay![size: [100., 100.]]
+            )
+        });
+        let is_blue = |pixel: resvg::tiny_skia::PremultipliedColorU8| {
+            pixel.blue() > 200 && pixel.red() < 50 && pixel.green() < 50
+        };
+        assert!(is_blue(harness.render().pixmap().pixel(50, 50).unwrap()));
+        // A paused animation stays on its frame
+        harness.tick();
+        assert!(is_blue(harness.render().pixmap().pixel(50, 50).unwrap()));
+
+        // Files are decoded off the UI thread, and shown on the first tick after that
+        let path = std::env::temp_dir().join(format!(
+            "mctk_test_animated_image_{}.gif",
+            std::process::id()
+        ));
+        std::fs::write(&path, &gif).unwrap();
+        let path = path.to_string_lossy().to_string();
+        let mut harness = TestHarness::single(move || {
+            node!(
+                AnimatedImage::new("spinner_file".to_string(), path.clone())
+                    .playing(false)
+                    .seek(ms(250)),
+                lay![size: [100., 100.]]
+            )
+        });
+        for _ in 0..500 {
+            harness.tick();
+            if is_blue(harness.render().pixmap().pixel(50, 50).unwrap()) {
+                return;
+            }
+            std::thread::sleep(ms(5));
+        }
+        panic!("The animation was never shown");
+    }
+}
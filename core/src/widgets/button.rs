@@ -3,6 +3,7 @@ use std::time::Instant;
 // use super::ToolTip;
 use crate::component::{Component, Message};
 use crate::font_cache::TextSegment;
+use crate::renderables::rect::Gradient;
 use crate::style::Styled;
 use crate::{event, lay, rect};
 use crate::{node, node::Node};
@@ -86,6 +87,11 @@ impl Component for Button {
         let background_color: Color = self.style_val("background_color").into();
         let border_color: Color = self.style_val("border_color").into();
         let border_width: f32 = self.style_val("border_width").unwrap().f32();
+        // Optional, and only shown while the Button is neither hovered nor pressed
+        let background_gradient: Option<Gradient> =
+            self.style_val("background_gradient").map(Into::into);
+        let border_gradient: Option<Gradient> = self.style_val("border_gradient").map(Into::into);
+        let idle = !self.state_ref().pressed && !self.state_ref().hover;
 
         let mut base = node!(
             super::RoundedRect {
@@ -96,7 +102,9 @@ impl Component for Button {
                 } else {
                     background_color
                 },
+                gradient: background_gradient.filter(|_| idle),
                 border_color,
+                border_gradient,
                 border_width: (border_width, border_width, border_width, border_width),
                 radius: (radius, radius, radius, radius),
                 ..Default::default()
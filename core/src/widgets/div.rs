@@ -4,8 +4,8 @@ use std::ops::Neg;
 use crate::component::{Component, ComponentHasher, RenderContext};
 use crate::event;
 use crate::layout::*;
-use crate::renderables::rect::InstanceBuilder;
-use crate::renderables::{Rect, Renderable};
+use crate::renderables::rect::{BoxShadow, Gradient, InstanceBuilder};
+use crate::renderables::{backdrop, Backdrop, Rect, Renderable};
 use crate::style::{HorizontalPosition, StyleVal, Styled, VerticalPosition};
 use crate::types::*;
 
@@ -30,9 +30,15 @@ pub struct DivState {
 #[derive(Debug, Default)]
 pub struct Div {
     pub background: Option<Color>,
+    pub gradient: Option<Gradient>,
     pub border_color: Option<Color>,
+    pub border_gradient: Option<Gradient>,
     pub border_width: Option<f32>,
     pub radius: Option<(f32, f32, f32, f32)>,
+    pub shadows: Vec<BoxShadow>,
+    pub backdrop_blur: Option<f32>,
+    pub clip: Option<Clip>,
+    pub cache_layer: bool,
 }
 
 impl Div {
@@ -45,6 +51,13 @@ impl Div {
         self
     }
 
+    /// Fill the background with a gradient, over any [`bg`][Self::bg] color.
+    /// The gradient is given in fractions of the Div, see [`Gradient::fit`]. Also set by the `background_gradient` style.
+    pub fn bg_gradient(mut self, gradient: Gradient) -> Self {
+        self.gradient = Some(gradient);
+        self
+    }
+
     pub fn border<C: Into<Color>>(
         mut self,
         color: C,
@@ -57,6 +70,57 @@ impl Div {
         self
     }
 
+    /// Draw the border with a gradient, in place of its color. Takes effect along with [`border`][Self::border].
+    /// The gradient is given in fractions of the Div, see [`Gradient::fit`]. Also set by the `border_gradient` style.
+    pub fn border_gradient(mut self, gradient: Gradient) -> Self {
+        self.border_gradient = Some(gradient);
+        self
+    }
+
+    /// Add a shadow, in logical pixels. Can be called more than once. The `shadow` style also adds one, e.g. through the `shadow-md` class.
+    pub fn shadow(mut self, shadow: BoxShadow) -> Self {
+        self.shadows.push(shadow);
+        self
+    }
+
+    fn gradients(&self) -> (Option<Gradient>, Option<Gradient>) {
+        (
+            self.gradient
+                .clone()
+                .or_else(|| self.style_val("background_gradient").map(Into::into)),
+            self.border_gradient
+                .clone()
+                .or_else(|| self.style_val("border_gradient").map(Into::into)),
+        )
+    }
+
+    fn all_shadows(&self) -> Vec<BoxShadow> {
+        let mut shadows = self.shadows.clone();
+        if let Some(shadow) = self.style_val("shadow") {
+            shadows.push(shadow.into());
+        }
+        shadows
+    }
+
+    /// Blur whatever is drawn beneath this Div, by a standard deviation of `blur` logical pixels.
+    /// A translucent [`bg`][Self::bg] tints the blur, for a frosted glass look.
+    pub fn backdrop_blur(mut self, blur: f32) -> Self {
+        self.backdrop_blur = Some(blur);
+        self
+    }
+
+    /// Clip the children of this Div to `clip`, e.g. `Clip::rounded(8.)` to keep them within rounded corners. See [`Component::clip`].
+    pub fn clip(mut self, clip: Clip) -> Self {
+        self.clip = Some(clip);
+        self
+    }
+
+    /// Render this Div and its children into a cached offscreen layer. See [`Component::cache_layer`].
+    pub fn cache_layer(mut self, cache_layer: bool) -> Self {
+        self.cache_layer = cache_layer;
+        self
+    }
+
     pub fn scroll_x(mut self) -> Self {
         self = self.style("x", true);
         self.state = Some(DivState::default());
@@ -172,6 +236,10 @@ impl Component for Div {
         if let Some(color) = self.background {
             color.hash(hasher);
         }
+        self.gradients().hash(hasher);
+        self.all_shadows().hash(hasher);
+        self.backdrop_blur.map(f32::to_bits).hash(hasher);
+        self.clip.hash(hasher);
         // Maybe TODO: Should hash scroll_descriptor
     }
 
@@ -305,6 +373,14 @@ impl Component for Div {
         );
     }
 
+    fn cache_layer(&self) -> bool {
+        self.cache_layer
+    }
+
+    fn clip(&self) -> Option<Clip> {
+        self.clip.clone()
+    }
+
     fn scroll_position(&self) -> Option<ScrollPosition> {
         if self.scrollable() {
             let p = self.state_ref().scroll_position;
@@ -355,7 +431,25 @@ impl Component for Div {
             .border_width
             .map_or(0.0, |x| (x * context.scale_factor.floor()).round());
 
-        if let Some(bg) = self.background {
+        if let Some(blur) = self.backdrop_blur {
+            let instance = backdrop::InstanceBuilder::default()
+                .pos(context.aabb.pos)
+                .scale(context.aabb.size())
+                .radius(self.radius.unwrap_or_default())
+                .blur(blur * context.scale_factor)
+                .build()
+                .unwrap();
+            rs.push(Renderable::Backdrop(Backdrop::from_instance_data(instance)));
+        }
+
+        let shadows: Vec<BoxShadow> = self
+            .all_shadows()
+            .into_iter()
+            .map(|s| s.scale(context.scale_factor))
+            .collect();
+        let (gradient, border_gradient) = self.gradients();
+        if self.background.is_some() || gradient.is_some() || !shadows.is_empty() {
+            let bg = self.background.unwrap_or(Color::TRANSPARENT);
             // println!("Background color {:?} {:?}", bg, context.scissor);
             let mut rect_instance = InstanceBuilder::default()
                 .pos(Pos {
@@ -365,6 +459,8 @@ impl Component for Div {
                 })
                 .scale(context.aabb.size())
                 .color(bg)
+                .gradient(gradient.map(|g| g.fit(context.aabb)))
+                .shadows(shadows)
                 .build()
                 .unwrap();
             if let Some(radius) = self.radius {
@@ -381,6 +477,7 @@ impl Component for Div {
                 .pos(context.aabb.pos)
                 .scale(context.aabb.size())
                 .border_color(color)
+                .border_gradient(border_gradient.map(|g| g.fit(context.aabb)))
                 .border_size((width, width, width, width))
                 .radius(radius)
                 .build()
@@ -543,3 +640,90 @@ impl Component for Div {
         Some(rs)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::TestHarness;
+    use crate::{lay, node, size};
+
+    #[test]
+    fn test_box_shadow() {
+        let mut harness = TestHarness::single(|| {
+            node!(
+                Div::new()
+                    .bg(Color::WHITE)
+                    .shadow(BoxShadow::new(Color::BLACK, (0., 20.), 0., 0.))
+                    .shadow(BoxShadow::new(Color::BLACK, (10., 0.), 0., 0.).inset()),
+                lay![size: [50., 50.]]
+            )
+        });
+        let pixmap = harness.render().pixmap();
+        // The outer shadow is drawn below the Div
+        assert_eq!(pixmap.pixel(25, 60).unwrap().alpha(), 255);
+        assert_eq!(pixmap.pixel(25, 60).unwrap().red(), 0);
+        assert_eq!(pixmap.pixel(25, 80).unwrap().alpha(), 0);
+        // The inset shadow darkens the left edge of the Div, over its background
+        assert_eq!(pixmap.pixel(5, 25).unwrap().red(), 0);
+        assert_eq!(pixmap.pixel(25, 25).unwrap().red(), 255);
+        // The area that the outer shadow covers is part of the Node
+        assert_eq!(harness.root().children()[0].children()[0].overflow, 20.);
+    }
+
+    #[test]
+    fn test_backdrop_blur() {
+        let mut harness = TestHarness::single(|| {
+            node!(
+                Div::new(),
+                lay![size: [100., 100.], direction: Direction::Row]
+            )
+            .push(node!(
+                Div::new().bg(Color::BLACK),
+                lay![size: [50., 100.]],
+                0
+            ))
+            .push(node!(
+                Div::new().bg(Color::WHITE),
+                lay![size: [50., 100.]],
+                1
+            ))
+            .push(node!(
+                Div::new().backdrop_blur(4.),
+                lay![
+                    size: [50., 50.],
+                    position_type: Absolute,
+                    position: [25., Auto, Auto, 25.],
+                ],
+                2
+            ))
+        });
+        let pixmap = harness.render().pixmap();
+        // The edge between black and white is blurred beneath the Div, and only there
+        let red = |x, y| pixmap.pixel(x, y).unwrap().red();
+        assert!((40..=215).contains(&red(50, 50)));
+        assert!(red(49, 50) < red(50, 50));
+        assert_eq!(red(49, 10), 0);
+        assert_eq!(red(50, 10), 255);
+        assert_eq!(pixmap.pixel(50, 50).unwrap().alpha(), 255);
+    }
+
+    #[test]
+    fn test_gradient() {
+        let mut harness = TestHarness::single(|| {
+            node!(
+                Div::new().bg_gradient(Gradient::linear(
+                    90.,
+                    vec![(0., Color::BLACK), (1., Color::WHITE)]
+                )),
+                lay![size: [100., 20.]]
+            )
+        });
+        let pixmap = harness.render().pixmap();
+        // From black on the left to white on the right, across the whole Div
+        let red = |x| pixmap.pixel(x, 10).unwrap().red();
+        assert!(red(0) < 10);
+        assert!((110..=145).contains(&red(50)));
+        assert!(red(99) > 245);
+        assert_eq!(pixmap.pixel(50, 30).unwrap().alpha(), 0);
+    }
+}
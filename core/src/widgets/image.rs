@@ -1,20 +1,35 @@
 use std::hash::Hash;
 
-use mctk_macros::component;
+use mctk_macros::{component, state_component_impl};
 
+use crate::assets::{self, AssetState};
 use crate::component::{Component, ComponentHasher, RenderContext};
+use crate::event::{self, Event};
 
-use crate::renderables::image::InstanceBuilder as ImageInstanceBuilder;
+use crate::renderables::image::{ImageFit, InstanceBuilder as ImageInstanceBuilder, NinePatch};
 use crate::renderables::types::{Point, Size};
 use crate::renderables::{self, Rect, Renderable};
-use crate::style::{self, Styled};
+use crate::style::{self, BorderWidth, HorizontalPosition, Styled, VerticalPosition};
 use crate::types::*;
 
-#[component(Styled)]
+#[derive(Debug, Default)]
+struct ImageState {
+    /// The state of the image in the [asset registry][crate::assets], if it is registered there.
+    asset: Option<AssetState>,
+}
+
+/// Draws the asset called `name`: either one that the window was created with, or one from the [asset registry][crate::assets].
+/// Registered images are drawn once they have loaded.
+///
+/// How the image fills the widget is set by the `fit`, `h_align`, `v_align`, `flip_x`, `flip_y`, `tint` and `nine_patch` styles, which have builder methods of their own.
+#[component(State = "ImageState", Styled)]
 #[derive(Debug)]
 pub struct Image {
     pub name: String,
+    /// A file to [register][crate::assets::register] the image from, if it isn't loaded yet. It is decoded on a background thread.
     pub dynamic_load_from: Option<String>,
+    /// The part of the image to draw, in image pixels.
+    pub crop: Option<AABB>,
 }
 
 impl Default for Image {
@@ -22,6 +37,9 @@ impl Default for Image {
         Self {
             name: "".to_string(),
             dynamic_load_from: None,
+            crop: None,
+            state: Some(ImageState::default()),
+            dirty: false,
             class: Default::default(),
             style_overrides: Default::default(),
         }
@@ -33,6 +51,9 @@ impl Image {
         Self {
             name: name.into(),
             dynamic_load_from: None,
+            crop: None,
+            state: Some(ImageState::default()),
+            dirty: false,
             class: Default::default(),
             style_overrides: Default::default(),
         }
@@ -42,11 +63,127 @@ impl Image {
         self.dynamic_load_from = v;
         self
     }
+
+    /// Only draw the part of the image within `rect`, in image pixels.
+    pub fn crop(mut self, rect: AABB) -> Self {
+        self.crop = Some(rect);
+        self
+    }
+
+    pub fn fit(self, fit: ImageFit) -> Self {
+        self.style("fit", fit)
+    }
+
+    /// Where the image is placed when it doesn't fill the widget, or which part of it is shown when it overflows.
+    pub fn align(self, h: HorizontalPosition, v: VerticalPosition) -> Self {
+        self.style("h_align", h).style("v_align", v)
+    }
+
+    pub fn flip_x(self, flip: bool) -> Self {
+        self.style("flip_x", flip)
+    }
+
+    pub fn flip_y(self, flip: bool) -> Self {
+        self.style("flip_y", flip)
+    }
+
+    /// Multiply the colors of the image by `color`, e.g. to recolor a white icon.
+    pub fn tint(self, color: Color) -> Self {
+        self.style("tint", color)
+    }
+
+    /// Stretch only the middle of the image, keeping borders of the given widths, in image pixels, at their size.
+    /// The `fit` style is ignored. Useful for skinnable backgrounds, e.g. of buttons.
+    pub fn nine_patch(self, top: f32, right: f32, bottom: f32, left: f32) -> Self {
+        self.style(
+            "nine_patch",
+            BorderWidth {
+                top,
+                left,
+                bottom,
+                right,
+            },
+        )
+    }
+}
+
+impl Image {
+    /// Register `dynamic_load_from` when nothing was registered under `name` yet, and watch the registered image.
+    fn load(&mut self) {
+        if let Some(path) = &self.dynamic_load_from {
+            if assets::state(&self.name).is_none() {
+                assets::register(self.name.clone(), AssetParams::new(path.clone()));
+            }
+        }
+        self.state_mut().asset = assets::state(&self.name);
+    }
+
+    fn align_fractions(&self) -> (f32, f32) {
+        let h = match self.style_val("h_align").unwrap().horizontal_position() {
+            HorizontalPosition::Left => 0.,
+            HorizontalPosition::Center => 0.5,
+            HorizontalPosition::Right => 1.,
+        };
+        let v = match self.style_val("v_align").unwrap().vertical_position() {
+            VerticalPosition::Top => 0.,
+            VerticalPosition::Center => 0.5,
+            VerticalPosition::Bottom => 1.,
+        };
+        (h, v)
+    }
+
+    fn nine_patch_insets(&self) -> Option<(f32, f32, f32, f32)> {
+        self.style_val("nine_patch").map(|v| {
+            let BorderWidth {
+                top,
+                left,
+                bottom,
+                right,
+            } = v.into();
+            (top, right, bottom, left)
+        })
+    }
 }
 
+#[state_component_impl(ImageState)]
 impl Component for Image {
+    fn init(&mut self) {
+        self.load();
+    }
+
+    fn new_props(&mut self) {
+        self.load();
+    }
+
+    fn props_hash(&self, hasher: &mut ComponentHasher) {
+        self.name.hash(hasher);
+        self.dynamic_load_from.hash(hasher);
+    }
+
     fn render_hash(&self, hasher: &mut ComponentHasher) {
         self.name.hash(hasher);
+        self.state_ref().asset.hash(hasher);
+        self.crop
+            .map(|c| (c.pos, Scale::new(c.width(), c.height())))
+            .hash(hasher);
+        self.style_val("fit").unwrap().image_fit().hash(hasher);
+        let (h, v) = self.align_fractions();
+        (h.to_bits(), v.to_bits()).hash(hasher);
+        self.style_val("flip_x").unwrap().bool().hash(hasher);
+        self.style_val("flip_y").unwrap().bool().hash(hasher);
+        self.style_val("tint").map(|c| c.color()).hash(hasher);
+        self.nine_patch_insets()
+            .map(|(t, r, b, l)| [t, r, b, l].map(f32::to_bits))
+            .hash(hasher);
+    }
+
+    fn on_tick(&mut self, _event: &mut Event<event::Tick>) {
+        if self.state_ref().asset == Some(AssetState::Loading) {
+            let asset = assets::state(&self.name);
+            if asset != self.state_ref().asset {
+                self.state_mut().asset = asset;
+            }
+        }
     }
 
     fn render(&mut self, context: RenderContext) -> Option<Vec<Renderable>> {
@@ -54,13 +191,23 @@ impl Component for Image {
         let height = context.aabb.height();
         let AABB { pos, .. } = context.aabb;
         let radius = self.style_val("radius").unwrap().f32();
+        let nine_patch = self.nine_patch_insets().map(|insets| NinePatch {
+            insets,
+            scale: context.scale_factor,
+        });
 
         let instance = ImageInstanceBuilder::default()
             .pos(pos)
             .scale(Scale { width, height })
             .name(self.name.clone())
             .radius(radius)
-            .dynamic_load_from(self.dynamic_load_from.clone())
+            .fit(self.style_val("fit").unwrap().image_fit())
+            .align(self.align_fractions())
+            .crop(self.crop)
+            .flip_x(self.style_val("flip_x").unwrap().bool())
+            .flip_y(self.style_val("flip_y").unwrap().bool())
+            .tint(self.style_val("tint").map(|c| c.color()))
+            .nine_patch(nine_patch)
             .build()
             .unwrap();
 
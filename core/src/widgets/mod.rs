@@ -18,6 +18,12 @@ pub use div::Div;
 mod image;
 pub use image::Image;
 
+mod animated_image;
+pub use animated_image::{AnimatedImage, Animation, AnimationFrame};
+
+mod texture_view;
+pub use texture_view::TextureView;
+
 mod svg;
 pub use svg::Svg;
 
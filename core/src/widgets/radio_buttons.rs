@@ -367,7 +367,8 @@ impl Component for RadioButton {
                         border_width: (border_width, border_width, border_width, border_width),
                         radius: self.radius,
                         scissor: None,
-                        swipe: 0
+                        swipe: 0,
+                        ..Default::default()
                     },
                     lay!(
                         size: size_pct!(100.0),
@@ -422,7 +423,8 @@ impl Component for RadioButton {
                         border_width: (border_width, border_width, border_width, border_width),
                         radius: self.radius,
                         scissor: None,
-                        swipe: 0
+                        swipe: 0,
+                        ..Default::default()
                     },
                     lay!(
                         size: size_pct!(100.0),
@@ -1,15 +1,23 @@
 use crate::component::{Component, ComponentHasher, RenderContext};
 
-use crate::renderables::rect::InstanceBuilder;
+use crate::renderables::rect::{Gradient, InstanceBuilder};
 use crate::renderables::types::{Point, Size};
 use crate::renderables::{Rect, Renderable};
 use crate::types::*;
 use std::hash::Hash;
 
+/// A rect with rounded corners and a border.
+///
+/// Fields may be added, so build one with [`RoundedRect::new`] or [`Default`], then set its fields or use its builder methods.
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct RoundedRect {
     pub background_color: Color,
+    /// Drawn over the background color, in fractions of the rect. See [`Gradient::fit`].
+    pub gradient: Option<Gradient>,
     pub border_color: Color,
+    /// Drawn in place of the border color, in fractions of the rect. See [`Gradient::fit`].
+    pub border_gradient: Option<Gradient>,
     pub border_width: (f32, f32, f32, f32),
     pub radius: (f32, f32, f32, f32),
     pub scissor: Option<bool>,
@@ -20,7 +28,9 @@ impl Default for RoundedRect {
     fn default() -> Self {
         Self {
             background_color: Color::WHITE,
+            gradient: None,
             border_color: Color::BLACK,
+            border_gradient: None,
             border_width: (0., 0.,  0., 0.),
             radius: (3.0, 3.0, 3.0, 3.0),
             scissor: None,
@@ -33,7 +43,9 @@ impl RoundedRect {
     pub fn new<C: Into<Color>>(bg: C, radius: f32) -> Self {
         Self {
             background_color: bg.into(),
+            gradient: None,
             border_color: Color::BLACK,
+            border_gradient: None,
             border_width: (0., 0.,  0., 0.),
             radius: (radius, radius, radius, radius),
             scissor: None,
@@ -46,6 +58,16 @@ impl RoundedRect {
         self
     }
 
+    pub fn gradient(mut self, gradient: Gradient) -> Self {
+        self.gradient = Some(gradient);
+        self
+    }
+
+    pub fn border_gradient(mut self, gradient: Gradient) -> Self {
+        self.border_gradient = Some(gradient);
+        self
+    }
+
     pub fn swipe(mut self, s: i32) -> Self {
         self.swipe = s;
         self
@@ -55,7 +77,9 @@ impl RoundedRect {
 impl Component for RoundedRect {
     fn render_hash(&self, hasher: &mut ComponentHasher) {
         self.background_color.hash(hasher);
+        self.gradient.hash(hasher);
         self.border_color.hash(hasher);
+        self.border_gradient.hash(hasher);
         (self.border_width.0 as u32, self.border_width.1 as u32, self.border_width.2 as u32, self.border_width.3 as u32).hash(hasher);
         (self.radius.0 as i32).hash(hasher);
         (self.radius.1 as i32).hash(hasher);
@@ -74,7 +98,9 @@ impl Component for RoundedRect {
             .pos(pos)
             .scale(Scale { width, height })
             .color(self.background_color)
+            .gradient(self.gradient.as_ref().map(|g| g.fit(context.aabb)))
             .border_color(self.border_color)
+            .border_gradient(self.border_gradient.as_ref().map(|g| g.fit(context.aabb)))
             .border_size(self.border_width)
             .scissor(self.scissor)
             .radius(self.radius)
@@ -1,12 +1,17 @@
 use std::hash::Hash;
 
+use mctk_macros::component;
+
 use crate::component::{Component, ComponentHasher, RenderContext};
 
 use crate::renderables::svg::InstanceBuilder;
 use crate::renderables::types::{Point, Size};
 use crate::renderables::{self, Rect, Renderable};
+use crate::style::Styled;
 use crate::types::*;
 
+/// Draws the SVG called `name`. It is drawn in its own colors, unless the `color` style is set.
+#[component(Styled, Internal)]
 #[derive(Debug)]
 pub struct Svg {
     pub name: String,
@@ -18,6 +23,8 @@ impl Default for Svg {
         Self {
             name: "".to_string(),
             dynamic_load_from: None,
+            class: Default::default(),
+            style_overrides: Default::default(),
         }
     }
 }
@@ -27,6 +34,8 @@ impl Svg {
         Self {
             name: name.into(),
             dynamic_load_from: None,
+            class: Default::default(),
+            style_overrides: Default::default(),
         }
     }
 
@@ -34,11 +43,17 @@ impl Svg {
         self.dynamic_load_from = v;
         self
     }
+
+    /// Draw the SVG in `color`, keeping only its alpha, e.g. to match a monochrome icon to the theme.
+    pub fn color(self, color: Color) -> Self {
+        self.style("color", color)
+    }
 }
 
 impl Component for Svg {
     fn render_hash(&self, hasher: &mut ComponentHasher) {
         self.name.hash(hasher);
+        self.style_val("color").map(|c| c.color()).hash(hasher);
     }
 
     fn render(&mut self, context: RenderContext) -> Option<Vec<Renderable>> {
@@ -50,6 +65,7 @@ impl Component for Svg {
             .scale(scale)
             .name(self.name.clone())
             .dynamic_load_from(self.dynamic_load_from.clone())
+            .color(self.style_val("color").map(|c| c.color()))
             .build()
             .unwrap();
 
@@ -58,3 +74,52 @@ impl Component for Svg {
         ))])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::TestHarness;
+    use crate::{lay, node, size};
+
+    #[test]
+    fn test_svg_color() {
+        let path =
+            std::env::temp_dir().join(format!("mctk_test_svg_color_{}.svg", std::process::id()));
+        std::fs::write(
+            &path,
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+                <linearGradient id="g"><stop offset="0" stop-color="red"/><stop offset="1" stop-color="blue"/></linearGradient>
+                <rect width="10" height="10" fill="url(#g)" opacity="0.5"/>
+            </svg>"##,
+        )
+        .unwrap();
+        let path = path.to_str().unwrap().to_string();
+
+        let draw = |color: Option<Color>| {
+            let path = path.clone();
+            let mut harness = TestHarness::single(move || {
+                let mut svg =
+                    Svg::new(format!("{:?}", color)).dynamic_load_from(Some(path.clone()));
+                if let Some(color) = color {
+                    svg = svg.color(color);
+                }
+                node!(svg, lay![size: [20., 20.]])
+            });
+            let pixmap = harness.render().pixmap();
+            [1, 18].map(|x| {
+                let p = pixmap.pixel(x, 10).unwrap();
+                [p.red(), p.green(), p.blue(), p.alpha()]
+            })
+        };
+
+        // The gradient goes from red to blue, at half opacity
+        let [left, right] = draw(None);
+        assert!(left[0] > 100 && left[2] < 30 && (120..136).contains(&left[3]));
+        assert!(right[2] > 100 && right[0] < 30 && (120..136).contains(&right[3]));
+        // Recolored, only the opacity is left
+        let [left, right] = draw(Some(Color::rgb(0., 255., 0.)));
+        assert_eq!(left, right);
+        assert_eq!((left[0], left[2]), (0, 0));
+        assert!((120..136).contains(&left[1]) && left[1] == left[3]);
+    }
+}
@@ -1,15 +1,25 @@
+use std::cmp::Ordering;
 use std::hash::Hash;
+use std::ops::Range;
+use std::time::Instant;
 
-use crate::component::{Component, ComponentHasher, RenderContext};
+use crate::component::{Component, ComponentHasher, Message, RenderContext};
+use crate::event;
 use crate::font_cache::{FontCache, TextSegment};
-use crate::renderables::text::InstanceBuilder;
+use crate::input::Key;
+use crate::renderables::text::{
+    FontFeature, InstanceBuilder, TextDecoration, TextDecorationLine, TextShadow, TextSpan,
+    TextWrap,
+};
 use crate::renderables::{text, Renderable};
-use crate::style::{FontWeight, HorizontalPosition, Styled};
+use crate::style::{FontStretch, FontStyle, FontWeight, HorizontalPosition, Styled};
 use crate::types::*;
 use cosmic_text::LayoutGlyph;
 use femtovg::Align;
 use mctk_macros::{component, state_component_impl};
 
+use super::textbox::word_at;
+
 #[derive(Debug, Default)]
 struct BoundsCache {
     width: Option<f32>,
@@ -22,24 +32,287 @@ struct BoundsCache {
 #[derive(Debug, Default)]
 pub struct TextState {
     bounds_cache: BoundsCache,
+    /// The boxes of the segments that are links, relative to the text, in physical pixels.
+    links: Vec<(AABB, String)>,
+    /// Where the selection of `selectable` text started, as a byte offset in the text.
+    selection_from: Option<usize>,
+    /// Where the selection ends, as a byte offset in the text.
+    cursor_pos: usize,
+    /// The box of every glyph, relative to the text, in physical pixels, with the bytes of the text that it shows.
+    glyphs: Vec<(AABB, Range<usize>)>,
+    /// When and where a touch that could become a long press went down.
+    touch_down: Option<(Instant, Point)>,
+    /// Where the mouse was pressed, relative to the text, which is where a drag-selection starts.
+    mouse_down: Point,
+    /// Whether a selection was just made by dragging or long-pressing, which the Click that ends it shouldn't clear.
+    selected: bool,
 }
 
+/// Draws a line of text, or several when the `wrap` style is set. Text that doesn't fit is cut off at `max_lines`, optionally with an `ellipsis`.
+///
+/// Each [`TextSegment`] is a span with its own color, weight, style, font and background highlight.
+/// Clicking a segment that has a `link` emits the message returned by `on_link`.
+///
+/// [`selectable`][Text::selectable] text can be selected by dragging over it, or by double-clicking or long-pressing a word,
+/// and the selection copied to the clipboard with Ctrl+C.
 #[component(State = "TextState", Styled, Internal)]
-#[derive(Debug)]
 pub struct Text {
     pub text: Vec<TextSegment>,
+    pub on_link: Option<Box<dyn Fn(String) -> Message + Send + Sync>>,
+    pub selectable: bool,
+}
+
+impl std::fmt::Debug for Text {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Text")
+            .field("text", &self.text)
+            .field("selectable", &self.selectable)
+            .finish()
+    }
 }
 
 impl Text {
     pub fn new(text: Vec<TextSegment>) -> Self {
         Self {
             text,
+            on_link: None,
+            selectable: false,
             class: Default::default(),
             style_overrides: Default::default(),
             state: Some(TextState::default()),
             dirty: false,
         }
     }
+
+    pub fn wrap(self, wrap: TextWrap) -> Self {
+        self.style("wrap", wrap)
+    }
+
+    pub fn max_lines(self, max_lines: u32) -> Self {
+        self.style("max_lines", max_lines)
+    }
+
+    pub fn ellipsis(self, ellipsis: bool) -> Self {
+        self.style("ellipsis", ellipsis)
+    }
+
+    pub fn font_style(self, font_style: FontStyle) -> Self {
+        self.style("font_style", font_style)
+    }
+
+    pub fn italic(self) -> Self {
+        self.font_style(FontStyle::Italic)
+    }
+
+    pub fn stretch(self, stretch: FontStretch) -> Self {
+        self.style("font_stretch", stretch)
+    }
+
+    /// Space added between glyphs, in logical pixels.
+    pub fn letter_spacing(self, letter_spacing: f32) -> Self {
+        self.style("letter_spacing", letter_spacing)
+    }
+
+    /// Give every digit the same width, so that numbers that change, like those of a clock, don't move around.
+    pub fn tabular_nums(self) -> Self {
+        self.style("tabular_nums", true)
+    }
+
+    /// OpenType features, like `"tnum ss01=2"`. See [`FontFeature`] for which of them are supported.
+    pub fn font_features(self, features: &'static str) -> Self {
+        self.style("font_features", features)
+    }
+
+    pub fn decoration(self, decoration: TextDecoration) -> Self {
+        self.style("decoration", decoration)
+    }
+
+    pub fn underline(self) -> Self {
+        self.decoration(TextDecoration::new(TextDecorationLine::Underline))
+    }
+
+    pub fn line_through(self) -> Self {
+        self.decoration(TextDecoration::new(TextDecorationLine::LineThrough))
+    }
+
+    /// Draw a blurred shadow beneath the text, e.g. to keep it legible on top of a picture.
+    pub fn shadow(self, shadow: TextShadow) -> Self {
+        self.style("shadow", shadow)
+    }
+
+    /// Called with the `link` of a segment when it is clicked.
+    pub fn on_link(mut self, f: Box<dyn Fn(String) -> Message + Send + Sync>) -> Self {
+        self.on_link = Some(f);
+        self
+    }
+
+    /// Let the text be selected and copied. The selection is drawn behind it with the `selection_color` style.
+    pub fn selectable(mut self) -> Self {
+        self.selectable = true;
+        self
+    }
+
+    /// All of the segments, joined.
+    fn string(&self) -> String {
+        self.text.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    fn selection(&self) -> Option<Range<usize>> {
+        let pos = self.state_ref().cursor_pos;
+        self.state_ref()
+            .selection_from
+            .and_then(|selection_from| match pos.cmp(&selection_from) {
+                Ordering::Equal => None,
+                Ordering::Greater => Some(selection_from..pos),
+                Ordering::Less => Some(pos..selection_from),
+            })
+    }
+
+    /// The byte offset in the text that is closest to `p`, which is relative to the text.
+    fn position(&self, p: Point) -> usize {
+        let glyphs = &self.state_ref().glyphs;
+        // The line that `p` is on, or the first one when it is above them all
+        let Some(top) = glyphs
+            .iter()
+            .map(|(aabb, _)| aabb.pos.y)
+            .filter(|y| *y <= p.y)
+            .last()
+            .or_else(|| glyphs.first().map(|(aabb, _)| aabb.pos.y))
+        else {
+            return 0;
+        };
+        let line = glyphs.iter().filter(|(aabb, _)| aabb.pos.y == top);
+        if let Some((_, range)) = line
+            .clone()
+            .find(|(aabb, _)| p.x < aabb.pos.x + aabb.width() / 2.0)
+        {
+            range.start
+        } else {
+            line.last().map_or(0, |(_, range)| range.end)
+        }
+    }
+
+    // Returns whether or not there was a word to select
+    fn select_word(&mut self, p: Point) -> bool {
+        let word = word_at(&self.string(), self.position(p));
+
+        if !word.is_empty() {
+            self.state_mut().selection_from = Some(word.start);
+            self.state_mut().cursor_pos = word.end;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The byte ranges that the segments take up in the joined text.
+    fn segment_ranges(&self) -> impl Iterator<Item = (std::ops::Range<usize>, &TextSegment)> {
+        self.text.iter().scan(0, |start, segment| {
+            let range = *start..*start + segment.text.len();
+            *start = range.end;
+            Some((range, segment))
+        })
+    }
+
+    fn spans(&self) -> Vec<TextSpan> {
+        let mut spans: Vec<TextSpan> = self
+            .segment_ranges()
+            .filter(|(_, s)| {
+                s.color.is_some()
+                    || s.weight.is_some()
+                    || s.italic
+                    || s.font.is_some()
+                    || s.background.is_some()
+            })
+            .map(|(range, s)| TextSpan {
+                range,
+                color: s.color,
+                weight: s.weight,
+                italic: s.italic,
+                font: s.font.clone(),
+                background: s.background,
+            })
+            .collect();
+        // Last, so that it is drawn over the backgrounds of the segments
+        if let Some(range) = self.selection() {
+            spans.push(TextSpan {
+                range,
+                background: self.style_val("selection_color").map(|c| c.color()),
+                ..Default::default()
+            });
+        }
+        spans
+    }
+
+    /// The text to draw in a box of the given size, in physical pixels.
+    fn instance(&self, pos: Pos, scale: Scale, scale_factor: f32) -> text::Instance {
+        let h_alignment: HorizontalPosition =
+            if let Some(h_alignment) = self.style_val("h_alignment") {
+                h_alignment.horizontal_position()
+            } else {
+                HorizontalPosition::Left
+            };
+        let font = self.style_val("font").map(|p| p.str().to_string());
+        let color: Color = self.style_val("color").into();
+        // Lines can only have one size, so that of the first segment that has one wins
+        let size: f32 = if let Some(size) = self.text.iter().find_map(|s| s.size) {
+            size
+        } else if let Some(size) = self.style_val("size") {
+            size.f32()
+        } else {
+            16.
+        };
+        let font_weight = if let Some(font_weight) = self.style_val("font_weight") {
+            font_weight.font_weight()
+        } else {
+            FontWeight::Normal
+        };
+        let mut features = self
+            .style_val("font_features")
+            .map_or(vec![], |v| FontFeature::parse(v.str()));
+        if self.style_val("tabular_nums").map_or(false, |v| v.bool()) {
+            features.push(FontFeature::TABULAR_NUMBERS);
+        }
+        // line height as 1.3 of font_size
+        let line_height = if let Some(line_height) = self.style_val("line_height") {
+            line_height.f32()
+        } else {
+            size * 1.3
+        };
+
+        InstanceBuilder::default()
+            .align(match h_alignment {
+                HorizontalPosition::Left => Align::Left,
+                HorizontalPosition::Center => Align::Center,
+                HorizontalPosition::Right => Align::Right,
+            })
+            .pos(pos)
+            .scale(scale)
+            .text(self.string())
+            .spans(self.spans())
+            .color(color)
+            .font(font)
+            .weight(font_weight)
+            .font_style(self.style_val("font_style").unwrap().font_style())
+            .stretch(self.style_val("font_stretch").unwrap().font_stretch())
+            .letter_spacing(self.style_val("letter_spacing").unwrap().f32() * scale_factor)
+            .features(features)
+            .decoration(
+                self.style_val("decoration")
+                    .map(|v| v.text_decoration().scale(scale_factor)),
+            )
+            .shadow(
+                self.style_val("shadow")
+                    .map(|v| v.text_shadow().scale(scale_factor)),
+            )
+            .line_height(line_height * scale_factor)
+            .font_size(size * scale_factor)
+            .wrap(self.style_val("wrap").unwrap().text_wrap())
+            .max_lines(self.style_val("max_lines").map(|v| v.u32() as usize))
+            .ellipsis(self.style_val("ellipsis").unwrap().bool())
+            .build()
+            .unwrap()
+    }
 }
 
 #[state_component_impl(TextState)]
@@ -58,6 +331,19 @@ impl Component for Text {
         (self.style_val("color").unwrap().color()).hash(hasher);
         (self.style_val("font").map(|p| p.str().to_string())).hash(hasher);
         (self.style_val("h_alignment").map(|v| v.horizontal_position())).hash(hasher);
+        (self.style_val("font_weight").map(|v| v.font_weight())).hash(hasher);
+        (self.style_val("font_style").map(|v| v.font_style())).hash(hasher);
+        (self.style_val("font_stretch").map(|v| v.font_stretch())).hash(hasher);
+        ((self.style_val("letter_spacing").unwrap().f32() * 100.) as i32).hash(hasher);
+        (self.style_val("tabular_nums").map(|v| v.bool())).hash(hasher);
+        (self.style_val("font_features").map(|v| v.str())).hash(hasher);
+        (self.style_val("decoration").map(|v| v.text_decoration())).hash(hasher);
+        (self.style_val("shadow").map(|v| v.text_shadow())).hash(hasher);
+        (self.style_val("wrap").map(|v| v.text_wrap())).hash(hasher);
+        (self.style_val("max_lines").map(|v| v.u32())).hash(hasher);
+        (self.style_val("ellipsis").map(|v| v.bool())).hash(hasher);
+        (self.style_val("selection_color").map(|v| v.color())).hash(hasher);
+        self.selection().hash(hasher);
     }
 
     fn fill_bounds(
@@ -83,28 +369,18 @@ impl Component for Text {
             (output.0, output.1);
         }
 
-        let text = self.text.get(0).unwrap().text.clone();
-        let size: f32 = self.style_val("size").unwrap().f32();
-        let font = self.style_val("font").map(|p| p.str().to_string());
-        let mut line_height = size * 1.3; // line height as 1.3 of font_size
-        if self.style_val("line_height").is_some() {
-            line_height = self.style_val("line_height").unwrap().f32();
-        }
-
-        let (t_w, t_h, ..) = font_cache.measure_text(
-            text.clone(),
-            font,
-            size,
+        // Text wraps at, and is cut off at, the width that is available to it
+        let (t_w, t_h, ..) = font_cache.measure_instance(self.instance(
+            Pos::default(),
+            Scale {
+                width: width.or(max_width).map_or(std::f32::MAX, |w| w * scale_factor),
+                height: height.or(max_height).map_or(std::f32::MAX, |h| h * scale_factor),
+            },
             scale_factor,
-            line_height,
-            HorizontalPosition::Left,
-            (
-                width.or(max_width).unwrap_or(std::f32::MAX) * scale_factor,
-                height.or(max_height).unwrap_or(std::f32::MAX) * scale_factor,
-            ),
-        );
+        ));
 
-        let output = (t_w, t_h);
+        // Measured in physical pixels, while the layout is in logical ones
+        let output = (t_w.map(|w| w / scale_factor), t_h.map(|h| h / scale_factor));
         self.state_mut().bounds_cache = BoundsCache {
             width,
             height,
@@ -116,70 +392,450 @@ impl Component for Text {
     }
 
     fn render(&mut self, context: RenderContext) -> Option<Vec<Renderable>> {
-        let h_alignment: HorizontalPosition =
-            if let Some(h_alignment) = self.style_val("h_alignment") {
-                h_alignment.horizontal_position()
-            } else {
-                HorizontalPosition::Left
-            };
-        let font = self.style_val("font").map(|p| p.str().to_string());
-        let color: Color = self.style_val("color").into();
-        let scale = context.aabb.size();
-        let size: f32 = if let Some(size) = self.style_val("size") {
-            size.f32()
-        } else {
-            16.
-        };
-        let AABB { pos, .. } = context.aabb;
-        let font_weight = if let Some(font_weight) = self.style_val("font_weight") {
-            font_weight.font_weight()
-        } else {
-            FontWeight::Normal
-        };
-        // line height as 1.3 of font_size
-        let line_height = if let Some(line_height) = self.style_val("line_height") {
-            line_height.f32()
+        let text_instance =
+            self.instance(context.aabb.pos, context.aabb.size(), context.scale_factor);
+
+        let (ranges, targets): (Vec<_>, Vec<_>) = self
+            .segment_ranges()
+            .filter_map(|(range, s)| Some((range, s.link.clone()?)))
+            .unzip();
+        let links = if self.on_link.is_some() && !ranges.is_empty() {
+            let mut instance = text_instance.clone();
+            instance.pos = Pos::default();
+            let rects = context
+                .caches
+                .font
+                .write()
+                .unwrap()
+                .measure_ranges(instance, &ranges);
+            rects
+                .into_iter()
+                .zip(targets)
+                .flat_map(|(rects, target)| rects.into_iter().map(move |r| (r, target.clone())))
+                .collect()
         } else {
-            size * 1.3
+            vec![]
         };
+        // Where the links are only matters to events, so this doesn't need another render
+        self.state.as_mut().unwrap().links = links;
 
-        // let font = Some(String::from("SpaceGrotesk-Bold"));
-
-        // let screen_position = (
-        //     match h_alignment {
-        //         HorizontalPosition::Left => 0.0,
-        //         HorizontalPosition::Center => scale.width / 2.0,
-        //         HorizontalPosition::Right => scale.width,
-        //     },
-        //     0.0,
-        // );
-
-        // let pos = context.aabb.pos
-        //     + Pos {
-        //         x: screen_position.0,
-        //         y: screen_position.1,
-        //         z: 0.,
-        //     };
-
-        let text_instance = InstanceBuilder::default()
-            .align(match h_alignment {
-                HorizontalPosition::Left => Align::Left,
-                HorizontalPosition::Center => Align::Center,
-                HorizontalPosition::Right => Align::Right,
-            })
-            .pos(pos)
-            .scale(scale)
-            .text(self.text.get(0).unwrap().text.clone())
-            .color(color)
-            .font(font)
-            .weight(font_weight)
-            .line_height(line_height)
-            .font_size(size)
-            .build()
-            .unwrap();
+        if self.selectable {
+            let text = &text_instance.text;
+            let mut instance = text_instance.clone();
+            instance.pos = Pos::default();
+            let glyphs = context
+                .caches
+                .font
+                .write()
+                .unwrap()
+                .glyph_boxes(instance)
+                .into_iter()
+                // Leave out the ellipsis, which isn't part of the text
+                .filter(|(_, range)| {
+                    range.end <= text.len()
+                        && text.is_char_boundary(range.start)
+                        && text.is_char_boundary(range.end)
+                })
+                .collect();
+            self.state.as_mut().unwrap().glyphs = glyphs;
+        }
 
         Some(vec![Renderable::Text(text::Text::from_instance_data(
             text_instance,
         ))])
     }
+
+    fn on_click(&mut self, event: &mut event::Event<event::Click>) {
+        if std::mem::take(&mut self.state.as_mut().unwrap().selected) {
+            return;
+        }
+        if self.state_ref().selection_from.is_some() {
+            self.state_mut().selection_from = None;
+        }
+        let Some(f) = &self.on_link else {
+            return;
+        };
+        // Clicks can come from the mouse or from a tap
+        let link = [
+            event.relative_physical_position(),
+            event.relative_physical_position_touch(),
+        ]
+        .into_iter()
+        .find_map(|p| {
+            self.state_ref()
+                .links
+                .iter()
+                .find(|(aabb, _)| aabb.is_under(p))
+        });
+        if let Some((_, target)) = link {
+            event.emit(f(target.clone()));
+            event.stop_bubbling();
+        }
+    }
+
+    fn on_double_click(&mut self, event: &mut event::Event<event::DoubleClick>) {
+        if !self.selectable {
+            return;
+        }
+        event.stop_bubbling();
+        event.focus();
+        self.select_word(pointer_position(event));
+    }
+
+    fn on_drag_start(&mut self, event: &mut event::Event<event::DragStart>) {
+        if !self.selectable {
+            return;
+        }
+        // Drags only start once the mouse has moved a bit, so the selection starts where it was pressed
+        let from = self.position(self.state_ref().mouse_down);
+        let to = self.position(event.relative_physical_position());
+        self.state_mut().selection_from = Some(from);
+        self.state_mut().cursor_pos = to;
+        event.focus();
+        event.stop_bubbling();
+    }
+
+    fn on_drag_end(&mut self, _event: &mut event::Event<event::DragEnd>) {
+        self.state.as_mut().unwrap().selected = self.selection().is_some();
+    }
+
+    fn on_mouse_down(&mut self, event: &mut event::Event<event::MouseDown>) {
+        let state = self.state.as_mut().unwrap();
+        state.mouse_down = event.relative_physical_position();
+        state.selected = false;
+    }
+
+    fn on_drag(&mut self, event: &mut event::Event<event::Drag>) {
+        if self.state_ref().selection_from.is_none() {
+            return;
+        }
+        let new_pos = self.position(event.relative_physical_position());
+        if new_pos != self.state_ref().cursor_pos {
+            self.state_mut().cursor_pos = new_pos;
+        }
+    }
+
+    fn on_touch_down(&mut self, event: &mut event::Event<event::TouchDown>) {
+        if !self.selectable {
+            return;
+        }
+        // Ticks can't focus, so this is done now in case the touch becomes a long press
+        event.focus();
+        let state = self.state.as_mut().unwrap();
+        state.touch_down = Some((Instant::now(), event.relative_physical_position_touch()));
+        state.selected = false;
+    }
+
+    fn on_touch_up(&mut self, _event: &mut event::Event<event::TouchUp>) {
+        self.state.as_mut().unwrap().touch_down = None;
+    }
+
+    fn on_touch_cancel(&mut self, _event: &mut event::Event<event::TouchCancel>) {
+        self.state.as_mut().unwrap().touch_down = None;
+    }
+
+    fn on_touch_drag_start(&mut self, event: &mut event::Event<event::TouchDragStart>) {
+        self.state.as_mut().unwrap().touch_down = None;
+        // Dragging after a long press extends the selection, rather than scrolling
+        if self.state_ref().selection_from.is_some() {
+            event.stop_bubbling();
+        }
+    }
+
+    fn on_touch_drag_end(&mut self, _event: &mut event::Event<event::TouchDragEnd>) {
+        self.state.as_mut().unwrap().selected = self.selection().is_some();
+    }
+
+    fn on_touch_drag(&mut self, event: &mut event::Event<event::TouchDrag>) {
+        if self.state_ref().selection_from.is_none() {
+            return;
+        }
+        let new_pos = self.position(event.relative_physical_position_touch());
+        if new_pos != self.state_ref().cursor_pos {
+            self.state_mut().cursor_pos = new_pos;
+        }
+    }
+
+    fn on_tick(&mut self, _event: &mut event::Event<event::Tick>) {
+        if let Some((at, p)) = self.state_ref().touch_down {
+            if at.elapsed().as_millis() >= event::LONG_PRESS_MS {
+                self.state.as_mut().unwrap().touch_down = None;
+                let selected = self.select_word(p);
+                // The touch is still down, and the tap when it's lifted shouldn't clear the selection
+                self.state.as_mut().unwrap().selected = selected;
+            }
+        }
+    }
+
+    fn on_blur(&mut self, _event: &mut event::Event<event::Blur>) {
+        self.state.as_mut().unwrap().touch_down = None;
+        if self.state_ref().selection_from.is_some() {
+            self.state_mut().selection_from = None;
+        }
+    }
+
+    fn on_key_down(&mut self, event: &mut event::Event<event::KeyDown>) {
+        if !self.selectable || !event.modifiers_held.ctrl {
+            return;
+        }
+        match event.input.0 {
+            Key::C => {
+                if let Some(range) = self.selection() {
+                    event.put_on_clipboard(Data::String(self.string()[range].to_string()));
+                    event.stop_bubbling();
+                }
+            }
+            Key::A => {
+                self.state_mut().selection_from = Some(0);
+                self.state_mut().cursor_pos = self.string().len();
+                event.stop_bubbling();
+            }
+            _ => (),
+        }
+    }
+}
+
+/// The position of the mouse relative to the Node of `event` or, when that isn't over it, that of the touch.
+fn pointer_position<T: event::EventInput>(event: &event::Event<T>) -> Point {
+    let aabb = event.current_physical_aabb();
+    let size = Point::new(aabb.width(), aabb.height());
+    let mouse = event.relative_physical_position();
+    if mouse.x >= 0.0 && mouse.y >= 0.0 && mouse.x <= size.x && mouse.y <= size.y {
+        mouse
+    } else {
+        event.relative_physical_position_touch()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::TestHarness;
+    use crate::widgets::Div;
+    use crate::{lay, msg, node, size};
+
+    #[test]
+    fn test_text_wrap() {
+        // The size of a Text that is given a width of 100
+        let measure = |text: fn() -> Text| {
+            let mut harness = TestHarness::with_font(200, 200, move || {
+                node!(Div::new(), lay![size: [200., 200.]]).push(node!(
+                    text().style("font", "Space Grotesk"),
+                    lay![size: [100., Auto]],
+                    0
+                ))
+            });
+            harness.render();
+            let text = harness.root().children()[0].children()[0].id();
+            let aabb = harness.aabb(text).unwrap();
+            (aabb.width(), aabb.height())
+        };
+        fn text() -> Text {
+            Text::new(vec!["The quick brown fox jumps over the lazy dog".into()])
+        }
+        // 12px text has 15.6px lines
+        let lines = |height: f32| height / 15.6;
+
+        let (width, height) = measure(text);
+        assert!(width > 100.);
+        assert_eq!(lines(height).round(), 1.);
+
+        let (width, height) = measure(|| text().wrap(TextWrap::Word));
+        assert!(width <= 100.);
+        assert!(lines(height).round() >= 3.);
+
+        let (_, height) = measure(|| text().wrap(TextWrap::Word).max_lines(2));
+        assert_eq!(lines(height).round(), 2.);
+        let (_, height) = measure(|| text().wrap(TextWrap::Word).max_lines(2).ellipsis(true));
+        assert_eq!(lines(height).round(), 2.);
+
+        // Cut off at the width of the box
+        let (width, height) = measure(|| text().ellipsis(true));
+        assert!(width <= 100.);
+        assert_eq!(lines(height).round(), 1.);
+    }
+
+    #[test]
+    fn test_text_spans() {
+        let mut harness = TestHarness::with_font(200, 50, || {
+            node!(Div::new(), lay![size: [200., 50.]]).push(node!(
+                Text::new(vec![
+                    "Read ".into(),
+                    TextSegment::new("more")
+                        .background(Color::rgb(0., 255., 0.))
+                        .link("more"),
+                ])
+                .style("font", "Space Grotesk")
+                .on_link(Box::new(|link| msg!(link))),
+                lay![size: [200., Auto]],
+                0
+            ))
+        });
+
+        // Only the link is highlighted, above its glyphs
+        let pixmap = harness.render().pixmap();
+        let highlighted = (0..200)
+            .filter(|&x| {
+                let p = pixmap.pixel(x, 1).unwrap();
+                [p.red(), p.green(), p.blue()] == [0, 255, 0]
+            })
+            .collect::<Vec<_>>();
+        assert!(!highlighted.is_empty());
+        assert!(highlighted[0] > 10);
+
+        harness.click(highlighted[0] as f32 - 5., 5.);
+        assert!(harness.messages().is_empty());
+        harness.click(highlighted[0] as f32 + 2., 5.);
+        let messages = harness.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            messages[0].downcast_ref::<String>(),
+            Some(&"more".to_string())
+        );
+    }
+
+    #[test]
+    fn test_text_features() {
+        // The width of a Text
+        let measure = |text: Box<dyn Fn() -> Text + Send + Sync>| {
+            let mut harness = TestHarness::with_font(200, 200, move || {
+                node!(Div::new(), lay![size: [200., 200.]]).push(node!(
+                    text().style("font", "Space Grotesk"),
+                    lay![size: [200., Auto]],
+                    0
+                ))
+            });
+            harness.render();
+            let text = harness.root().children()[0].children()[0].id();
+            harness.aabb(text).unwrap().width()
+        };
+        let text = |s: &'static str| move || Text::new(vec![s.into()]);
+
+        // Digits are proportional, unless they are tabular
+        assert!(measure(Box::new(text("111"))) < measure(Box::new(text("888"))));
+        let ones = measure(Box::new(move || text("111")().tabular_nums()));
+        let eights = measure(Box::new(move || text("888")().tabular_nums()));
+        assert!((ones - eights).abs() < 0.01);
+        let ones = measure(Box::new(move || text("111")().font_features("tnum")));
+        assert!((ones - eights).abs() < 0.01);
+
+        // Spacing goes between glyphs
+        let spaced = measure(Box::new(move || text("888")().letter_spacing(5.)));
+        assert!((spaced - measure(Box::new(text("888"))) - 10.).abs() < 1.);
+
+        assert_eq!(
+            FontFeature::parse("tnum ss01=2, liga=0 toolong x=y"),
+            vec![
+                FontFeature::TABULAR_NUMBERS,
+                FontFeature::new(*b"ss01", 2),
+                FontFeature::new(*b"liga", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_text_decoration_and_shadow() {
+        // The pixels of the given color, in the rows from `top`
+        let count = |text: fn() -> Text, color: [u8; 3], top: u32| {
+            let mut harness = TestHarness::with_font(200, 60, move || {
+                node!(Div::new(), lay![size: [200., 60.]]).push(node!(
+                    text().style("font", "Space Grotesk"),
+                    lay![size: [200., Auto]],
+                    0
+                ))
+            });
+            let pixmap = harness.render().pixmap();
+            (0..200)
+                .flat_map(|x| (top..60).map(move |y| (x, y)))
+                .filter(|&(x, y)| {
+                    let p = pixmap.pixel(x, y).unwrap();
+                    [p.red(), p.green(), p.blue()] == color && p.alpha() == 255
+                })
+                .count()
+        };
+        fn text() -> Text {
+            Text::new(vec!["Hello".into()])
+        }
+
+        assert_eq!(count(text, [255, 0, 0], 0), 0);
+        let underlined = count(
+            || {
+                text().decoration(
+                    TextDecoration::new(TextDecorationLine::Underline).color(Color::RED),
+                )
+            },
+            [255, 0, 0],
+            0,
+        );
+        assert!(underlined > 20);
+
+        // Shadows are drawn outside of the box of the text
+        let shadow = || text().shadow(TextShadow::new(Color::BLUE, (0., 30.), 0.));
+        assert!(count(shadow, [0, 0, 255], 30) > 20);
+        let blurred = || text().shadow(TextShadow::new(Color::BLUE, (0., 30.), 6.));
+        let pixels = count(blurred, [0, 0, 255], 30);
+        assert!(pixels < count(shadow, [0, 0, 255], 30));
+    }
+
+    #[test]
+    fn test_text_selection() {
+        use crate::input::{Button, Input, MouseButton};
+        use crate::window::Window;
+
+        let mut harness = TestHarness::with_font(200, 40, || {
+            node!(Div::new(), lay![size: [200., 40.]]).push(node!(
+                Text::new(vec!["Hello ".into(), "world".into()])
+                    .selectable()
+                    .style("font", "Space Grotesk")
+                    .style("selection_color", Color::RED),
+                lay![size: [200., Auto]],
+                0
+            ))
+        });
+        let red = |harness: &mut TestHarness<_, _>| {
+            let pixmap = harness.render().pixmap();
+            pixmap
+                .pixels()
+                .iter()
+                .filter(|p| [p.red(), p.green(), p.blue(), p.alpha()] == [255, 0, 0, 255])
+                .count()
+        };
+        assert_eq!(red(&mut harness), 0);
+
+        // Double-clicking selects the word under the mouse, which is drawn highlighted
+        harness.click(5., 8.);
+        harness.click(5., 8.);
+        assert!(red(&mut harness) > 20);
+
+        harness.input(Input::Press(Button::Keyboard(Key::LCtrl)));
+        harness.input(Input::Press(Button::Keyboard(Key::C)));
+        assert_eq!(
+            harness.ui().window.read().unwrap().get_from_clipboard(),
+            Some(Data::String("Hello".to_string()))
+        );
+
+        // Selecting everything spans the segments
+        harness.input(Input::Press(Button::Keyboard(Key::A)));
+        harness.input(Input::Press(Button::Keyboard(Key::C)));
+        assert_eq!(
+            harness.ui().window.read().unwrap().get_from_clipboard(),
+            Some(Data::String("Hello world".to_string()))
+        );
+
+        // A drag that is short enough to also send a Click keeps its selection
+        harness.mouse_move(2., 8.);
+        harness.input(Input::Press(Button::Mouse(MouseButton::Left)));
+        harness.mouse_move(25., 8.);
+        harness.input(Input::Release(Button::Mouse(MouseButton::Left)));
+        harness.input(Input::Press(Button::Keyboard(Key::C)));
+        let Some(Data::String(selected)) = harness.ui().window.read().unwrap().get_from_clipboard()
+        else {
+            panic!("Nothing was copied");
+        };
+        assert!(selected.len() > 1 && "Hello".starts_with(&selected));
+
+        // While a click clears it
+        harness.click(5., 8.);
+        assert_eq!(red(&mut harness), 0);
+    }
 }
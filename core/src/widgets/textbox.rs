@@ -1,7 +1,7 @@
 use std::cmp::Ordering;
 use std::default;
 use std::hash::Hash;
-use std::ops::Add;
+use std::ops::{Add, Range};
 use std::time::Instant;
 
 use crate::component::{Component, ComponentHasher, Message, RenderContext};
@@ -403,25 +403,11 @@ impl TextBoxText {
 
     // Returns whether or not there was a word to select
     fn select_word(&mut self) -> bool {
-        let pos = self.state_ref().cursor_pos;
-        let text = &self.state_ref().text;
-        let end_pos = pos
-            + text
-                .chars()
-                .skip(pos)
-                .position(|x| !x.is_alphanumeric())
-                .unwrap_or(text.len() - pos);
-        let start_pos = pos
-            - text
-                .chars()
-                .rev()
-                .skip(text.len() - pos)
-                .position(|x| !x.is_alphanumeric())
-                .unwrap_or(pos);
-
-        if start_pos != end_pos {
-            self.state_mut().selection_from = Some(start_pos);
-            self.state_mut().cursor_pos = end_pos;
+        let word = word_at(&self.state_ref().text, self.state_ref().cursor_pos);
+
+        if !word.is_empty() {
+            self.state_mut().selection_from = Some(word.start);
+            self.state_mut().cursor_pos = word.end;
             true
         } else {
             false
@@ -973,3 +959,19 @@ impl Component for TextBoxText {
 fn get_masked_text<S: Into<String>>(text: S) -> String {
     text.into().chars().into_iter().map(|_| "•").collect()
 }
+
+/// The byte range of the word of `text` that the byte offset `pos` is in or next to. Empty if there is none.
+pub(crate) fn word_at(text: &str, pos: usize) -> Range<usize> {
+    if pos > text.len() || !text.is_char_boundary(pos) {
+        return pos..pos;
+    }
+    let end = text[pos..]
+        .find(|c: char| !c.is_alphanumeric())
+        .map_or(text.len(), |i| pos + i);
+    let start = text[..pos]
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !c.is_alphanumeric())
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    start..end
+}
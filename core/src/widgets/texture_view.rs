@@ -0,0 +1,126 @@
+use std::hash::Hash;
+
+use mctk_macros::{component, state_component_impl};
+
+use crate::component::{Component, ComponentHasher, RenderContext};
+use crate::event::{self, Event};
+use crate::renderables::texture::InstanceBuilder as TextureInstanceBuilder;
+use crate::renderables::{self, Renderable};
+use crate::style::Styled;
+use crate::texture::Texture;
+use crate::types::*;
+
+#[derive(Debug, Default)]
+struct TextureViewState {
+    generation: u64,
+}
+
+/// Shows the latest frame of a streaming [`Texture`], e.g. a camera feed. It is redrawn on the first [`Tick`][event::Tick] after a new frame is pushed.
+#[component(State = "TextureViewState", Styled = "Image", Internal)]
+#[derive(Debug)]
+pub struct TextureView {
+    pub texture: Texture,
+}
+
+impl TextureView {
+    pub fn new(texture: Texture) -> Self {
+        Self {
+            texture,
+            state: Some(TextureViewState::default()),
+            dirty: false,
+            class: Default::default(),
+            style_overrides: Default::default(),
+        }
+    }
+
+    /// Show the texture called `name`, see [`Texture::named`].
+    pub fn named<S: Into<String>>(name: S) -> Self {
+        Self::new(Texture::named(name))
+    }
+}
+
+#[state_component_impl(TextureViewState)]
+impl Component for TextureView {
+    fn init(&mut self) {
+        self.state_mut().generation = self.texture.generation();
+    }
+
+    fn render_hash(&self, hasher: &mut ComponentHasher) {
+        self.texture.name().hash(hasher);
+        self.texture.id().hash(hasher);
+        self.state_ref().generation.hash(hasher);
+    }
+
+    fn on_tick(&mut self, _event: &mut Event<event::Tick>) {
+        let generation = self.texture.generation();
+        if generation != self.state_ref().generation {
+            self.state_mut().generation = generation;
+        }
+    }
+
+    fn render(&mut self, context: RenderContext) -> Option<Vec<Renderable>> {
+        let AABB { pos, .. } = context.aabb;
+        let radius = self.style_val("radius").unwrap().f32();
+
+        let instance = TextureInstanceBuilder::default()
+            .texture(self.texture.clone())
+            .pos(pos)
+            .scale(Scale {
+                width: context.aabb.width(),
+                height: context.aabb.height(),
+            })
+            .radius(radius)
+            .generation(self.state_ref().generation)
+            .build()
+            .unwrap();
+
+        Some(vec![Renderable::Texture(
+            renderables::Texture::from_instance_data(instance),
+        )])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::TestHarness;
+    use crate::texture::{TestFrameSource, TextureFormat};
+    use crate::{lay, node, size};
+
+    #[test]
+    fn test_texture() {
+        let texture = Texture::named("test_texture");
+        // Eight bars of 10px each
+        let mut source = TestFrameSource::new(80, 10, TextureFormat::Nv12);
+        source.push_to(&texture).unwrap();
+        assert!(texture
+            .push(80, 10, TextureFormat::Yuyv, &[0; 100])
+            .is_err());
+        assert_eq!(texture.generation(), 1);
+
+        let mut harness = TestHarness::single(|| {
+            node!(TextureView::named("test_texture"), lay![size: [80., 10.]])
+        });
+        let rgb = |pixel: resvg::tiny_skia::PremultipliedColorU8| {
+            [pixel.red(), pixel.green(), pixel.blue()]
+        };
+        let close = |a: [u8; 3], b: [u8; 3]| a.iter().zip(b).all(|(a, b)| a.abs_diff(b) <= 4);
+        let pixel = rgb(harness.render().pixmap().pixel(58, 5).unwrap());
+        assert!(close(pixel, TestFrameSource::BARS[5]));
+
+        // A later frame, with the bars scrolled by two pixels, is pushed from another thread
+        let other = texture.clone();
+        std::thread::spawn(move || {
+            let mut source = TestFrameSource::new(80, 10, TextureFormat::Yuyv);
+            source.next_frame();
+            source.next_frame();
+            source.push_to(&other).unwrap();
+        })
+        .join()
+        .unwrap();
+        harness.tick();
+        let pixel = rgb(harness.render().pixmap().pixel(58, 5).unwrap());
+        assert!(close(pixel, TestFrameSource::BARS[6]));
+        Texture::remove("test_texture");
+    }
+}
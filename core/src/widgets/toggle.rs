@@ -15,7 +15,7 @@ struct ToggleState {
     pressed: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum ToggleType {
     Type1,
     Type2,
@@ -329,3 +329,36 @@ impl Component for Toggle {
         base
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::{HeadlessWindow, TestHarness, TestRootParams};
+    use crate::snapshot::{compare, snapshot};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_toggle_click() {
+        for toggle_type in [ToggleType::Type1, ToggleType::Type2, ToggleType::Type3] {
+            let active = snapshot(100, 50, 2.0, move || {
+                node!(Toggle::new(true).toggle_type(toggle_type))
+            });
+            let differing = |harness: &mut TestHarness<_, _>| {
+                compare(&harness.snapshot(), &active, 2)
+                    .unwrap()
+                    .differing_pixels
+            };
+
+            // Clicking an inactive toggle renders it as active
+            let mut harness = TestHarness::with_window(
+                HeadlessWindow::new(100, 50).scale_factor(2.0),
+                TestRootParams {
+                    view: Arc::new(move || node!(Toggle::new(false).toggle_type(toggle_type))),
+                },
+            );
+            assert!(differing(&mut harness) > 0);
+            harness.click(10.0, 10.0);
+            assert_eq!(differing(&mut harness), 0);
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
     raw_handle::RawWaylandHandle,
-    types::{Data, PixelSize},
+    renderer::RendererType,
+    types::{Data, PixelSize, AABB},
     AssetParams,
 };
 use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle};
@@ -23,6 +24,19 @@ pub trait Window: HasRawWindowHandle + HasRawDisplayHandle + Send + Sync + Any {
     /// Request next frame
     fn next_frame(&self) {}
 
+    /// Which renderer the [`UI`][crate::ui::UI] should draw this window with.
+    fn renderer_type(&self) -> RendererType {
+        RendererType::Canvas
+    }
+
+    /// Called when the [`UI`][crate::ui::UI] has to fall back to a different renderer, e.g. when EGL cannot be initialized.
+    fn set_renderer_type(&mut self, _renderer_type: RendererType) {}
+
+    /// Called with every frame drawn by the [`RendererType::Software`] renderer.
+    /// `data` holds `width * height` premultiplied RGBA8 pixels, row by row.
+    /// `damage` holds the regions, in physical pixels, that changed since the previous frame.
+    fn present(&self, _width: u32, _height: u32, _data: &[u8], _damage: &[AABB]) {}
+
     /// Set the current cursor. Cursor names are backend-specific, but they should support the following:
     /// - "Arrow"
     /// - "None"
@@ -140,6 +140,7 @@ async fn main() -> anyhow::Result<()> {
         height: 480 as u32,
         width: 480 as u32,
         scale_factor: 1.0,
+        ..Default::default()
     };
 
     let (mut app, mut event_loop, ..) = layer_window::LayerWindow::open_blocking::<App, AppParams>(
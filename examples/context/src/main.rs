@@ -143,6 +143,7 @@ fn launch_ui(id: i32) -> anyhow::Result<()> {
         height: 480_u32,
         width: 480_u32,
         scale_factor: 1.0,
+        ..Default::default()
     };
 
     println!("id: {id:?}");
@@ -159,6 +159,7 @@ fn launch_ui(id: i32) -> anyhow::Result<()> {
         height: 300 as u32,
         width: 350 as u32,
         scale_factor: 1.0,
+        ..Default::default()
     };
 
     println!("id: {id:?}");
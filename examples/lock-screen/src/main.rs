@@ -113,6 +113,7 @@ async fn main() -> anyhow::Result<()> {
         height: 480 as u32,
         width: 480 as u32,
         scale_factor: 1.0,
+        ..Default::default()
     };
 
     let (session_lock_tx, session_lock_rx) = calloop::channel::channel();
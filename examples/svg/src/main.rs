@@ -165,6 +165,7 @@ async fn main() -> anyhow::Result<()> {
         height: 480 as u32,
         width: 480 as u32,
         scale_factor: 1.0,
+        ..Default::default()
     };
 
     let window_info = WindowInfo {